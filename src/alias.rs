@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use clap::Command;
+
+/// Split an alias definition into arguments like a shell would, keeping
+/// "double" or 'single' quoted text together.
+pub fn split(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("unclosed quote in \"{}\"", text));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// `args`, the full command line, with an alias from `aliases` in command
+/// position replaced by its definition. Built-in commands always win, and
+/// a definition isn't expanded again.
+pub fn expand(
+    args: Vec<String>,
+    aliases: &HashMap<String, String>,
+    cli: &Command,
+) -> Result<Vec<String>, String> {
+    if aliases.is_empty() {
+        return Ok(args);
+    }
+    // Global options such as --file take the next argument as their value
+    let takes_value = cli
+        .get_arguments()
+        .filter(|a| a.get_action().takes_values())
+        .filter_map(|a| a.get_long())
+        .map(|long| format!("--{}", long))
+        .collect::<Vec<_>>();
+
+    let mut i = 1;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            return Ok(args);
+        }
+        if !arg.starts_with('-') {
+            break;
+        }
+        i += if takes_value.contains(arg) { 2 } else { 1 };
+    }
+    let Some(name) = args.get(i) else {
+        return Ok(args);
+    };
+    let built_in = name == "help"
+        || cli
+            .get_subcommands()
+            .any(|c| c.get_name() == name || c.get_all_aliases().any(|a| a == name));
+    let Some(definition) = aliases.get(name).filter(|_| !built_in) else {
+        return Ok(args);
+    };
+
+    let expansion = split(definition).map_err(|e| format!("in alias '{}': {}", name, e))?;
+    if expansion.is_empty() {
+        return Err(format!("alias '{}' is empty", name));
+    }
+    let mut expanded = args[..i].to_vec();
+    expanded.extend(expansion);
+    expanded.extend_from_slice(&args[i + 1..]);
+    Ok(expanded)
+}
@@ -0,0 +1,79 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::crypt;
+use crate::storage;
+use crate::task::{self, Task};
+
+/// Split the task file into the lines to keep and the task blocks (each task
+/// line with its notes) matching `take`, both in file order.
+pub fn split(lines: &[String], take: impl Fn(&Task) -> bool) -> (Vec<String>, Vec<String>) {
+    let mut kept = Vec::new();
+    let mut taken = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let task = Task::parse(&lines[i]);
+        let block = 1 + task.as_ref().map_or(0, |_| task::notes(lines, i).len());
+        let target = if task.is_some_and(|t| take(&t)) {
+            &mut taken
+        } else {
+            &mut kept
+        };
+        target.extend_from_slice(&lines[i..i + block]);
+        i += block;
+    }
+    (kept, taken)
+}
+
+/// Whether `task` carries `tag` (with or without the leading `#`).
+pub fn has_tag(task: &Task, tag: &str) -> bool {
+    task.tags().contains(&tag.trim_start_matches('#'))
+}
+
+/// Append `lines` under a `## heading` to the markdown file at `path`,
+/// creating it if needed. Existing content is never overwritten. If
+/// `sealed` and encryption is on, or the file is already encrypted, it's
+/// rewritten whole through [`storage::write_text`] instead, so no task
+/// lands in plain text.
+pub fn append(path: &Path, heading: &str, lines: &[String], sealed: bool) -> Result<(), String> {
+    let mut section = format!("## {}\n\n", heading);
+    for line in lines {
+        section.push_str(line);
+        section.push('\n');
+    }
+    let encrypted = fs::read(path).is_ok_and(|b| crypt::tool_of(&b) != crypt::Tool::None);
+    if sealed && (crypt::enabled() || encrypted) {
+        let mut text = if path.exists() {
+            storage::read_text(path)
+                .map_err(|e| format!("error reading {}: {}", path.display(), e))?
+        } else {
+            String::new()
+        };
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&section);
+        return storage::write_text(path, &text)
+            .map_err(|e| format!("error writing {}: {}", path.display(), e));
+    }
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("error creating {}: {}", dir.display(), e))?;
+    }
+    let fresh = !path.exists() || fs::metadata(path).is_ok_and(|m| m.len() == 0);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("error opening {}: {}", path.display(), e))?;
+
+    let mut contents = String::new();
+    if !fresh {
+        contents.push('\n');
+    }
+    contents.push_str(&section);
+    file.write_all(contents.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("error writing {}: {}", path.display(), e))
+}
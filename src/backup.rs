@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::Deserialize;
+
+use crate::storage;
+use crate::task::Task;
+
+/// `[backup]` config: copies of the task file taken before it is rewritten.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    /// Backups kept per task file; 0 keeps any number
+    pub keep: usize,
+    /// Backups older than this many days are removed; 0 keeps them forever
+    pub keep_days: i64,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        BackupConfig {
+            enabled: true,
+            keep: 50,
+            keep_days: 30,
+        }
+    }
+}
+
+/// Directory under the task directory that holds the backups.
+const DIR: &str = "backups";
+
+/// Timestamp in backup names, also what `task restore` takes.
+pub const STAMP: &str = "%Y%m%d-%H%M%S";
+
+static SETTINGS: OnceLock<(PathBuf, BackupConfig)> = OnceLock::new();
+
+/// Task files already backed up by this process. A command that saves
+/// several times only needs the version from before it started.
+static TAKEN: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+pub fn init(task_dir: &Path, config: &BackupConfig) {
+    let _ = SETTINGS.set((task_dir.join(DIR), config.clone()));
+}
+
+/// A backup of a task file.
+pub struct Backup {
+    pub path: PathBuf,
+    pub stamp: String,
+    pub taken: NaiveDateTime,
+}
+
+impl Backup {
+    /// Number of tasks in the backup.
+    pub fn tasks(&self) -> usize {
+        storage::read_text(&self.path).map_or(0, |text| {
+            text.lines().filter(|l| Task::parse(l).is_some()).count()
+        })
+    }
+}
+
+fn stem(task_file: &Path) -> String {
+    task_file
+        .file_stem()
+        .map_or("tasks".to_string(), |s| s.to_string_lossy().into_owned())
+}
+
+/// Backups of `task_file` in `dir`, newest first.
+fn backups_in(dir: &Path, task_file: &Path) -> Vec<Backup> {
+    let prefix = format!("{}-", stem(task_file));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(&prefix)?.strip_suffix(".md")?;
+            let taken = NaiveDateTime::parse_from_str(stamp, STAMP).ok()?;
+            Some(Backup {
+                path: entry.path(),
+                stamp: stamp.to_string(),
+                taken,
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.taken));
+    backups
+}
+
+/// The directory backups are kept in.
+pub fn dir(task_dir: &Path) -> PathBuf {
+    task_dir.join(DIR)
+}
+
+/// Backups of `task_file` under `task_dir`, newest first.
+pub fn list(task_dir: &Path, task_file: &Path) -> Vec<Backup> {
+    backups_in(&dir(task_dir), task_file)
+}
+
+/// The backup of `task_file` taken at `stamp`, or the only one whose stamp
+/// starts with it (`20251015` for that day, if there was just one).
+pub fn find(task_dir: &Path, task_file: &Path, stamp: &str) -> Result<Backup, String> {
+    let mut matching = list(task_dir, task_file)
+        .into_iter()
+        .filter(|b| b.stamp.starts_with(stamp))
+        .collect::<Vec<_>>();
+    if let Some(i) = matching.iter().position(|b| b.stamp == stamp) {
+        return Ok(matching.swap_remove(i));
+    }
+    match matching.len() {
+        0 => Err(format!(
+            "no backup taken at {}; 'task backup list' shows them",
+            stamp
+        )),
+        1 => Ok(matching.remove(0)),
+        n => Err(format!(
+            "{} backups match {}; give the full timestamp",
+            n, stamp
+        )),
+    }
+}
+
+/// Copy `contents`, the version of `task_file` about to be replaced, into
+/// the backup directory, then drop backups beyond the configured retention.
+/// Failures are reported but don't stop the write.
+pub fn take(task_file: &Path, contents: &[u8]) {
+    let Some((dir, config)) = SETTINGS.get() else {
+        return;
+    };
+    if !config.enabled {
+        return;
+    }
+    {
+        let mut taken = TAKEN.lock().unwrap_or_else(|e| e.into_inner());
+        if taken.iter().any(|p| p == task_file) {
+            return;
+        }
+        taken.push(task_file.to_path_buf());
+    }
+
+    let now = Local::now().naive_local();
+    let path = dir.join(format!("{}-{}.md", stem(task_file), now.format(STAMP)));
+    // Another command in the same second already kept the older version
+    if !path.exists() {
+        let written = fs::create_dir_all(dir).and_then(|_| fs::write(&path, contents));
+        if let Err(e) = written {
+            eprintln!("Warning: could not back up the task file: {}", e);
+            return;
+        }
+    }
+
+    let cutoff = now - Duration::days(config.keep_days);
+    for (i, backup) in backups_in(dir, task_file).into_iter().enumerate() {
+        let too_many = config.keep > 0 && i >= config.keep;
+        let too_old = config.keep_days > 0 && backup.taken < cutoff;
+        if too_many || too_old {
+            let _ = fs::remove_file(&backup.path);
+        }
+    }
+}
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::{NaiveDate, Utc};
+
+use crate::config::TaskFormat;
+use crate::recurrence::stable_hash;
+use crate::storage;
+use crate::sync::SyncConfig;
+use crate::task::{Priority, Status, Task};
+
+const QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>
+"#;
+
+/// Identity of a task on both sides: its text and creation date.
+type Key = (String, Option<NaiveDate>);
+
+struct Remote {
+    href: String,
+    uid: String,
+    task: Task,
+}
+
+/// What `sync caldav` did, for the summary line.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub deleted: usize,
+    pub conflicts: usize,
+}
+
+/// Where to sync and as whom, from `[sync]` and the command line.
+pub struct Account {
+    url: String,
+    user: String,
+    password: String,
+}
+
+impl Account {
+    /// Resolve the account from config. The password comes from
+    /// `caldav_password`, or the output of `caldav_password_command` so it
+    /// can live in a keyring (`secret-tool lookup ...`, `pass show ...`).
+    pub fn from_config(sync: &SyncConfig, url: Option<String>) -> Result<Self, String> {
+        let url = url
+            .or(sync.caldav_url.clone())
+            .ok_or("no CalDAV calendar configured; pass its URL or set caldav_url under [sync]")?;
+        let user = sync
+            .caldav_user
+            .clone()
+            .ok_or("set caldav_user under [sync] to sync with CalDAV")?;
+        let password = match (&sync.caldav_password, &sync.caldav_password_command) {
+            (Some(password), _) => password.clone(),
+            (None, Some(command)) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stderr(Stdio::inherit())
+                    .output()
+                    .map_err(|e| format!("could not run caldav_password_command: {}", e))?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "caldav_password_command exited with {}",
+                        output.status
+                    ));
+                }
+                String::from_utf8_lossy(&output.stdout)
+                    .trim_end()
+                    .to_string()
+            }
+            (None, None) => {
+                return Err(
+                    "set caldav_password or caldav_password_command under [sync]".to_string(),
+                );
+            }
+        };
+        Ok(Account {
+            url: format!("{}/", url.trim_end_matches('/')),
+            user,
+            password,
+        })
+    }
+
+    /// Resolve a server `href` (usually an absolute path) against the
+    /// calendar URL.
+    fn resolve(&self, href: &str) -> String {
+        if href.contains("://") {
+            return href.to_string();
+        }
+        match (self.url.find("://"), href.starts_with('/')) {
+            (Some(scheme), true) => {
+                let host_end = self.url[scheme + 3..]
+                    .find('/')
+                    .map_or(self.url.len(), |i| scheme + 3 + i);
+                format!("{}{}", &self.url[..host_end], href)
+            }
+            _ => format!("{}{}", self.url, href),
+        }
+    }
+
+    /// Run one request with curl. Credentials and the body go in on stdin
+    /// rather than the command line, where other users could see them.
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[&str],
+        body: Option<&str>,
+    ) -> Result<String, String> {
+        let mut command = Command::new("curl");
+        command
+            .args(["--silent", "--show-error", "--fail", "--config", "-"])
+            .args(["--request", method]);
+        for header in headers {
+            command.args(["--header", header]);
+        }
+        let mut child = command
+            .arg(url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not run curl: {}", e))?;
+
+        let quote = |s: &str| {
+            s.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+                .replace('\t', "\\t")
+        };
+        let mut stdin = child.stdin.take().ok_or("could not open curl stdin")?;
+        writeln!(
+            stdin,
+            "user = \"{}:{}\"",
+            quote(&self.user),
+            quote(&self.password)
+        )
+        .map_err(|e| format!("error passing credentials to curl: {}", e))?;
+        // The body goes the same way, so no task is written to disk, where
+        // it would be in plain text even with encryption on
+        if let Some(body) = body {
+            writeln!(stdin, "data-binary = \"{}\"", quote(body))
+                .map_err(|e| format!("error passing the request to curl: {}", e))?;
+        }
+        drop(stdin);
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("curl failed: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} {} failed: {}",
+                method,
+                url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+fn base_path(task_dir: &Path, url: &str) -> PathBuf {
+    let name = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    task_dir.join("sync").join(format!("caldav_{}.md", name))
+}
+
+/// The part of a task a VTODO can round-trip. Waiting tasks look pending to
+/// the server, which has no such state.
+fn project(task: &Task) -> Task {
+    let mut shown = Task::new(task.text.clone(), task.due, NaiveDate::MIN);
+    shown.created = task.created;
+    shown.status = match task.status {
+        Status::Waiting => Status::Pending,
+        status => status,
+    };
+    shown.priority = task.priority;
+    shown.start = task.start;
+    shown.done = task.done;
+    shown.cancelled = task.cancelled;
+    shown
+}
+
+fn projected(task: &Task) -> String {
+    project(task).to_line(TaskFormat::Obsidian)
+}
+
+fn ical_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Fold content lines at 75 octets, as RFC 5545 asks.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// The VTODO properties mapped from a task, without DTSTAMP, so two
+/// renderings can be compared.
+fn properties(task: &Task, uid: &str) -> Vec<String> {
+    let mut props = vec![
+        format!("UID:{}", uid),
+        format!("SUMMARY:{}", escape(&task.text)),
+    ];
+    if let Some(created) = task.created {
+        props.push(format!("CREATED:{}T000000Z", ical_date(created)));
+    }
+    if let Some(start) = task.start {
+        props.push(format!("DTSTART;VALUE=DATE:{}", ical_date(start)));
+    }
+    if let Some(due) = task.due {
+        props.push(format!("DUE;VALUE=DATE:{}", ical_date(due)));
+    }
+    let priority = match task.priority {
+        Priority::Highest => 1,
+        Priority::High => 3,
+        Priority::Medium => 5,
+        Priority::Normal => 0,
+        Priority::Low => 7,
+        Priority::Lowest => 9,
+    };
+    if priority > 0 {
+        props.push(format!("PRIORITY:{}", priority));
+    }
+    match task.status {
+        Status::Pending | Status::Waiting | Status::Proposed => {
+            props.push("STATUS:NEEDS-ACTION".to_string())
+        }
+        Status::Done => {
+            props.push("STATUS:COMPLETED".to_string());
+            if let Some(done) = task.done {
+                props.push(format!("COMPLETED:{}T000000Z", ical_date(done)));
+            }
+        }
+        Status::Cancelled => props.push("STATUS:CANCELLED".to_string()),
+    }
+    props
+}
+
+fn vcalendar(task: &Task, uid: &str) -> String {
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//task//EN",
+        "BEGIN:VTODO",
+    ] {
+        out.push_str(&fold(line));
+    }
+    out.push_str(&fold(&format!(
+        "DTSTAMP:{}",
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    )));
+    for prop in properties(task, uid) {
+        out.push_str(&fold(&prop));
+    }
+    out.push_str(&fold("END:VTODO"));
+    out.push_str(&fold("END:VCALENDAR"));
+    out
+}
+
+/// A date or date-time value's date part, e.g. `20261015T090000Z`.
+fn parse_ical_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+/// Read the VTODO in a calendar object into the fields a task keeps,
+/// returning it with its UID.
+fn parse_vtodo(data: &str, today: NaiveDate) -> Option<(String, Task)> {
+    let mut unfolded: Vec<String> = Vec::new();
+    for line in data.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), unfolded.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => unfolded.push(line.to_string()),
+        }
+    }
+
+    let mut in_todo = false;
+    let mut uid = None;
+    let mut task = Task::new(String::new(), None, today);
+    task.created = None;
+    let mut modified = None;
+    for line in &unfolded {
+        match line.as_str() {
+            "BEGIN:VTODO" => in_todo = true,
+            "END:VTODO" => break,
+            _ if !in_todo => {}
+            _ => {
+                let Some((name, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let name = name.split(';').next().unwrap_or_default();
+                match name.to_ascii_uppercase().as_str() {
+                    "UID" => uid = Some(value.to_string()),
+                    "SUMMARY" => task.text = unescape(value).replace('\n', " "),
+                    "CREATED" => task.created = parse_ical_date(value),
+                    "DTSTART" => task.start = parse_ical_date(value),
+                    "DUE" => task.due = parse_ical_date(value),
+                    "COMPLETED" => task.done = parse_ical_date(value),
+                    "LAST-MODIFIED" => modified = parse_ical_date(value),
+                    "PRIORITY" => {
+                        task.priority = match value.trim().parse::<u8>().unwrap_or(0) {
+                            1 | 2 => Priority::Highest,
+                            3 | 4 => Priority::High,
+                            5 => Priority::Medium,
+                            6 | 7 => Priority::Low,
+                            8 | 9 => Priority::Lowest,
+                            _ => Priority::Normal,
+                        }
+                    }
+                    "STATUS" => {
+                        task.status = match value.trim() {
+                            "COMPLETED" => Status::Done,
+                            "CANCELLED" => Status::Cancelled,
+                            _ => Status::Pending,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    if task.text.trim().is_empty() {
+        return None;
+    }
+    match task.status {
+        Status::Done if task.done.is_none() => task.done = Some(modified.unwrap_or(today)),
+        Status::Cancelled => task.cancelled = Some(modified.unwrap_or(today)),
+        Status::Pending => task.done = None,
+        _ => {}
+    }
+    Some((uid?, task))
+}
+
+/// Inner text of every element named `name` in any namespace.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[..end];
+        let tag_name = tag
+            .split([' ', '\t', '\r', '\n', '/'])
+            .next()
+            .unwrap_or_default();
+        let local = tag_name.rsplit(':').next().unwrap_or_default();
+        if local != name || tag.ends_with('/') || tag.starts_with(['/', '?', '!']) {
+            continue;
+        }
+        let inner = &rest[end + 1..];
+        let close = format!("</{}>", tag_name);
+        if let Some(stop) = inner.find(&close) {
+            found.push(&inner[..stop]);
+            rest = &inner[stop + close.len()..];
+        }
+    }
+    found
+}
+
+fn xml_text(text: &str) -> String {
+    let text = text.trim();
+    text.strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .map(String::from)
+        .unwrap_or_else(|| {
+            text.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&#13;", "\r")
+                .replace("&#xD;", "\r")
+                .replace("&amp;", "&")
+        })
+}
+
+fn fetch(account: &Account, today: NaiveDate) -> Result<HashMap<Key, Remote>, String> {
+    let response = account.request(
+        "REPORT",
+        &account.url,
+        &["Depth: 1", "Content-Type: application/xml; charset=utf-8"],
+        Some(QUERY),
+    )?;
+    let mut remote = HashMap::new();
+    for block in elements(&response, "response") {
+        let Some(href) = elements(block, "href").first().map(|h| xml_text(h)) else {
+            continue;
+        };
+        let Some(data) = elements(block, "calendar-data")
+            .first()
+            .map(|d| xml_text(d))
+        else {
+            continue;
+        };
+        if let Some((uid, task)) = parse_vtodo(&data, today) {
+            remote.insert(
+                (task.text.clone(), task.created),
+                Remote { href, uid, task },
+            );
+        }
+    }
+    Ok(remote)
+}
+
+/// Carry the server's changes onto the local task, keeping everything a
+/// VTODO doesn't hold (ids, recurrence, fields).
+fn apply(local: &mut Task, remote: &Task) {
+    if remote.status != project(local).status {
+        local.status = remote.status;
+    }
+    local.priority = remote.priority;
+    local.start = remote.start;
+    local.due = remote.due;
+    local.done = remote.done;
+    local.cancelled = remote.cancelled;
+}
+
+/// Two-way sync of pending tasks with a CalDAV task list. Each side's
+/// changes since the last sync are merged task by task; when both changed
+/// the same task, a completion or cancellation on either side wins, and
+/// otherwise the local edit does. Done and cancelled tasks are only sent
+/// when the server already has them. Returns the lines to write locally.
+pub fn sync(
+    task_dir: &Path,
+    account: &Account,
+    local: &[String],
+    format: TaskFormat,
+    today: NaiveDate,
+) -> Result<(Vec<String>, Summary), String> {
+    let base_file = base_path(task_dir, &account.url);
+    let base: HashMap<Key, String> = storage::read_text(&base_file)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            Task::parse(line).map(|t| ((t.text.clone(), t.created), line.to_string()))
+        })
+        .collect();
+
+    println!("Fetching tasks from {}...", account.url);
+    let mut remote = fetch(account, today)?;
+    let mut summary = Summary::default();
+
+    let mut merged = Vec::new();
+    // What the server holds once this sync is done, for next time
+    let mut synced = Vec::new();
+    let mut pushes: Vec<(Task, Option<(String, String)>)> = Vec::new();
+    for line in local {
+        let Some(mut task) = Task::parse(line) else {
+            merged.push(line.clone());
+            continue;
+        };
+        let key = (task.text.clone(), task.created);
+        let ours = projected(&task);
+        let before = base.get(&key);
+        match remote.remove(&key) {
+            Some(theirs) => {
+                let theirs_line = projected(&theirs.task);
+                let remote_changed = before != Some(&theirs_line);
+                let local_changed = before != Some(&ours);
+                if remote_changed && (!local_changed || theirs.task.status != Status::Pending) {
+                    if local_changed && ours != theirs_line {
+                        summary.conflicts += 1;
+                    }
+                    apply(&mut task, &theirs.task);
+                    summary.pulled += usize::from(projected(&task) != ours);
+                    merged.push(task.to_line(format));
+                    synced.push(theirs_line);
+                    continue;
+                }
+                if remote_changed && local_changed && ours != theirs_line {
+                    summary.conflicts += 1;
+                }
+                if ours != theirs_line {
+                    pushes.push((task, Some((theirs.href, theirs.uid))));
+                }
+                merged.push(line.clone());
+                synced.push(ours);
+            }
+            // Deleted on the server since the last sync
+            None if before == Some(&ours) => summary.deleted += 1,
+            None => {
+                if task.status == Status::Pending || task.status == Status::Waiting {
+                    synced.push(ours);
+                    pushes.push((task, None));
+                }
+                merged.push(line.clone());
+            }
+        }
+    }
+
+    let mut deletes = Vec::new();
+    let mut added = remote.into_iter().collect::<Vec<_>>();
+    added.sort_by(|a, b| a.0.cmp(&b.0));
+    for (key, theirs) in added {
+        match base.get(&key) {
+            // Deleted here, untouched there
+            Some(before) if *before == projected(&theirs.task) => deletes.push(theirs.href),
+            _ => {
+                merged.push(theirs.task.to_line(format));
+                synced.push(projected(&theirs.task));
+                summary.pulled += 1;
+            }
+        }
+    }
+
+    for (task, existing) in &pushes {
+        let (url, uid) = match existing {
+            Some((href, uid)) => (account.resolve(href), uid.clone()),
+            None => {
+                let uid = format!(
+                    "task-{:016x}",
+                    stable_hash(&format!("{:?}|{}", task.created, task.text))
+                );
+                (format!("{}{}.ics", account.url, uid), uid)
+            }
+        };
+        account.request(
+            "PUT",
+            &url,
+            &["Content-Type: text/calendar; charset=utf-8"],
+            Some(&vcalendar(task, &uid)),
+        )?;
+        summary.pushed += 1;
+    }
+    for href in &deletes {
+        account.request("DELETE", &account.resolve(href), &[], None)?;
+        summary.deleted += 1;
+    }
+
+    let state = synced
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+    storage::write_text(&base_file, &state)
+        .map_err(|e| format!("error saving sync state: {}", e))?;
+    Ok((merged, summary))
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use colored::Colorize;
+
+use crate::dates;
+use crate::report::week_start;
+use crate::task::{Priority, Status, Task};
+
+const CELL: usize = 7;
+
+/// Colour a day cell by the most urgent pending task due that day.
+fn paint(cell: &str, priority: Option<Priority>, is_today: bool) -> String {
+    let mut out = match priority {
+        Some(Priority::Highest | Priority::High) => cell.red(),
+        Some(Priority::Medium) => cell.yellow(),
+        Some(Priority::Normal) => cell.normal(),
+        Some(Priority::Low | Priority::Lowest) => cell.blue(),
+        None => cell.dimmed(),
+    };
+    if is_today {
+        out = out.bold().underline();
+    }
+    out.to_string()
+}
+
+/// Print a Monday-first calendar grid of the month containing `anchor`, with
+/// the number of pending tasks due on each day. Past days that still have
+/// tasks due are marked `!` and today `*`, so they stand out without colour.
+pub fn month(tasks: &[Task], anchor: NaiveDate, today: NaiveDate) {
+    let (first, last) = dates::month_bounds(anchor);
+
+    let mut days: HashMap<NaiveDate, (usize, Priority)> = HashMap::new();
+    for task in tasks.iter().filter(|t| t.status == Status::Pending) {
+        let Some(due) = task.due.filter(|d| *d >= first && *d <= last) else {
+            continue;
+        };
+        let day = days.entry(due).or_insert((0, task.priority));
+        day.0 += 1;
+        day.1 = day.1.max(task.priority);
+    }
+
+    println!(
+        "{:^width$}",
+        first.format("%B %Y").to_string(),
+        width = CELL * 7
+    );
+    for name in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"] {
+        print!("{:<width$}", name, width = CELL);
+    }
+    println!();
+
+    let mut date = week_start(first);
+    while date <= last {
+        for _ in 0..7 {
+            if date.month() != first.month() {
+                print!("{:<width$}", "", width = CELL);
+            } else {
+                let entry = days.get(&date);
+                let count = entry.map_or(String::new(), |(n, _)| format!("[{}]", n));
+                let marker = if date == today {
+                    "*"
+                } else if entry.is_some() && date < today {
+                    "!"
+                } else {
+                    ""
+                };
+                let cell = format!(
+                    "{:>2}{:<width$}",
+                    date.day(),
+                    count + marker,
+                    width = CELL - 2
+                );
+                print!("{}", paint(&cell, entry.map(|(_, p)| *p), date == today));
+            }
+            date += Duration::days(1);
+        }
+        println!();
+    }
+
+    let total = days.values().map(|(n, _)| n).sum::<usize>();
+    println!();
+    println!(
+        "{} pending task(s) due this month. [n] = tasks due that day, ! = overdue, * = today; {} / {} / {} = highest priority that day.",
+        total,
+        "high".red(),
+        "medium".yellow(),
+        "low".blue()
+    );
+    let step =
+        |month: Option<NaiveDate>| month.map_or(String::new(), |m| m.format("%Y-%m").to_string());
+    println!(
+        "Previous: task cal {}   Next: task cal {}   Pass a date (task cal YYYY-MM-DD) to list that day's tasks.",
+        step(first.checked_sub_months(Months::new(1))),
+        step(first.checked_add_months(Months::new(1)))
+    );
+}
@@ -0,0 +1,150 @@
+use std::process::{Command, Stdio};
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::dates;
+use crate::task::Priority;
+
+/// A task read from free-form text, as typed or spoken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub text: String,
+    pub due: Option<NaiveDate>,
+    pub priority: Priority,
+}
+
+/// Run the configured speech-to-text command and return what it printed.
+/// It keeps the terminal, so it can show its own prompts while recording.
+pub fn transcribe(command: &str) -> Result<String, String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("could not run voice_command: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("voice_command exited with {}", output.status));
+    }
+    let text = String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        return Err("nothing was transcribed".to_string());
+    }
+    Ok(text)
+}
+
+fn number(word: &str) -> Option<i64> {
+    let words = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        _ => word
+            .parse()
+            .ok()
+            .or_else(|| words.iter().position(|w| *w == word).map(|i| i as i64 + 1)),
+    }
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" => Some(Weekday::Mon),
+        "tuesday" => Some(Weekday::Tue),
+        "wednesday" => Some(Weekday::Wed),
+        "thursday" => Some(Weekday::Thu),
+        "friday" => Some(Weekday::Fri),
+        "saturday" => Some(Weekday::Sat),
+        "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The next `day` after today (a week ahead when it's today).
+fn next(day: Weekday, today: NaiveDate) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+/// A date phrase at the start of `words`, with how many words it took.
+fn date_phrase(words: &[String], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let word = |i: usize| words.get(i).map(String::as_str).unwrap_or_default();
+    match (word(0), word(1), word(2)) {
+        ("today" | "tonight", _, _) => Some((today, 1)),
+        ("tomorrow", _, _) => Some((today + Duration::days(1), 1)),
+        ("day", "after", "tomorrow") => Some((today + Duration::days(2), 3)),
+        ("next", "week", _) => Some((next(Weekday::Mon, today), 2)),
+        ("next", day, _) => weekday(day).map(|d| (next(d, today), 2)),
+        ("in", n, unit) => {
+            let n = number(n)?;
+            let days = match unit.trim_end_matches('s') {
+                "day" => n,
+                "week" => n * 7,
+                _ => return None,
+            };
+            Some((today + Duration::days(days), 3))
+        }
+        (day, _, _) => weekday(day)
+            .map(|d| (next(d, today), 1))
+            .or_else(|| dates::parse_input(day).map(|d| (d, 1))),
+    }
+}
+
+/// Pull a due date, a priority and `#tags` out of natural text such as
+/// "call the dentist tomorrow, high priority, hashtag health". Words that
+/// aren't recognised stay in the task text.
+pub fn parse(input: &str, today: NaiveDate) -> Capture {
+    let raw = input
+        .split_whitespace()
+        .map(|w| w.trim_end_matches([',', '.', '!', '?']).to_string())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+    let lower = raw.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>();
+
+    let mut kept: Vec<String> = Vec::new();
+    let mut due = None;
+    let mut priority = Priority::Normal;
+    let mut i = 0;
+    while i < raw.len() {
+        // "due/by/on friday": the preposition goes with the date
+        let lead = usize::from(matches!(lower[i].as_str(), "due" | "by" | "on"));
+        if due.is_none()
+            && let Some((date, used)) = date_phrase(&lower[i + lead..], today)
+        {
+            due = Some(date);
+            i += lead + used;
+            continue;
+        }
+        match (lower[i].as_str(), lower.get(i + 1).map(String::as_str)) {
+            ("urgent" | "urgently" | "asap", _) => priority = Priority::Highest,
+            (level, Some("priority")) if Priority::from_name(level).is_some() => {
+                priority = Priority::from_name(level).unwrap_or_default();
+                i += 1;
+            }
+            ("hashtag", Some(_)) => {
+                kept.push(format!("#{}", lower[i + 1]));
+                i += 1;
+            }
+            _ => kept.push(raw[i].clone()),
+        }
+        i += 1;
+    }
+
+    // Dictation tends to start with the instruction itself
+    let mut text = kept.join(" ");
+    for filler in ["remind me to ", "add a task to ", "add task ", "i need to "] {
+        if text.to_lowercase().starts_with(filler)
+            && let Some(rest) = text.get(filler.len()..)
+        {
+            text = rest.to_string();
+            break;
+        }
+    }
+    Capture {
+        text,
+        due,
+        priority,
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+
+use crate::nag;
+use crate::task::{Status, Task};
+
+/// Per-status styles from the `[theme]` config table. Each value is a colour
+/// name (`red`, `bright blue`, `#ff8800`) and/or attributes (`bold`, `dimmed`,
+/// `italic`, `underline`, `strikethrough`), e.g. `"bright red bold"`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub pending: String,
+    pub overdue: String,
+    /// Tasks overdue past the `[nag]` threshold
+    pub critical: String,
+    pub today: String,
+    pub done: String,
+    pub cancelled: String,
+    /// Pending tasks waiting on another task
+    pub blocked: String,
+    /// Delegated tasks parked with `task wait`
+    pub waiting: String,
+    /// Tasks from integrations awaiting `task approve`
+    pub proposed: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            pending: String::new(),
+            overdue: "red".to_string(),
+            critical: "bright red bold underline".to_string(),
+            today: "yellow".to_string(),
+            done: "green".to_string(),
+            cancelled: "dimmed strikethrough".to_string(),
+            blocked: "dimmed".to_string(),
+            waiting: "cyan dimmed".to_string(),
+            proposed: "magenta".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Style {
+    color: Option<Color>,
+    attributes: Vec<String>,
+}
+
+impl Style {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut style = Style::default();
+        let mut color_words = Vec::new();
+        for word in spec.split_whitespace() {
+            match word {
+                "bold" | "dimmed" | "dim" | "italic" | "underline" | "strikethrough" => {
+                    style.attributes.push(word.to_string())
+                }
+                _ => color_words.push(word),
+            }
+        }
+        if !color_words.is_empty() {
+            let name = color_words.join(" ");
+            if name != "default" {
+                let color = name
+                    .parse::<Color>()
+                    .map_err(|_| format!("unknown colour '{}' in theme", name))?;
+                style.color = Some(color);
+            }
+        }
+        Ok(style)
+    }
+
+    fn apply(&self, text: &str) -> ColoredString {
+        let mut out = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        for attribute in &self.attributes {
+            out = match attribute.as_str() {
+                "bold" => out.bold(),
+                "dimmed" | "dim" => out.dimmed(),
+                "italic" => out.italic(),
+                "underline" => out.underline(),
+                _ => out.strikethrough(),
+            };
+        }
+        out
+    }
+}
+
+struct Palette {
+    today: NaiveDate,
+    pending: Style,
+    overdue: Style,
+    critical: Style,
+    due_today: Style,
+    done: Style,
+    cancelled: Style,
+    blocked: Style,
+    waiting: Style,
+    proposed: Style,
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+/// Set up colouring for this run. `--no-color`, `NO_COLOR` and non-terminal
+/// output all turn it off.
+pub fn init(theme: &Theme, enabled: bool, today: NaiveDate) -> Result<(), String> {
+    if !enabled {
+        colored::control::set_override(false);
+    }
+    let palette = Palette {
+        today,
+        pending: Style::parse(&theme.pending)?,
+        overdue: Style::parse(&theme.overdue)?,
+        critical: Style::parse(&theme.critical)?,
+        due_today: Style::parse(&theme.today)?,
+        done: Style::parse(&theme.done)?,
+        cancelled: Style::parse(&theme.cancelled)?,
+        blocked: Style::parse(&theme.blocked)?,
+        waiting: Style::parse(&theme.waiting)?,
+        proposed: Style::parse(&theme.proposed)?,
+    };
+    let _ = PALETTE.set(palette);
+    Ok(())
+}
+
+/// Colour `text` according to the status and due date of the task on `line`.
+pub fn paint(line: &str, text: &str) -> String {
+    let (Some(palette), Some(task)) = (PALETTE.get(), Task::parse(line)) else {
+        return text.to_string();
+    };
+    let style = match task.status {
+        Status::Done => &palette.done,
+        Status::Cancelled => &palette.cancelled,
+        Status::Waiting => &palette.waiting,
+        Status::Proposed => &palette.proposed,
+        Status::Pending if nag::is_critical(&task) => &palette.critical,
+        Status::Pending => match task.due {
+            Some(due) if due < palette.today => &palette.overdue,
+            Some(due) if due == palette.today => &palette.due_today,
+            _ => &palette.pending,
+        },
+    };
+    style.apply(text).to_string()
+}
+
+/// Style `text` for a task blocked by a pending dependency.
+pub fn blocked(text: &str) -> String {
+    match PALETTE.get() {
+        Some(palette) => palette.blocked.apply(text).to_string(),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::backup::BackupConfig;
+use crate::color::Theme;
+use crate::crypt::EncryptionConfig;
+use crate::ids::Strategy;
+use crate::nag::NagConfig;
+use crate::notify::NotifyConfig;
+use crate::proposals::ProposalConfig;
+use crate::serve::ServeConfig;
+use crate::storage;
+use crate::sync::SyncConfig;
+use crate::task::{Status, Task};
+use crate::urgency::UrgencyConfig;
+
+/// How new and modified task lines are written back to the file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskFormat {
+    /// Obsidian Tasks layout: description first, `➕` for the creation date
+    #[default]
+    Obsidian,
+    /// Original layout: `📅 due 📋 created` before the description
+    Legacy,
+    /// Obsidian Tasks' Dataview layout: `[due:: 2025-10-01]` inline fields
+    /// instead of emoji, for Dataview queries
+    #[value(alias = "obsidian-dataview")]
+    Dataview,
+}
+
+/// How date-filtered views (`today`, `week`, `due`, `month`) treat pending
+/// tasks without a due date.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Undated {
+    /// Leave them out
+    #[default]
+    Exclude,
+    /// List them in a separate "No date" section
+    Section,
+    /// Treat them as due today
+    Today,
+}
+
+impl Undated {
+    /// The due date a view should file `task` under.
+    pub fn due(self, task: &Task, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Undated::Today if task.status == Status::Pending => task.due.or(Some(today)),
+            _ => task.due,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub format: TaskFormat,
+    pub theme: Theme,
+    pub nag: NagConfig,
+    pub urgency: UrgencyConfig,
+    pub notify: NotifyConfig,
+    pub sync: SyncConfig,
+    pub proposals: ProposalConfig,
+    pub serve: ServeConfig,
+    pub backup: BackupConfig,
+    pub encryption: EncryptionConfig,
+    pub undated: Undated,
+    /// How `🆔` ids are generated for new dependencies
+    pub id_strategy: Strategy,
+    /// strftime format for typing and showing dates, e.g. `%d/%m/%Y`
+    pub date_format: String,
+    /// Show weekday names next to dates in listings
+    pub weekday_names: bool,
+    /// Show ISO week numbers next to dates in listings
+    pub week_numbers: bool,
+    /// Print the banner and emoji legend before every command, not just
+    /// the help screen
+    pub banner: bool,
+    /// List overdue tasks above the week in `task week`
+    pub week_overdue: bool,
+    /// Add tasks under a `## YYYY-MM-DD` heading for the day, as in a
+    /// daily note, starting it when it's the day's first task
+    pub journal: bool,
+    /// Where `task snooze` moves a task by default: `tomorrow`, a weekday
+    /// such as `monday`, or an offset like `+1w`
+    pub snooze: String,
+    /// Hours of estimated work a day can take before `task week --workload`
+    /// flags it
+    pub workday_hours: i64,
+    /// Speech-to-text command for `task capture --voice`; prints the
+    /// transcription on stdout
+    pub voice_command: Option<String>,
+    /// Command listings taller than the terminal are shown through instead
+    /// of `$PAGER`; empty turns paging off
+    pub pager: Option<String>,
+    /// Command shortcuts, e.g. `alias.standup = "report weekly --markdown"`
+    pub alias: HashMap<String, String>,
+    /// Seconds to wait for another `task` process to release the file
+    pub lock_timeout: u64,
+    /// Task files at least this many KiB are updated in place from the
+    /// first changed line instead of rewritten; 0 turns this off
+    pub partial_writes_kb: u64,
+    /// Task files updated in place are rewritten whole after this many
+    /// in-place updates; 0 turns this off
+    pub compact_every: u64,
+    /// Refuse to write a change that removes more than this percentage of
+    /// the task file's lines (archive, delete and restore excepted); 0
+    /// turns this off
+    pub max_removed_percent: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            format: TaskFormat::default(),
+            theme: Theme::default(),
+            nag: NagConfig::default(),
+            urgency: UrgencyConfig::default(),
+            notify: NotifyConfig::default(),
+            sync: SyncConfig::default(),
+            proposals: ProposalConfig::default(),
+            serve: ServeConfig::default(),
+            backup: BackupConfig::default(),
+            encryption: EncryptionConfig::default(),
+            undated: Undated::default(),
+            id_strategy: Strategy::default(),
+            date_format: "%Y-%m-%d".to_string(),
+            weekday_names: false,
+            week_numbers: false,
+            banner: false,
+            week_overdue: true,
+            journal: false,
+            workday_hours: 8,
+            snooze: "tomorrow".to_string(),
+            voice_command: None,
+            pager: None,
+            alias: HashMap::new(),
+            lock_timeout: 10,
+            partial_writes_kb: 256,
+            compact_every: 100,
+            max_removed_percent: 50,
+        }
+    }
+}
+
+impl Config {
+    /// Load `config.toml` from where [`storage::config_file`] finds it. A
+    /// missing file simply means defaults.
+    pub fn load(task_dir: &Path) -> Self {
+        let path = storage::config_file(task_dir);
+
+        if !path.exists() {
+            return Config::default();
+        }
+
+        let contents = fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("Error reading config file at {}: {}", path.display(), e);
+            std::process::exit(1);
+        });
+        toml::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Error parsing config file at {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::task::{self, Priority, Task};
+
+const STATE_FILE: &str = "contexts";
+
+/// One condition of a filter.
+#[derive(Debug, Clone)]
+enum Term {
+    Tag(String),
+    NotTag(String),
+    Priority(Priority),
+    Text(String),
+}
+
+impl Term {
+    fn parse(word: &str) -> Result<Term, String> {
+        if let Some(tag) = word.strip_prefix('+').filter(|t| !t.is_empty()) {
+            return Ok(Term::Tag(tag.trim_start_matches('#').to_lowercase()));
+        }
+        if let Some(tag) = word.strip_prefix('-').filter(|t| !t.is_empty()) {
+            return Ok(Term::NotTag(tag.trim_start_matches('#').to_lowercase()));
+        }
+        if let Some(name) = word
+            .strip_prefix("priority:")
+            .or_else(|| word.strip_prefix("pri:"))
+        {
+            return Priority::from_name(name)
+                .map(Term::Priority)
+                .ok_or_else(|| format!("unknown priority '{}'", name));
+        }
+        Ok(Term::Text(word.to_lowercase()))
+    }
+
+    fn matches(&self, task: &Task) -> bool {
+        let has_tag = |tag: &str| task.tags().iter().any(|t| t.to_lowercase() == tag);
+        match self {
+            Term::Tag(tag) => has_tag(tag),
+            Term::NotTag(tag) => !has_tag(tag),
+            Term::Priority(priority) => task.priority == *priority,
+            Term::Text(text) => task.text.to_lowercase().contains(text),
+        }
+    }
+}
+
+/// A context's filter: groups of terms joined by `or`, where every term in
+/// a group must match, as in `+work or +office -someday`.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    groups: Vec<Vec<Term>>,
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Filter, String> {
+        let mut groups = vec![Vec::new()];
+        for word in text.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                "or" => groups.push(Vec::new()),
+                "and" => {}
+                _ => groups.last_mut().unwrap().push(Term::parse(word)?),
+            }
+        }
+        if groups.iter().any(Vec::is_empty) {
+            return Err(format!("'{}' has an empty condition around 'or'", text));
+        }
+        Ok(Filter { groups })
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.groups
+            .iter()
+            .any(|group| group.iter().all(|term| term.matches(task)))
+    }
+}
+
+/// A named filter defined with `task context define`.
+pub struct Context {
+    pub name: String,
+    pub filter: String,
+    pub active: bool,
+}
+
+/// The contexts saved in the task directory, one `name: filter` per line,
+/// with `*` before the active one.
+pub fn load(task_dir: &Path) -> Vec<Context> {
+    let Ok(state) = fs::read_to_string(task_dir.join(STATE_FILE)) else {
+        return Vec::new();
+    };
+    state
+        .lines()
+        .filter_map(|line| {
+            let (active, line) = match line.strip_prefix("* ") {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (name, filter) = line.split_once(':')?;
+            Some(Context {
+                name: name.trim().to_string(),
+                filter: filter.trim().to_string(),
+                active,
+            })
+        })
+        .collect()
+}
+
+pub fn save(task_dir: &Path, contexts: &[Context]) -> Result<(), String> {
+    let contents = contexts
+        .iter()
+        .map(|c| {
+            let marker = if c.active { "* " } else { "" };
+            format!("{}{}: {}\n", marker, c.name, c.filter)
+        })
+        .collect::<String>();
+    fs::create_dir_all(task_dir).map_err(|e| format!("error creating task directory: {}", e))?;
+    fs::write(task_dir.join(STATE_FILE), contents)
+        .map_err(|e| format!("error saving contexts: {}", e))
+}
+
+/// The active context's filter, which hides non-matching tasks from task
+/// numbering and listings for the rest of the process.
+static ACTIVE: OnceLock<Filter> = OnceLock::new();
+
+pub fn init(task_dir: &Path) -> Result<Option<Context>, String> {
+    let Some(context) = load(task_dir).into_iter().find(|c| c.active) else {
+        return Ok(None);
+    };
+    let filter = Filter::parse(&context.filter)
+        .map_err(|e| format!("in context '{}': {}", context.name, e))?;
+    let _ = ACTIVE.set(filter);
+    Ok(Some(context))
+}
+
+/// Whether `task` is shown under the active context, if any.
+pub fn shows(task: &Task) -> bool {
+    ACTIVE.get().is_none_or(|filter| filter.matches(task))
+}
+
+/// `lines` with the tasks the active context hides, and their notes,
+/// blanked out, so line numbers stay as they are in the file.
+pub fn view(mut lines: Vec<String>) -> Vec<String> {
+    if ACTIVE.get().is_none() {
+        return lines;
+    }
+    let mut i = 0;
+    while i < lines.len() {
+        let hidden = Task::parse(&lines[i]).filter(|t| !shows(t));
+        let block = 1 + hidden.as_ref().map_or(0, |_| task::notes(&lines, i).len());
+        if hidden.is_some() {
+            lines[i..i + block].iter_mut().for_each(String::clear);
+        }
+        i += block;
+    }
+    lines
+}
@@ -0,0 +1,157 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+
+use serde::Deserialize;
+
+/// Program the task file is encrypted with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tool {
+    /// Plain text
+    #[default]
+    None,
+    Age,
+    Gpg,
+}
+
+/// `[encryption]` config: keep the task file encrypted at rest, decrypting
+/// it in memory for each command.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    pub tool: Tool,
+    /// age public keys (`age1…`) or GPG key ids and emails to encrypt to
+    pub recipients: Vec<String>,
+    /// age identity file to decrypt with; GPG asks its agent instead
+    pub identity: Option<String>,
+}
+
+// How each tool's armored output starts, which is how encrypted files are
+// told apart from plain ones
+const AGE_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const GPG_HEADER: &str = "-----BEGIN PGP MESSAGE-----";
+
+static CONFIG: OnceLock<EncryptionConfig> = OnceLock::new();
+
+pub fn init(config: &EncryptionConfig) -> Result<(), String> {
+    if config.tool != Tool::None && config.recipients.is_empty() {
+        return Err("[encryption] needs at least one key in recipients".to_string());
+    }
+    let _ = CONFIG.set(config.clone());
+    Ok(())
+}
+
+/// Whether task files are written encrypted.
+pub fn enabled() -> bool {
+    CONFIG.get().is_some_and(|c| c.tool != Tool::None)
+}
+
+/// `~/` at the start of `path` as the home directory.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+/// Run `program` with `input` on stdin, returning what it printed.
+fn run(program: &str, args: &[String], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run {} (is it installed?): {}", program, e))?;
+
+    // Fed from another thread, so a large file can't fill the output pipe
+    // while we're still writing
+    let mut stdin = child.stdin.take().ok_or("could not open stdin")?;
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("{} failed: {}", program, e))?;
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// The tool a task file's `bytes` were encrypted with, if any.
+pub fn tool_of(bytes: &[u8]) -> Tool {
+    if bytes.starts_with(AGE_HEADER.as_bytes()) {
+        Tool::Age
+    } else if bytes.starts_with(GPG_HEADER.as_bytes()) {
+        Tool::Gpg
+    } else {
+        Tool::None
+    }
+}
+
+/// The text of a task file's `bytes`, decrypted if they were encrypted with
+/// either tool, whatever the config says.
+pub fn open(bytes: Vec<u8>) -> Result<String, String> {
+    let bytes = if tool_of(&bytes) == Tool::Age {
+        let identity = CONFIG
+            .get()
+            .and_then(|c| c.identity.as_deref())
+            .ok_or("the task file is encrypted with age; set identity under [encryption]")?;
+        let args = ["--decrypt", "--identity", &expand_home(identity)].map(String::from);
+        run("age", &args, &bytes)?
+    } else if tool_of(&bytes) == Tool::Gpg {
+        run(
+            "gpg",
+            &["--batch", "--quiet", "--decrypt"].map(String::from),
+            &bytes,
+        )?
+    } else {
+        bytes
+    };
+    String::from_utf8(bytes).map_err(|_| "the task file isn't valid UTF-8".to_string())
+}
+
+/// `text` as it should be written: encrypted to the recipients if
+/// encryption is on, otherwise as it is.
+pub fn seal(text: &str) -> Result<Vec<u8>, String> {
+    let Some(config) = CONFIG.get() else {
+        return Ok(text.as_bytes().to_vec());
+    };
+    let mut args = Vec::new();
+    let program = match config.tool {
+        Tool::None => return Ok(text.as_bytes().to_vec()),
+        Tool::Age => {
+            args.push("--armor".to_string());
+            "age"
+        }
+        Tool::Gpg => {
+            // The recipients are the user's own choice, so don't ask
+            // whether to trust them
+            args.extend(
+                [
+                    "--batch",
+                    "--quiet",
+                    "--yes",
+                    "--armor",
+                    "--trust-model",
+                    "always",
+                    "--encrypt",
+                ]
+                .map(String::from),
+            );
+            "gpg"
+        }
+    };
+    for recipient in &config.recipients {
+        args.push("--recipient".to_string());
+        args.push(recipient.clone());
+    }
+    run(program, &args, text.as_bytes())
+}
@@ -0,0 +1,349 @@
+use std::env;
+use std::sync::OnceLock;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveTime, Weekday};
+
+use crate::task::parse_date;
+
+/// How dates are typed and shown, from `date_format`, `weekday_names` and
+/// `week_numbers` in the config. The task file always stores ISO dates.
+struct Style {
+    format: String,
+    weekday: bool,
+    week: bool,
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+const ISO: &str = "%Y-%m-%d";
+
+/// Set by `task as-of` on the command it replays.
+pub const AS_OF_VAR: &str = "TASK_AS_OF";
+
+/// Today's date, or the past day `task as-of` is replaying a command on.
+pub fn today() -> NaiveDate {
+    env::var(AS_OF_VAR)
+        .ok()
+        .and_then(|d| parse_date(&d))
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+pub fn init(format: &str, weekday: bool, week: bool) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid date_format '{}'", format));
+    }
+    let _ = STYLE.set(Style {
+        format: format.to_string(),
+        weekday,
+        week,
+    });
+    Ok(())
+}
+
+fn format() -> &'static str {
+    STYLE.get().map_or(ISO, |s| s.format.as_str())
+}
+
+/// `date` in the configured format.
+pub fn show(date: NaiveDate) -> String {
+    date.format(format()).to_string()
+}
+
+/// Parse a date typed on the command line: ISO always works, as does the
+/// configured format.
+pub fn parse_input(s: &str) -> Option<NaiveDate> {
+    parse_date(s).or_else(|| NaiveDate::parse_from_str(s, format()).ok())
+}
+
+/// A time of day, as `14:00`.
+pub fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M").ok()
+}
+
+/// A due date typed on the command line, optionally with a time of day
+/// after a `T`, as in `2025-09-15T14:00`. Dates are read with `date`.
+pub fn parse_due(
+    s: &str,
+    date: fn(&str) -> Option<NaiveDate>,
+) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    match s.split_once('T') {
+        Some((day, time)) => Some((date(day)?, Some(parse_time(time)?))),
+        None => Some((date(s)?, None)),
+    }
+}
+
+/// What `parse_input` accepts, for error messages.
+pub fn expected() -> String {
+    match format() {
+        ISO => "YYYY-MM-DD".to_string(),
+        custom => format!("YYYY-MM-DD or {}", custom),
+    }
+}
+
+/// A date in list output: the configured format, optionally with the
+/// weekday name and ISO week number.
+fn listed(date: NaiveDate) -> String {
+    let mut out = show(date);
+    if let Some(style) = STYLE.get() {
+        if style.weekday {
+            out = format!("{} {}", date.format("%a"), out);
+        }
+        if style.week {
+            out = format!("{} (W{:02})", out, date.iso_week().week());
+        }
+    }
+    out
+}
+
+/// Rewrite the ISO dates in a task line for display.
+pub fn localize(line: &str) -> String {
+    if STYLE
+        .get()
+        .is_none_or(|s| s.format == ISO && !s.weekday && !s.week)
+    {
+        return line.to_string();
+    }
+    let bytes = line.as_bytes();
+    let digit = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let mut out = String::new();
+    let (mut last, mut i) = (0, 0);
+    while i + 10 <= bytes.len() {
+        let shape = (0..10).all(|k| match k {
+            4 | 7 => bytes[i + k] == b'-',
+            _ => digit(i + k),
+        });
+        let bounded = (i == 0 || !digit(i - 1)) && !digit(i + 10);
+        if let Some(date) = (shape && bounded)
+            .then(|| parse_date(&line[i..i + 10]))
+            .flatten()
+        {
+            out.push_str(&line[last..i]);
+            out.push_str(&listed(date));
+            i += 10;
+            last = i;
+            continue;
+        }
+        i += 1;
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+/// A relative span such as `14d`, `2w`, `1m` or `1y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Span {
+    Days(i64),
+    Months(u32),
+}
+
+impl Span {
+    /// The day this span after `date`, or an error past the last date
+    /// there can be.
+    pub fn after(self, date: NaiveDate) -> Result<NaiveDate, String> {
+        match self {
+            Span::Days(n) => Duration::try_days(n).and_then(|d| date.checked_add_signed(d)),
+            Span::Months(n) => date.checked_add_months(Months::new(n)),
+        }
+        .ok_or_else(|| "span out of range".to_string())
+    }
+}
+
+pub fn parse_span(s: &str) -> Result<Span, String> {
+    let err = || format!("invalid span '{}' (expected e.g. 14d, 2w, 1m or 1y)", s);
+    let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(err)?;
+    let (num, unit) = s.split_at(split);
+    let n = num.parse::<u32>().map_err(|_| err())?;
+    match unit {
+        "d" | "day" | "days" => Ok(Span::Days(n.into())),
+        "w" | "week" | "weeks" => Ok(Span::Days(i64::from(n) * 7)),
+        "m" | "month" | "months" => Ok(Span::Months(n)),
+        "y" | "year" | "years" => n.checked_mul(12).map(Span::Months).ok_or_else(err),
+        _ => Err(err()),
+    }
+}
+
+/// Parse a forward offset like `+2d` or `+1w`; the leading `+` is optional.
+pub fn parse_offset(s: &str) -> Result<Span, String> {
+    parse_span(s.strip_prefix('+').unwrap_or(s))
+}
+
+/// Where `task snooze` moves a due date of `from`: `tomorrow`, the next
+/// weekday by name (`monday`), or an offset such as `+1w`.
+pub fn snooze_until(rule: &str, from: NaiveDate) -> Result<NaiveDate, String> {
+    let offset = if rule == "tomorrow" {
+        Span::Days(1)
+    } else if let Ok(day) = rule.parse::<Weekday>() {
+        let ahead = (day.num_days_from_monday() + 6 - from.weekday().num_days_from_monday()) % 7;
+        Span::Days(ahead as i64 + 1)
+    } else {
+        parse_offset(rule).map_err(|_| {
+            format!(
+                "invalid snooze '{}' (expected tomorrow, a weekday such as monday, or an offset like +1w)",
+                rule
+            )
+        })?
+    };
+    offset.after(from)
+}
+
+/// Parse a time estimate like `5m`, `1h`, `1h30m` or a bare number of
+/// minutes, returning minutes.
+pub fn parse_estimate(s: &str) -> Result<i64, String> {
+    let err = || format!("invalid estimate '{}' (expected e.g. 5m, 1h or 1h30m)", s);
+    // Digits only, so no sign gets through as in `-30` or `1h-5m`
+    let number = |n: &str| {
+        n.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| n.parse::<i64>().ok())
+            .flatten()
+    };
+    if let Some(minutes) = number(s) {
+        return Ok(minutes);
+    }
+    let (hours, rest) = match s.split_once('h') {
+        Some((h, rest)) => (number(h).ok_or_else(err)?, rest),
+        None if s.is_empty() => return Err(err()),
+        None => (0, s),
+    };
+    let minutes = match rest {
+        "" => 0,
+        m => m.strip_suffix('m').and_then(number).ok_or_else(err)?,
+    };
+    hours
+        .checked_mul(60)
+        .and_then(|h| h.checked_add(minutes))
+        .ok_or_else(err)
+}
+
+/// Write minutes back in the form `parse_estimate` reads, e.g. `1h30m`.
+pub fn format_estimate(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
+    }
+}
+
+/// First and last day of the month containing `date`.
+pub fn month_bounds(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = date.with_day(1).unwrap_or(date);
+    let last = first
+        .checked_add_months(Months::new(1))
+        .map_or(first, |next| next - Duration::days(1));
+    (first, last)
+}
+
+/// Resolve a month shortcut (`this`, `next`, `last` or `YYYY-MM`) relative
+/// to `today` into its first and last day.
+pub fn parse_month(s: &str, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), String> {
+    let anchor = match s {
+        "this" => Some(today),
+        "next" => today.checked_add_months(Months::new(1)),
+        "last" => today.checked_sub_months(Months::new(1)),
+        _ => NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d").ok(),
+    };
+    anchor.map(month_bounds).ok_or_else(|| {
+        format!(
+            "invalid month '{}' (expected this, next, last or YYYY-MM)",
+            s
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    #[test]
+    fn month_bounds_handle_leap_years() {
+        assert_eq!(
+            month_bounds(date("2024-02-10")),
+            (date("2024-02-01"), date("2024-02-29"))
+        );
+        assert_eq!(
+            month_bounds(date("2025-02-10")),
+            (date("2025-02-01"), date("2025-02-28"))
+        );
+        assert_eq!(
+            parse_month("last", date("2025-03-31")),
+            Ok((date("2025-02-01"), date("2025-02-28")))
+        );
+        assert!(parse_month("2025-13", date("2025-03-31")).is_err());
+    }
+
+    #[test]
+    fn spans_clamp_to_the_end_of_the_month() {
+        assert_eq!(parse_span("2w"), Ok(Span::Days(14)));
+        assert_eq!(parse_span("1y"), Ok(Span::Months(12)));
+        assert_eq!(
+            parse_span("1m").unwrap().after(date("2025-01-31")),
+            Ok(date("2025-02-28"))
+        );
+        assert_eq!(
+            parse_offset("+1y").unwrap().after(date("2024-02-29")),
+            Ok(date("2025-02-28"))
+        );
+        assert!(parse_span("3 days").is_err());
+        assert!(parse_span("d").is_err());
+    }
+
+    #[test]
+    fn huge_spans_are_errors_not_overflows() {
+        assert!(parse_span("4294967295y").is_err());
+        assert!(parse_span("4294967296d").is_err());
+        let weeks = parse_span("4294967295w").unwrap();
+        assert_eq!(weeks, Span::Days(4294967295 * 7));
+        assert!(weeks.after(date("2025-09-15")).is_err());
+        assert!(
+            parse_span("357913941y")
+                .unwrap()
+                .after(date("2025-09-15"))
+                .is_err()
+        );
+        assert!(Span::Days(1).after(NaiveDate::MAX).is_err());
+        assert!(snooze_until("tomorrow", NaiveDate::MAX).is_err());
+    }
+
+    #[test]
+    fn snooze_to_a_weekday_is_always_ahead() {
+        // 2025-09-15 is a Monday
+        let monday = date("2025-09-15");
+        assert_eq!(snooze_until("tomorrow", monday), Ok(date("2025-09-16")));
+        assert_eq!(snooze_until("monday", monday), Ok(date("2025-09-22")));
+        assert_eq!(snooze_until("friday", monday), Ok(date("2025-09-19")));
+        assert!(snooze_until("someday", monday).is_err());
+    }
+
+    #[test]
+    fn estimates_round_trip() {
+        for (text, minutes) in [("45", 45), ("5m", 5), ("1h", 60), ("1h30m", 90)] {
+            assert_eq!(parse_estimate(text), Ok(minutes));
+        }
+        for minutes in [5, 60, 90] {
+            assert_eq!(parse_estimate(&format_estimate(minutes)), Ok(minutes));
+        }
+        assert!(parse_estimate("1.5h").is_err());
+    }
+
+    #[test]
+    fn negative_and_overflowing_estimates_are_rejected() {
+        for text in [
+            "",
+            "-30",
+            "-1h",
+            "1h-5m",
+            "+5",
+            "h",
+            "m",
+            "153722867280912931h",
+        ] {
+            assert!(parse_estimate(text).is_err(), "{}", text);
+        }
+        assert_eq!(parse_estimate("0m"), Ok(0));
+    }
+}
@@ -0,0 +1,33 @@
+use crate::task::{Status, Task};
+
+/// Whether `task` waits on a `🆔` that still belongs to an open task.
+/// Like Obsidian Tasks, cancelled and unknown dependencies don't block.
+pub fn is_blocked(task: &Task, tasks: &[Task]) -> bool {
+    task.status == Status::Pending && !blockers(task, tasks).is_empty()
+}
+
+/// The pending or delegated tasks `task` is waiting on.
+pub fn blockers<'a>(task: &Task, tasks: &'a [Task]) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|t| matches!(t.status, Status::Pending | Status::Waiting))
+        .filter(|t| t.id.as_ref().is_some_and(|id| task.depends_on.contains(id)))
+        .collect()
+}
+
+/// Print the chain of pending tasks blocking `task`, indented one level per
+/// step. Cycles are cut off where they repeat.
+pub fn print_chain(task: &Task, tasks: &[Task], depth: usize, seen: &mut Vec<String>) {
+    for blocker in blockers(task, tasks) {
+        let id = blocker.id.clone().unwrap_or_default();
+        let indent = "    ".repeat(depth);
+        if seen.contains(&id) {
+            println!("{}⛔ {} (cycle)", indent, blocker.text);
+            continue;
+        }
+        println!("{}⛔ waits on: {}", indent, blocker.text);
+        seen.push(id);
+        print_chain(blocker, tasks, depth + 1, seen);
+        seen.pop();
+    }
+}
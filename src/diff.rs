@@ -0,0 +1,135 @@
+use colored::Colorize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Whether a command prints a diff of the lines it rewrites: on for bulk
+/// operations unless `--no-diff`, always with `--show-diff`.
+#[derive(Debug, Clone, Copy)]
+pub struct Mode {
+    always: bool,
+    never: bool,
+}
+
+impl Mode {
+    pub fn new(show_diff: bool, no_diff: bool) -> Self {
+        Mode {
+            always: show_diff,
+            never: no_diff,
+        }
+    }
+
+    pub fn wanted(self, bulk: bool) -> bool {
+        !self.never && (self.always || bulk)
+    }
+}
+
+/// Shortest edit script turning `a` into `b` (Myers' algorithm), as
+/// `(op, index in a, index in b)` in file order.
+fn edits(a: &[String], b: &[String]) -> Vec<(Op, usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let offset = n + m + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=n + m {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let down =
+                k == -d || (k != d && v[(k - 1 + offset) as usize] < v[(k + 1 + offset) as usize]);
+            let mut x = if down {
+                v[(k + 1 + offset) as usize]
+            } else {
+                v[(k - 1 + offset) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(k + offset) as usize] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let down =
+            k == -d || (k != d && v[(k - 1 + offset) as usize] < v[(k + 1 + offset) as usize]);
+        let prev_k = if down { k + 1 } else { k - 1 };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            ops.push((Op::Same, x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push((Op::Added, x as usize, (y - 1) as usize));
+            } else {
+                ops.push((Op::Removed, (x - 1) as usize, y as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// Print a unified diff of the changed lines only, with `@@` hunk headers
+/// giving 1-based line numbers in the old and new file.
+pub fn print(before: &[String], after: &[String]) {
+    // Most commands touch a handful of lines; skip the shared ends
+    let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &before[prefix..before.len() - suffix];
+    let b = &after[prefix..after.len() - suffix];
+
+    let ops = edits(a, b);
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i].0 == Op::Same {
+            i += 1;
+            continue;
+        }
+        let end = ops[i..]
+            .iter()
+            .position(|(op, _, _)| *op == Op::Same)
+            .map_or(ops.len(), |p| i + p);
+        let hunk = &ops[i..end];
+        let removed = hunk.iter().filter(|(op, _, _)| *op == Op::Removed).count();
+        let added = hunk.len() - removed;
+        // Unified diffs number an empty side by the line before it
+        let (_, old, new) = hunk[0];
+        let old_start = prefix + old + usize::from(removed > 0);
+        let new_start = prefix + new + usize::from(added > 0);
+        println!(
+            "{}",
+            format!("@@ -{},{} +{},{} @@", old_start, removed, new_start, added).cyan()
+        );
+        for &(_, old, _) in hunk.iter().filter(|(op, _, _)| *op == Op::Removed) {
+            println!("{}", format!("-{}", a[old]).red());
+        }
+        for &(_, _, new) in hunk.iter().filter(|(op, _, _)| *op == Op::Added) {
+            println!("{}", format!("+{}", b[new]).green());
+        }
+        i = end;
+    }
+}
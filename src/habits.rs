@@ -0,0 +1,142 @@
+use chrono::{Duration, NaiveDate};
+
+use crate::recurrence::next_occurrence;
+use crate::task::{Status, Task};
+
+/// A recurring task followed across all its occurrences.
+struct Habit {
+    text: String,
+    rule: String,
+    /// Days an occurrence was completed, oldest first
+    done: Vec<NaiveDate>,
+    /// Days an occurrence was cancelled instead, oldest first
+    skipped: Vec<NaiveDate>,
+}
+
+impl Habit {
+    /// The current and longest streaks: completions each no more than one
+    /// period after the one before, unbroken by a cancelled occurrence. The
+    /// current one lasts until a period has passed since the last completion.
+    fn streaks(&self, today: NaiveDate) -> (usize, usize) {
+        let mut events = self
+            .done
+            .iter()
+            .map(|&d| (d, true))
+            .chain(self.skipped.iter().map(|&d| (d, false)))
+            .collect::<Vec<_>>();
+        events.sort();
+        let (mut run, mut longest) = (0, 0);
+        let mut last: Option<NaiveDate> = None;
+        for (date, done) in events {
+            if !done {
+                run = 0;
+                last = None;
+                continue;
+            }
+            match last {
+                // Done twice in a day counts once
+                Some(prev) if prev == date => continue,
+                Some(prev) if next_occurrence(&self.rule, prev).is_some_and(|n| date <= n) => {
+                    run += 1
+                }
+                _ => run = 1,
+            }
+            longest = longest.max(run);
+            last = Some(date);
+        }
+        let alive =
+            last.is_some_and(|l| next_occurrence(&self.rule, l).is_some_and(|n| today <= n));
+        (if alive { run } else { 0 }, longest)
+    }
+
+    /// One character per day from `from` to `today`: `■` done, `×`
+    /// cancelled, `·` neither.
+    fn strip(&self, from: NaiveDate, today: NaiveDate) -> String {
+        from.iter_days()
+            .take_while(|d| *d <= today)
+            .map(|d| {
+                if self.done.contains(&d) {
+                    '■'
+                } else if self.skipped.contains(&d) {
+                    '×'
+                } else {
+                    '·'
+                }
+            })
+            .collect()
+    }
+}
+
+/// The recurring tasks among `tasks`, each occurrence grouped with the
+/// others of the same text and rule.
+fn collect(tasks: &[Task]) -> Vec<Habit> {
+    let mut habits: Vec<Habit> = Vec::new();
+    for task in tasks {
+        let Some(rule) = task.recurrence.as_deref() else {
+            continue;
+        };
+        // Rules the next occurrence can't be worked out from aren't habits
+        if next_occurrence(rule, task.created.unwrap_or_default()).is_none() {
+            continue;
+        }
+        let found = habits.iter().position(|h| {
+            h.text.eq_ignore_ascii_case(&task.text) && h.rule.eq_ignore_ascii_case(rule.trim())
+        });
+        let habit = match found {
+            Some(i) => &mut habits[i],
+            None => {
+                habits.push(Habit {
+                    text: task.text.clone(),
+                    rule: rule.trim().to_string(),
+                    done: Vec::new(),
+                    skipped: Vec::new(),
+                });
+                habits.last_mut().unwrap()
+            }
+        };
+        match (task.status, task.done, task.cancelled) {
+            (Status::Done, Some(done), _) => habit.done.push(done),
+            (Status::Cancelled, _, Some(cancelled)) => habit.skipped.push(cancelled),
+            _ => {}
+        }
+    }
+    for habit in &mut habits {
+        habit.done.sort();
+        habit.skipped.sort();
+    }
+    habits
+}
+
+/// Show each recurring task's current and longest streaks, with a strip of
+/// the last `weeks` weeks marking the days it was done.
+pub fn print(tasks: &[Task], weeks: u32, today: NaiveDate) {
+    let mut habits = collect(tasks)
+        .into_iter()
+        .map(|h| (h.streaks(today), h))
+        .collect::<Vec<_>>();
+    println!("Habits over the last {} week(s):", weeks);
+    if habits.is_empty() {
+        println!("No recurring tasks. Add one with 🔁, e.g. 'task add \"Stretch 🔁 every day\"'.");
+        return;
+    }
+    // Longest-running streaks first
+    habits.sort_by(|((a, _), _), ((b, _), _)| b.cmp(a));
+
+    let from = today - Duration::days(weeks.max(1) as i64 * 7 - 1);
+    for (i, ((current, longest), habit)) in habits.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{} (🔁 {})", habit.text, habit.rule);
+        let streak = match current {
+            0 => format!("no current streak, longest {}", longest),
+            n => format!("🔥 {} in a row, longest {}", n, longest),
+        };
+        println!(
+            "  {}  {}, done {} time(s) in all",
+            habit.strip(from, today),
+            streak,
+            habit.done.len()
+        );
+    }
+}
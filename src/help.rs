@@ -0,0 +1,835 @@
+use clap::Command;
+
+/// What `task help <command>` shows beyond the generated usage: a one-line
+/// summary for commands without a doc comment of their own (also what
+/// shell completion and `task man` list), worked examples and notes on how
+/// flags combine.
+pub struct Topic {
+    pub command: &'static str,
+    pub about: &'static str,
+    pub examples: &'static [(&'static str, &'static str)],
+    pub notes: &'static str,
+}
+
+/// Shown after the command list by `task --help` and a bare `task`.
+const OVERVIEW: &[(&str, &str)] = &[
+    ("task add \"Buy groceries\"", "add a task due today"),
+    (
+        "task add 2025-09-15 \"Finish project\"",
+        "add a task with a due date",
+    ),
+    (
+        "task add \"Water plants\" --recur \"every week\"",
+        "add a recurring task",
+    ),
+    ("task pending", "list pending tasks"),
+    ("task done 2", "mark task 2 as complete"),
+    ("task help done", "usage, examples and notes for a command"),
+];
+
+pub const TOPICS: &[Topic] = &[
+    Topic {
+        command: "add",
+        about: "Add a new task, due today unless given a date",
+        examples: &[
+            ("task add \"Write report\"", "due today"),
+            ("task add 2025-09-19 \"Call the bank\"", "with a due date"),
+            (
+                "task add 2025-09-19T14:00 \"Dentist\"",
+                "due at a time of day",
+            ),
+            (
+                "task add \"Water plants\" --recur \"every 3 days when done\"",
+                "repeats three days after each completion",
+            ),
+            (
+                "task add \"Read the RFC\" --no-due",
+                "no due date (someday)",
+            ),
+            (
+                "task add \"Deploy\" --after 2 --estimate 30m",
+                "blocked until task 2 is done",
+            ),
+            (
+                "grep -rn TODO src | task add -",
+                "one task per line of input",
+            ),
+        ],
+        notes: "The first word is taken as the due date when it is one \
+                (YYYY-MM-DD, or in date_format); otherwise it is part of the \
+                text. A future --start also sets the due date when none is \
+                given, and --no-due can't be combined with a date. With \
+                --strict the text must be one quoted argument. With -, every \
+                line of stdin is added this way, in one write.",
+    },
+    Topic {
+        command: "today",
+        about: "List tasks due today",
+        examples: &[("task today --undated section", "with undated tasks below")],
+        notes: "",
+    },
+    Topic {
+        command: "week",
+        about: "List overdue tasks and those due in the next 7 days",
+        examples: &[
+            ("task week", "overdue tasks, then the coming week"),
+            ("task week --no-overdue", "just the coming week"),
+            ("task week --workload", "estimated hours per day"),
+        ],
+        notes: "The overdue section can be turned off for good with \
+                week_overdue = false in config.toml. --workload flags days \
+                with more estimated work than workday_hours (default 8).",
+    },
+    Topic {
+        command: "due",
+        about: "List tasks due in any date range",
+        examples: &[
+            ("task due --next 14d", "the next two weeks"),
+            ("task due --month next", "all of next month"),
+            (
+                "task due --from 2025-09-01 --to 2025-09-30",
+                "an explicit range",
+            ),
+        ],
+        notes: "--month can't be combined with the other flags, and --to \
+                can't be combined with --next. Without --to or --next the \
+                range is seven days from --from (default today).",
+    },
+    Topic {
+        command: "month",
+        about: "Calendar grid of this (or any) month; a date lists that day",
+        examples: &[
+            ("task month", "this month, ! marking overdue days"),
+            ("task cal 2025-11", "another month"),
+            ("task month 2025-11-14", "the tasks due on one day"),
+        ],
+        notes: "",
+    },
+    Topic {
+        command: "last-week",
+        about: "List tasks completed in the last X weeks (default: 1)",
+        examples: &[("task lw 4", "completed in the last four weeks")],
+        notes: "",
+    },
+    Topic {
+        command: "last-month",
+        about: "",
+        examples: &[
+            ("task last-month", "the month up to today"),
+            ("task lm 3", "the last three months"),
+        ],
+        notes: "Lists the tasks completed and the ones cancelled in the \
+                period, then the tasks created in it that are still pending, \
+                with their numbers. The summary line counts each.",
+    },
+    Topic {
+        command: "review",
+        about: "",
+        examples: &[
+            (
+                "task review --from 2025-07-01 --to 2025-09-30",
+                "a quarter, for a performance review",
+            ),
+            ("task review --from 2025-01-01", "the year so far"),
+        ],
+        notes: "Shows the same as last-month for any period. Completed and \
+                cancelled tasks count by the day they were finished, pending \
+                ones by the day they were created.",
+    },
+    Topic {
+        command: "pending",
+        about: "List pending tasks, numbered for the other commands",
+        examples: &[
+            ("task pending", "tasks that have started"),
+            ("task p --all", "including ones with a future start date"),
+            (
+                "task p --since-review",
+                "added since the last weekly report",
+            ),
+            ("task p --show-urgency", "with the score that orders them"),
+        ],
+        notes: "These numbers are what done, cancel, postpone and the other \
+                commands take. They change as tasks are added and completed, \
+                so list again before acting on an old listing. The most urgent \
+                tasks come first; the weights are set under [urgency] in \
+                config.toml.",
+    },
+    Topic {
+        command: "done",
+        about: "Mark tasks as complete, or list completed tasks",
+        examples: &[
+            ("task done 3", "complete task 3"),
+            ("task done 1-4,7", "a range and a single task"),
+            ("task done --all-overdue", "everything past its due date"),
+            ("task done --tag errands", "every pending #errands task"),
+            (
+                "task done groceries",
+                "the one pending task containing \"groceries\"",
+            ),
+            (
+                "task done 3 --on 2025-09-12",
+                "finished earlier, only logged now",
+            ),
+            (
+                "task done 3 --note \"shipped in v2.1\"",
+                "keep a note with the task",
+            ),
+            ("task done", "list completed tasks"),
+        ],
+        notes: "Completing a recurring task adds its next occurrence above \
+                it, and a running timer on the task is stopped. --on records \
+                an earlier day as the ✅ date; the due date is left alone, \
+                and a \"when done\" recurrence counts from that day. A \
+                --note is kept with the task as [note:: ...] and shown by \
+                show, by done without numbers and in 'task report weekly'; \
+                a recurring task's next occurrence starts without it. Numbers, \
+                --all-overdue and --tag can be combined; each task is only \
+                completed once. Text matches pending tasks ignoring case; if \
+                several contain it, nothing is completed and they are listed, \
+                unless one's text is exactly what was given.",
+    },
+    Topic {
+        command: "triage-done",
+        about: "Tick pending tasks in a checklist and complete, cancel or postpone them together",
+        examples: &[("task triage-done", "review the pending list in one pass")],
+        notes: "Move with the arrow keys (or j/k), tick with space and a \
+                toggles every task. Press d, c or p to complete, cancel or \
+                postpone the ticked tasks; postponing asks for an offset such \
+                as +1d. q or Esc leaves without changing anything. Everything \
+                is written in one save.",
+    },
+    Topic {
+        command: "pick",
+        about: "Find a pending task by typing part of its text, then act on it",
+        examples: &[
+            ("task pick", "search every pending task"),
+            ("task pick groceries", "start with a search already typed"),
+        ],
+        notes: "Matching is fuzzy: the typed characters must appear in order, \
+                so \"grcr\" finds \"Buy groceries\", and each word is matched \
+                on its own. The best matches come first. Move with the arrow \
+                keys (or Ctrl-P/Ctrl-N) and press Enter, then d, c, e or s to \
+                complete, cancel, edit or show the task; q goes back to the \
+                search and Ctrl-C leaves without changing anything.",
+    },
+    Topic {
+        command: "cancel",
+        about: "Mark tasks as cancelled, or list cancelled tasks",
+        examples: &[
+            ("task cancel 2 --reason \"Not needed\"", "with a reason"),
+            (
+                "task cancel --tag old-project",
+                "every pending #old-project task",
+            ),
+            (
+                "task cancel 5 --on 2025-09-12",
+                "dropped earlier, logged now",
+            ),
+            ("task cancel", "list cancelled tasks with their reasons"),
+        ],
+        notes: "Tasks are selected as for done, and --on and --note work \
+                the same way. Cancelling a recurring task ends the \
+                series.",
+    },
+    Topic {
+        command: "reopen",
+        about: "",
+        examples: &[
+            ("task done", "find the task, listed as e.g. L12"),
+            ("task reopen L12", "make it pending again"),
+            ("task reopen L12 L15", "several at once"),
+        ],
+        notes: "Takes the L numbers 'task done' and 'task cancel' list, which \
+                are line numbers in the task file. The ✅ or ❌ date, the \
+                strikethrough and any --reason or --note go; the created and \
+                due dates stay. Reopening a completed recurring task leaves \
+                the next occurrence it added, which can be cancelled if it's \
+                no longer needed.",
+    },
+    Topic {
+        command: "postpone",
+        about: "Push a pending task's due date back",
+        examples: &[
+            ("task postpone 3 +2d", "two days later"),
+            ("task postpone 3 +1w", "a week later"),
+            (
+                "task postpone --overdue +1d",
+                "every overdue task to tomorrow",
+            ),
+        ],
+        notes: "With --overdue the offset counts from today, not from each \
+                task's old due date.",
+    },
+    Topic {
+        command: "snooze",
+        about: "Put a pending task off and count how often it was",
+        examples: &[
+            ("task snooze 4", "to the configured default"),
+            ("task snooze 4 monday", "to next Monday"),
+            ("task snooze 4 +3d", "three days on"),
+        ],
+        notes: "The default is snooze in config.toml: tomorrow (the \
+                default), a weekday or an offset. An overdue task is put \
+                off from today, and one due later from its due date. The \
+                count is kept in [snoozed:: n], and 'task stats' lists \
+                pending tasks snoozed 3 or more times.",
+    },
+    Topic {
+        command: "promote",
+        about: "Raise the priority of tasks one level",
+        examples: &[
+            ("task promote 2 5", "two tasks"),
+            ("task promote 2 --top", "straight to highest"),
+        ],
+        notes: "",
+    },
+    Topic {
+        command: "demote",
+        about: "Lower the priority of tasks one level",
+        examples: &[("task demote 4 --bottom", "straight to lowest")],
+        notes: "",
+    },
+    Topic {
+        command: "reschedule",
+        about: "Set a new due date for a pending task",
+        examples: &[("task reschedule 2 2025-10-01", "")],
+        notes: "",
+    },
+    Topic {
+        command: "note",
+        about: "Attach a note to a pending task",
+        examples: &[("task note 2 \"Waiting on legal\"", "")],
+        notes: "Notes are indented lines under the task, which Obsidian shows \
+                as a nested list.",
+    },
+    Topic {
+        command: "show",
+        about: "Show every detail of a task",
+        examples: &[
+            ("task show 2", "pending task 2"),
+            ("task show --line 14", "any task, by its line in task all"),
+        ],
+        notes: "",
+    },
+    Topic {
+        command: "blocked",
+        about: "List blocked tasks and what they wait on",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "waiting",
+        about: "List waiting tasks and when they wake",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "estimate",
+        about: "Record how long a pending task should take",
+        examples: &[("task estimate 3 15m", ""), ("task estimate 3 1h30m", "")],
+        notes: "",
+    },
+    Topic {
+        command: "quickies",
+        about: "Quick tasks, oldest first",
+        examples: &[
+            ("task quickies", "tasks estimated at 10 minutes or less"),
+            (
+                "task quickies --max 5m --batch-done",
+                "go through them one by one",
+            ),
+        ],
+        notes: "",
+    },
+    Topic {
+        command: "stale",
+        about: "",
+        examples: &[
+            ("task stale", "pending tasks created over 30 days ago"),
+            (
+                "task stale --days 90 --auto-cancel",
+                "cancel them after asking",
+            ),
+        ],
+        notes: "Age comes from the ➕ creation date, so tasks without one are never listed. \
+                Cancelled tasks get a [reason:: stale] field.",
+    },
+    Topic {
+        command: "start",
+        about: "Start a timer on a pending task",
+        examples: &[("task start 2", "")],
+        notes: "Starting another task stops the running timer first.",
+    },
+    Topic {
+        command: "stop",
+        about: "Stop the running timer",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "pomo",
+        about: "Pomodoro timer for a task",
+        examples: &[(
+            "task pomo 2 --rounds 4 --notify",
+            "four pomodoros with alerts",
+        )],
+        notes: "",
+    },
+    Topic {
+        command: "active",
+        about: "Show the running timer",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "time",
+        about: "Time tracked per task and tag",
+        examples: &[("task time --week", "this week only")],
+        notes: "",
+    },
+    Topic {
+        command: "all",
+        about: "List all tasks",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "wins",
+        about: "Celebrate tasks completed this week (or month)",
+        examples: &[("task wins --month --random 3", "three from this month")],
+        notes: "",
+    },
+    Topic {
+        command: "cohorts",
+        about: "Show how tasks created each week were resolved",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "stats",
+        about: "Show counts, completion rate and busiest tags",
+        examples: &[("task stats --week", "this week against last week")],
+        notes: "",
+    },
+    Topic {
+        command: "status",
+        about: "",
+        examples: &[
+            ("task status --short", "3 due today, 1 overdue"),
+            ("task status --json", "for a waybar custom module"),
+        ],
+        notes: "The counts are cached until the task file changes or the day \
+                ends, so it is cheap to run every few seconds. The context \
+                isn't applied.",
+    },
+    Topic {
+        command: "burndown",
+        about: "",
+        examples: &[("task burndown --weeks 12", "the last quarter")],
+        notes: "The counts are worked out from creation, completion and \
+                cancellation dates, so tasks without a ➕ date are left out.",
+    },
+    Topic {
+        command: "habits",
+        about: "",
+        examples: &[("task habits --weeks 8", "with eight weeks of days")],
+        notes: "Occurrences of a 🔁 task with the same text and rule make up a \
+                habit, including ones in archive.md. A streak goes on while each \
+                completion comes within one period of the last (a day for \
+                'every day'); cancelling an occurrence ends it. In the strip, ■ \
+                is a day it was done and × a day it was cancelled.",
+    },
+    Topic {
+        command: "nag",
+        about: "List critically overdue tasks and send escalating reminders",
+        examples: &[],
+        notes: "",
+    },
+    Topic {
+        command: "notify",
+        about: "Desktop reminders for overdue tasks and those due soon",
+        examples: &[
+            (
+                "task notify --lead 3",
+                "also tasks due in the next three days",
+            ),
+            ("task notify --no-notify", "just list them"),
+        ],
+        notes: "Each task is announced at most once a day, so it can run \
+                from cron as often as you like. A task due at a time is \
+                announced on the day once that time, less lead_minutes, \
+                comes.",
+    },
+    Topic {
+        command: "archive",
+        about: "Move completed and cancelled tasks to an archive",
+        examples: &[("task archive --tag work --out work-archive.md", "")],
+        notes: "",
+    },
+    Topic {
+        command: "export",
+        about: "Copy tasks to their own file, optionally in another layout",
+        examples: &[
+            ("task export --tag work --out work.md", "as they are"),
+            (
+                "task export dataview --out tasks.md",
+                "in Dataview's layout",
+            ),
+            (
+                "task export todoist --out todoist.csv",
+                "for Todoist's import",
+            ),
+        ],
+        notes: "",
+    },
+    Topic {
+        command: "import",
+        about: "",
+        examples: &[
+            ("task import todoist Groceries.csv", "tagged #groceries"),
+            (
+                "task import taskwarrior tw.json",
+                "Taskwarrior's task export",
+            ),
+        ],
+        notes: "Taskwarrior tasks keep their uuid, so importing again skips \
+                the ones already there. Without a file the JSON is read from \
+                stdin.",
+    },
+    Topic {
+        command: "scan",
+        about: "",
+        examples: &[
+            ("task scan src/", "TODO and FIXME comments"),
+            (
+                "task scan . --pattern \"TODO|HACK\"",
+                "other markers, as whole words",
+            ),
+        ],
+        notes: "Tasks keep a hash of the file and comment in a [scan:: …] \
+                field, so scanning again adds only new comments. Hidden \
+                files, binaries, target, node_modules and vendor are \
+                skipped.",
+    },
+    Topic {
+        command: "batch",
+        about: "",
+        examples: &[
+            (
+                "task batch < plan.txt",
+                "e.g. 'done 3' and 'postpone 5 +1d' lines",
+            ),
+            ("printf 'done 1\\ndone 2\\n' | task batch", "from a script"),
+        ],
+        notes: "Every line is checked before any runs, and the task file is \
+                written once at the end, with one backup and one \
+                pre-batch/post-batch hook run. If a command fails, nothing is \
+                written. Blank lines and # comments are skipped; pick and \
+                triage-done can't be batched.",
+    },
+    Topic {
+        command: "as-of",
+        about: "",
+        examples: &[
+            ("task as-of 2025-09-01 pending", ""),
+            ("task as-of 2025-09-01 stats --weeks 4", ""),
+        ],
+        notes: "Only listings can be replayed. Put global flags such as \
+                --file before as-of.",
+    },
+    Topic {
+        command: "report",
+        about: "Summaries for reviews",
+        examples: &[("task report weekly --markdown > review.md", "")],
+        notes: "",
+    },
+    Topic {
+        command: "sync",
+        about: "Sync the task file with another machine or service",
+        examples: &[
+            ("task sync ssh me@laptop:~/.local/share/task", ""),
+            ("task sync git", "uses git_remote under [sync]"),
+            ("task sync caldav", "uses caldav_url under [sync]"),
+        ],
+        notes: "",
+    },
+    Topic {
+        command: "serve",
+        about: "",
+        examples: &[
+            ("task serve --token s3cret", "on 127.0.0.1:7878"),
+            (
+                "task serve --bind 0.0.0.0:7878 --token s3cret",
+                "for other devices",
+            ),
+        ],
+        notes: "Every request needs the token, sent as 'Authorization: \
+                Bearer <token>', even from this machine. Browsers only get \
+                responses for pages whose origin is listed in origins under \
+                [serve].",
+    },
+    Topic {
+        command: "init",
+        about: "Set up a shared task list in ./.task",
+        examples: &[(
+            "task init --template sprint",
+            "with a dated sprint checklist",
+        )],
+        notes: "",
+    },
+    Topic {
+        command: "man",
+        about: "",
+        examples: &[(
+            "task man > ~/.local/share/man/man1/task.1",
+            "install it for man task",
+        )],
+        notes: "",
+    },
+    Topic {
+        command: "context",
+        about: "Keep listings and task numbers to the tasks matching a saved filter",
+        examples: &[
+            (
+                "task context define work +work or +office",
+                "tasks tagged #work or #office",
+            ),
+            ("task context work", "switch it on"),
+            ("task context none", "back to every task"),
+            ("task context", "list contexts; * marks the active one"),
+        ],
+        notes: "Conditions in a filter must all match; 'or' separates \
+                alternatives. +tag and -tag require or exclude a tag, \
+                priority:high a priority, and any other word must appear in \
+                the description. 'task context delete <name>' removes one.",
+    },
+    Topic {
+        command: "sections",
+        about: "",
+        examples: &[
+            ("task sections", "each section and its pending count"),
+            (
+                "task add --section \"Q4 planning\" \"Draft budget\"",
+                "add under ## Q4 planning",
+            ),
+            (
+                "task pending --section errands",
+                "only that section's tasks",
+            ),
+        ],
+        notes: "A section is everything under a '## ' heading, up to the next \
+                '#' or '##' one; deeper headings belong to the section they're \
+                in. --section works with any command: listings show only the \
+                section's tasks and number them on their own, as a context \
+                does, and add and capture put new tasks after the section's \
+                last line, starting it at the end of the file if there's no \
+                such heading yet. Names match ignoring case. With journal = \
+                true in config.toml, tasks added without --section go under \
+                a heading for the day, such as '## 2025-09-14'.",
+    },
+    Topic {
+        command: "move",
+        about: "",
+        examples: &[
+            ("task move 4 --to personal", "task 4 to lists/personal.md"),
+            (
+                "task --list personal move --tag work --to default",
+                "every #work task back to the main list",
+            ),
+        ],
+        notes: "Tasks are selected as for done and appended to the other \
+                list with their notes and fields unchanged; a list that \
+                doesn't exist yet is started. The other list is written \
+                first, so a failure in between leaves the tasks in both \
+                lists, never in neither.",
+    },
+    Topic {
+        command: "delete",
+        about: "",
+        examples: &[
+            ("task delete 3", "move task 3 to the trash"),
+            (
+                "task delete --tag old-project",
+                "every pending #old-project task",
+            ),
+        ],
+        notes: "Tasks are selected as for done. 'task trash' lists what was \
+                deleted and 'task restore <num>' brings a task back.",
+    },
+    Topic {
+        command: "restore",
+        about: "",
+        examples: &[
+            ("task trash", "see what can be put back"),
+            (
+                "task restore 1",
+                "the most recently deleted task, as pending",
+            ),
+            ("task backup list", "see which backups there are"),
+            ("task restore 20251014-091512", "put the task file back"),
+        ],
+        notes: "Numbers come from 'task trash'; a restored cancelled task \
+                loses its cancellation date and reason. For backups, a unique \
+                start of the timestamp is enough, and the version being \
+                replaced is backed up first, so a restore can be undone the \
+                same way.",
+    },
+];
+
+/// Break `text` into lines of at most `width` characters.
+fn wrap(text: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines.join("\n")
+}
+
+fn examples(examples: &[(&str, &str)]) -> String {
+    let mut text = String::new();
+    let width = examples.iter().map(|(c, _)| c.len()).max().unwrap_or(0);
+    for (command, what) in examples {
+        if what.is_empty() {
+            text.push_str(&format!("  {}\n", command));
+        } else {
+            text.push_str(&format!(
+                "  {:width$}  # {}\n",
+                command,
+                what,
+                width = width
+            ));
+        }
+    }
+    text
+}
+
+/// Examples and notes as shown after a command's usage.
+fn render(topic: &Topic) -> String {
+    let mut text = String::new();
+    if !topic.examples.is_empty() {
+        text.push_str("Examples:\n");
+        text.push_str(&examples(topic.examples));
+    }
+    if !topic.notes.is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&wrap(topic.notes, 78));
+    }
+    text.trim_end().to_string()
+}
+
+/// Add each topic to the generated command tree: its summary, if it has
+/// one, and examples and notes after the long help. The overview's
+/// examples go after the top-level help.
+pub fn annotate(cmd: Command) -> Command {
+    let overview = format!("Examples:\n{}", examples(OVERVIEW).trim_end());
+    let mut cmd = cmd.after_help(overview);
+    for topic in TOPICS {
+        cmd = cmd.mut_subcommand(topic.command, |sub| {
+            let sub = if topic.about.is_empty() {
+                sub
+            } else {
+                sub.about(topic.about)
+            };
+            sub.after_long_help(render(topic))
+        });
+    }
+    cmd
+}
+
+fn roff(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with(['.', '\'']) {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn man_options(page: &mut String, cmd: &Command) {
+    for arg in cmd.get_arguments() {
+        if arg.is_hide_set() || arg.get_id() == "help" || arg.get_id() == "version" {
+            continue;
+        }
+        let name = match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("\\-\\-{}", roff(long)),
+            (None, Some(short)) => format!("\\-{}", short),
+            (None, None) => format!("<{}>", arg.get_id().as_str().to_uppercase()),
+        };
+        page.push_str(&format!(".TP\n.B {}\n", name));
+        if let Some(help) = arg.get_help() {
+            page.push_str(&format!("{}\n", roff(&help.to_string())));
+        }
+    }
+}
+
+/// A man page for the whole command tree, with each command's options,
+/// examples and notes.
+pub fn man(cmd: &Command) -> String {
+    let mut page = String::from(
+        ".TH TASK 1\n.SH NAME\ntask \\- a simple task manager for Obsidian Tasks markdown\n\
+         .SH SYNOPSIS\n.B task\n[\\fIOPTIONS\\fR] [\\fICOMMAND\\fR] [\\fIARGS\\fR]\n",
+    );
+    page.push_str(".SH OPTIONS\n");
+    man_options(&mut page, cmd);
+    page.push_str(".SH COMMANDS\n");
+    // In the order of `task --help`; annotating moves commands to the end
+    let mut subcommands = cmd.get_subcommands().collect::<Vec<_>>();
+    subcommands.sort_by_key(|s| s.get_display_order());
+    for sub in subcommands {
+        if sub.is_hide_set() || sub.get_name() == "help" {
+            continue;
+        }
+        page.push_str(&format!(".SS {}\n", roff(sub.get_name())));
+        let aliases = sub
+            .get_visible_aliases()
+            .chain(sub.get_aliases())
+            .collect::<Vec<_>>();
+        if let Some(about) = sub.get_about() {
+            page.push_str(&format!("{}\n", roff(&about.to_string())));
+        }
+        if !aliases.is_empty() {
+            page.push_str(&format!(".PP\nAliases: {}\n", roff(&aliases.join(", "))));
+        }
+        man_options(&mut page, sub);
+        for nested in sub.get_subcommands().filter(|s| s.get_name() != "help") {
+            page.push_str(&format!(".TP\n.B {}\n", roff(nested.get_name())));
+            if let Some(about) = nested.get_about() {
+                page.push_str(&format!("{}\n", roff(&about.to_string())));
+            }
+        }
+        if let Some(topic) = TOPICS.iter().find(|t| t.command == sub.get_name()) {
+            if !topic.notes.is_empty() {
+                page.push_str(&format!(".PP\n{}\n", roff(topic.notes)));
+            }
+            if !topic.examples.is_empty() {
+                page.push_str(&format!(
+                    ".PP\n.nf\n{}\n.fi\n",
+                    roff(&examples(topic.examples))
+                ));
+            }
+        }
+    }
+    page.push_str(
+        ".SH ENVIRONMENT\n.TP\n.B TASK_FILE\nTask file to use instead of work_log.md in the task directory\n\
+         .TP\n.B TASK_LIST\nTask list to use, like \\-\\-list\n\
+         .TP\n.B TASK_CONFIG\nConfig file to use instead of config.toml in the task directory\n\
+         .TP\n.B TASK_SOURCE\nDefault \\-\\-source for tasks added by bots and integrations\n\
+         .TP\n.B NO_COLOR\nTurn off coloured output\n",
+    );
+    page
+}
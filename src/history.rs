@@ -0,0 +1,118 @@
+use std::path::Path;
+use std::process::Command;
+
+use chrono::NaiveDate;
+
+use crate::config::TaskFormat;
+use crate::crypt;
+use crate::storage;
+use crate::task::{self, Status, Task};
+
+/// Where a past state of the task file came from.
+pub enum Source {
+    /// The last commit of the file on or before the day, from `sync git`
+    Commit(String),
+    /// Rebuilt from the creation, completion and cancellation dates of the
+    /// tasks in the file and the archive
+    Rebuilt,
+}
+
+fn lines_of(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+}
+
+/// The task file as committed at the end of `date`, if the task directory
+/// is a git repository with a commit of it by then.
+fn from_git(task_dir: &Path, task_file: &Path, date: NaiveDate) -> Option<(Vec<String>, String)> {
+    let file = task_file.strip_prefix(task_dir).ok()?.to_str()?;
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(task_dir)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+    };
+    let before = format!("--before={}T23:59:59", date);
+    let rev = git(&["log", "-1", "--format=%h", &before, "--", file])?;
+    let rev = rev.trim();
+    if rev.is_empty() {
+        return None;
+    }
+    let text = git(&["show", &format!("{}:{}", rev, file)])?;
+    let text = crypt::open(text.into_bytes()).ok()?;
+    Some((lines_of(&text), rev.to_string()))
+}
+
+/// Rebuild the list as of `date` from the dates each task carries: tasks
+/// created later are dropped, and ones finished later are pending again.
+/// Archived tasks are brought back from `archive`. Due dates show their
+/// current value, since postponements aren't recorded.
+fn rebuild(
+    lines: &[String],
+    archive: &[String],
+    date: NaiveDate,
+    format: TaskFormat,
+) -> Vec<String> {
+    // Only the tasks and their notes, not the archive's headings
+    let archived = archive
+        .iter()
+        .filter(|l| {
+            Task::parse(l).is_some() || (l.starts_with([' ', '\t']) && !l.trim().is_empty())
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut rebuilt = Vec::new();
+    for source in [&archived[..], lines] {
+        let mut i = 0;
+        while i < source.len() {
+            let Some(mut task) = Task::parse(&source[i]) else {
+                rebuilt.push(source[i].clone());
+                i += 1;
+                continue;
+            };
+            let block = 1 + task::notes(source, i).len();
+            if task.created.is_none_or(|c| c <= date) {
+                let finished_later =
+                    task.done.is_some_and(|d| d > date) || task.cancelled.is_some_and(|d| d > date);
+                if finished_later {
+                    task.status = Status::Pending;
+                    task.done = None;
+                    task.cancelled = None;
+                    rebuilt.push(task.to_line(format));
+                } else {
+                    rebuilt.push(source[i].clone());
+                }
+                rebuilt.extend_from_slice(&source[i + 1..i + block]);
+            }
+            i += block;
+        }
+    }
+    rebuilt
+}
+
+/// The task file as it stood at the end of `date`: from git history when
+/// the task directory has it, otherwise rebuilt from the task dates.
+pub fn as_of(
+    task_dir: &Path,
+    task_file: &Path,
+    date: NaiveDate,
+    format: TaskFormat,
+) -> (Vec<String>, Source) {
+    if let Some((lines, rev)) = from_git(task_dir, task_file, date) {
+        return (lines, Source::Commit(rev));
+    }
+    let read = |path: &Path| {
+        storage::read_text(path)
+            .map(|s| lines_of(&s))
+            .unwrap_or_default()
+    };
+    let archive = read(&task_dir.join("archive.md"));
+    (
+        rebuild(&read(task_file), &archive, date, format),
+        Source::Rebuilt,
+    )
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::sandbox::{self, Outcome};
+use crate::task::Task;
+
+/// Directory under the task directory holding the hook scripts.
+const DIR: &str = "hooks";
+
+// Hooks get as long as `task shell` does by default
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Hooks {
+    task_dir: PathBuf,
+    command: String,
+}
+
+static HOOKS: OnceLock<Hooks> = OnceLock::new();
+
+/// Run the `pre-<command>` and `post-<command>` hooks around every save
+/// for the rest of this process.
+pub fn init(task_dir: &Path, command: &str) {
+    let _ = HOOKS.set(Hooks {
+        task_dir: task_dir.to_path_buf(),
+        command: command.to_string(),
+    });
+}
+
+/// The tasks a change added or modified, as they are after it; for a change
+/// that only removed tasks, the removed ones.
+fn affected(before: &[String], after: &[String]) -> Vec<Task> {
+    let mut removed = before.to_vec();
+    let mut added = Vec::new();
+    for line in after {
+        match removed.iter().position(|l| l == line) {
+            Some(i) => {
+                removed.swap_remove(i);
+            }
+            None => added.push(line),
+        }
+    }
+    let parsed = added
+        .into_iter()
+        .filter_map(|l| Task::parse(l))
+        .collect::<Vec<_>>();
+    if parsed.is_empty() {
+        removed.iter().filter_map(|l| Task::parse(l)).collect()
+    } else {
+        parsed
+    }
+}
+
+/// Run hook `name` if there is one, with the affected tasks as a JSON array
+/// on stdin, sandboxed like `task shell`.
+fn run(
+    hooks: &Hooks,
+    name: &str,
+    task_file: &Path,
+    before: &[String],
+    after: &[String],
+) -> Option<Result<Outcome, String>> {
+    let script = hooks.task_dir.join(DIR).join(name);
+    if !script.is_file() {
+        return None;
+    }
+    let input = format!(
+        "[{}]",
+        affected(before, after)
+            .iter()
+            .map(sandbox::task_json)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let env = [
+        ("TASK_FILE", task_file.display().to_string()),
+        ("TASK_DIR", hooks.task_dir.display().to_string()),
+        ("TASK_COMMAND", hooks.command.clone()),
+        ("TASK_HOOK", name.to_string()),
+    ];
+    let outcome = sandbox::run(
+        &[script.display().to_string()],
+        &input,
+        &env,
+        TIMEOUT,
+        &hooks.task_dir.join("hooks.log"),
+    );
+    if let Ok(outcome) = &outcome {
+        print!("{}", outcome.stdout);
+        eprint!("{}", outcome.stderr);
+    }
+    Some(outcome)
+}
+
+/// Run the `pre-<command>` hook on a change about to be saved. An error
+/// means it refused the change, which must not be written.
+pub fn pre(task_file: &Path, before: &[String], after: &[String]) -> Result<(), String> {
+    let Some(hooks) = HOOKS.get() else {
+        return Ok(());
+    };
+    let name = format!("pre-{}", hooks.command);
+    match run(hooks, &name, task_file, before, after) {
+        None => Ok(()),
+        Some(Err(e)) => Err(format!("{} hook: {}", name, e)),
+        Some(Ok(outcome)) if outcome.timed_out => Err(format!(
+            "{} hook timed out after {}s",
+            name,
+            TIMEOUT.as_secs()
+        )),
+        Some(Ok(outcome)) if outcome.code != Some(0) => {
+            Err(format!("{} hook refused the change (see hooks.log)", name))
+        }
+        Some(Ok(_)) => Ok(()),
+    }
+}
+
+/// Run the `post-<command>` hook on a change that has been saved. It can't
+/// undo the change, so a failure is only reported.
+pub fn post(task_file: &Path, before: &[String], after: &[String]) {
+    let Some(hooks) = HOOKS.get() else {
+        return;
+    };
+    let name = format!("post-{}", hooks.command);
+    match run(hooks, &name, task_file, before, after) {
+        Some(Err(e)) => eprintln!("Warning: {} hook: {}", name, e),
+        Some(Ok(outcome)) if outcome.code != Some(0) => {
+            eprintln!("Warning: {} hook failed (see hooks.log).", name)
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use serde::Deserialize;
+
+use crate::config::TaskFormat;
+use crate::recurrence::stable_hash;
+use crate::task::Task;
+
+/// How new `🆔` task ids are generated, from `id_strategy` in the config.
+/// Tasks can carry ids of any form; the strategy only affects new ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// Six random characters, as Obsidian Tasks generates them
+    #[default]
+    Random,
+    /// 1, 2, 3, ...: short, but two machines can hand out the same number
+    Sequential,
+    /// Time-ordered 26-character ULIDs that won't collide across machines
+    Ulid,
+    /// Eight hex digits from the task's creation date and text
+    Hash,
+}
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// 48 bits of milliseconds since the epoch and 80 random bits, in Crockford
+/// base32.
+fn ulid() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() & ((1 << 48) - 1));
+    let random = rand::rng().random::<u128>() >> 48;
+    let value = (millis << 80) | random;
+    (0..26)
+        .rev()
+        .map(|i| CROCKFORD[((value >> (i * 5)) & 31) as usize] as char)
+        .collect()
+}
+
+/// A fresh id for `task` that isn't in `taken`.
+pub fn new_id(strategy: Strategy, task: &Task, taken: &[String]) -> String {
+    let free = |id: &String| !taken.contains(id);
+    match strategy {
+        Strategy::Random => {
+            let mut rng = rand::rng();
+            loop {
+                let id = (0..6)
+                    .map(|_| rng.sample(Alphanumeric).to_ascii_lowercase() as char)
+                    .collect::<String>();
+                if free(&id) {
+                    return id;
+                }
+            }
+        }
+        Strategy::Sequential => {
+            let last = taken.iter().filter_map(|id| id.parse::<u64>().ok()).max();
+            (last.unwrap_or(0) + 1).to_string()
+        }
+        Strategy::Ulid => loop {
+            let id = ulid();
+            if free(&id) {
+                return id;
+            }
+        },
+        // Identical tasks would hash alike, so salt until it's unique
+        Strategy::Hash => (0..)
+            .map(|salt| {
+                let created = task.created.map(|d| d.to_string()).unwrap_or_default();
+                let key = format!("{}|{}|{}", created, task.text, salt);
+                format!("{:08x}", stable_hash(&key) as u32)
+            })
+            .find(free)
+            .unwrap_or_default(),
+    }
+}
+
+/// Give every task that has an id a new one from `strategy`, updating the
+/// `⛔` references to match. Returns how many ids changed.
+pub fn convert(lines: &mut [String], strategy: Strategy, format: TaskFormat) -> usize {
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let mut taken = Vec::new();
+    for line in lines.iter() {
+        let Some(task) = Task::parse(line) else {
+            continue;
+        };
+        if let Some(old) = task.id.clone().filter(|id| !renamed.contains_key(id)) {
+            let id = new_id(strategy, &task, &taken);
+            taken.push(id.clone());
+            renamed.insert(old, id);
+        }
+    }
+
+    for line in lines.iter_mut() {
+        let Some(mut task) = Task::parse(line) else {
+            continue;
+        };
+        if task.id.is_none() && task.depends_on.is_empty() {
+            continue;
+        }
+        let rename = |id: &String| renamed.get(id).cloned().unwrap_or_else(|| id.clone());
+        task.id = task.id.as_ref().map(rename);
+        task.depends_on = task.depends_on.iter().map(rename).collect();
+        let updated = task.to_line(format);
+        if updated != *line {
+            *line = updated;
+        }
+    }
+    renamed.iter().filter(|(old, new)| old != new).count()
+}
@@ -0,0 +1,159 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::crypt;
+use crate::storage;
+
+/// Where an open task's line, or a heading's, is in the task file.
+struct Entry {
+    line: usize,
+    offset: u64,
+    len: usize,
+}
+
+/// The index of `path`: its fingerprint when indexed, how many lines it
+/// has and where its open tasks and headings are.
+fn save(path: &Path, total: usize, entries: &[Entry]) {
+    let Some(fingerprint) = storage::fingerprint(path) else {
+        return;
+    };
+    let mut contents = format!("{}\n{}\n", fingerprint, total);
+    for entry in entries {
+        contents.push_str(&format!("{} {} {}\n", entry.line, entry.offset, entry.len));
+    }
+    let index = storage::cache_file(path, "index");
+    if let Some(dir) = index.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    // Only a cache: without it, reads scan the file instead
+    let _ = fs::write(index, contents);
+}
+
+/// The index `save` kept for `path`, whether or not it's still up to date.
+fn load(path: &Path) -> Option<(String, usize, Vec<Entry>)> {
+    let contents = fs::read_to_string(storage::cache_file(path, "index")).ok()?;
+    let mut rows = contents.lines();
+    let fingerprint = rows.next()?.to_string();
+    let total = rows.next()?.parse().ok()?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let fields = row
+            .split(' ')
+            .map(|f| f.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let [line, offset, len] = fields[..] else {
+            return None;
+        };
+        entries.push(Entry {
+            line: line as usize,
+            offset,
+            len: len as usize,
+        });
+    }
+    Some((fingerprint, total, entries))
+}
+
+/// The lines of `path` with only its open tasks and headings filled in,
+/// read straight from where the index says they are. `None` if there's no
+/// index or the file has changed since it was made.
+pub fn open_lines(path: &Path) -> Option<Vec<String>> {
+    let (fingerprint, total, entries) = load(path)?;
+    if fingerprint != storage::fingerprint(path)? {
+        return None;
+    }
+    let mut lines = vec![String::new(); total];
+    let mut file = File::open(path).ok()?;
+    for entry in entries {
+        file.seek(SeekFrom::Start(entry.offset)).ok()?;
+        let mut bytes = vec![0; entry.len];
+        file.read_exact(&mut bytes).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        // Anything else means the index is wrong; scan the file instead
+        if !storage::open_or_heading(&text) {
+            return None;
+        }
+        *lines.get_mut(entry.line)? = text;
+    }
+    Some(lines)
+}
+
+/// Read `path` a line at a time, keeping only its open tasks and headings
+/// as [`open_lines`] does, and index it for next time. `None` for an encrypted
+/// file, which has no offsets to keep.
+pub fn scan(path: &Path) -> Option<Vec<String>> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    if crypt::tool_of(reader.fill_buf().ok()?) != crypt::Tool::None {
+        return None;
+    }
+    let (mut lines, mut entries) = (Vec::new(), Vec::new());
+    let mut offset = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf).unwrap_or_else(|e| {
+            eprintln!("Error reading task file at {}: {}", path.display(), e);
+            std::process::exit(storage::EXIT_STORAGE);
+        });
+        if read == 0 {
+            break;
+        }
+        let text = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        let text = String::from_utf8(text.to_vec()).unwrap_or_else(|_| {
+            eprintln!("Error reading task file: the task file isn't valid UTF-8");
+            std::process::exit(storage::EXIT_STORAGE);
+        });
+        if storage::open_or_heading(&text) {
+            entries.push(Entry {
+                line: lines.len(),
+                offset,
+                len: text.len(),
+            });
+            lines.push(text);
+        } else {
+            lines.push(String::new());
+        }
+        offset += read as u64;
+    }
+    save(path, lines.len(), &entries);
+    Some(lines)
+}
+
+/// Where the open tasks and headings among `lines` are, the first being
+/// line `first` of the file and starting `offset` bytes into it.
+fn entries(lines: &[String], first: usize, mut offset: u64, crlf: bool) -> Vec<Entry> {
+    let ending = if crlf { 2 } else { 1 };
+    let mut entries = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if storage::open_or_heading(line) {
+            entries.push(Entry {
+                line: first + i,
+                offset,
+                len: line.len(),
+            });
+        }
+        offset += (line.len() + ending) as u64;
+    }
+    entries
+}
+
+/// Index `path` just after `lines` were written to it, so the next read
+/// doesn't have to scan it.
+pub fn update(path: &Path, lines: &[String], crlf: bool) {
+    save(path, lines.len(), &entries(lines, 0, 0, crlf));
+}
+
+/// Add `lines`, just appended to `path` starting `offset` bytes in, to its
+/// index, if the index was up to date with the file as it was before
+/// (`previous`, its fingerprint then). Otherwise the next read rebuilds it.
+pub fn append(path: &Path, previous: Option<&str>, offset: u64, lines: &[String], crlf: bool) {
+    let Some((fingerprint, total, mut indexed)) = load(path) else {
+        return;
+    };
+    if previous != Some(fingerprint.as_str()) {
+        return;
+    }
+    indexed.extend(entries(lines, total, offset, crlf));
+    save(path, total + lines.len(), &indexed);
+}
@@ -0,0 +1,213 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// Just enough JSON for Taskwarrior's export and the `serve` API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn str(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// A list of strings, which older Taskwarrior versions write as one
+    /// comma-separated string (`depends`).
+    pub fn strings(&self, key: &str) -> Vec<String> {
+        match self.get(key) {
+            Some(Json::Array(items)) => items
+                .iter()
+                .filter_map(|i| match i {
+                    Json::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect(),
+            Some(Json::String(s)) => s
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    text: &'a str,
+}
+
+impl Parser<'_> {
+    fn error(&mut self, what: &str) -> String {
+        let at = self.chars.peek().map_or(self.text.len(), |(i, _)| *i);
+        let line = self.text[..at].matches('\n').count() + 1;
+        format!("invalid JSON on line {}: {}", line, what)
+    }
+
+    fn skip_space(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), String> {
+        self.skip_space();
+        match self.chars.next() {
+            Some((_, c)) if c == want => Ok(()),
+            _ => Err(self.error(&format!("expected '{}'", want))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_space();
+        let Some(&(start, c)) = self.chars.peek() else {
+            return Err(self.error("unexpected end of input"));
+        };
+        match c {
+            '{' => {
+                self.chars.next();
+                let mut fields = Vec::new();
+                self.skip_space();
+                if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_space();
+                    let Json::String(key) = self.value()? else {
+                        return Err(self.error("expected a string key"));
+                    };
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_space();
+                    match self.chars.next() {
+                        Some((_, ',')) => continue,
+                        Some((_, '}')) => return Ok(Json::Object(fields)),
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_space();
+                if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_space();
+                    match self.chars.next() {
+                        Some((_, ',')) => continue,
+                        Some((_, ']')) => return Ok(Json::Array(items)),
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            '"' => {
+                self.chars.next();
+                let mut s = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some((_, '"')) => return Ok(Json::String(s)),
+                        Some((_, '\\')) => match self.chars.next() {
+                            Some((_, 'n')) => s.push('\n'),
+                            Some((_, 't')) => s.push('\t'),
+                            Some((_, 'r')) => s.push('\r'),
+                            Some((_, 'b')) => s.push('\u{8}'),
+                            Some((_, 'f')) => s.push('\u{c}'),
+                            Some((_, 'u')) => {
+                                let hex = (0..4)
+                                    .filter_map(|_| self.chars.next().map(|(_, c)| c))
+                                    .collect::<String>();
+                                let code = u32::from_str_radix(&hex, 16)
+                                    .map_err(|_| self.error("bad \\u escape"))?;
+                                s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                            }
+                            Some((_, c)) => s.push(c),
+                            None => return Err(self.error("unterminated string")),
+                        },
+                        Some((_, c)) => s.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+            }
+            _ => {
+                let mut end = start;
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|(_, c)| c.is_ascii_alphanumeric() || "+-.".contains(*c))
+                {
+                    end = i + c.len_utf8();
+                }
+                match &self.text[start..end] {
+                    "null" => Ok(Json::Null),
+                    "true" => Ok(Json::Bool(true)),
+                    "false" => Ok(Json::Bool(false)),
+                    number => number
+                        .parse()
+                        .map(Json::Number)
+                        .map_err(|_| self.error("unexpected value")),
+                }
+            }
+        }
+    }
+}
+
+/// Parse a sequence of JSON values separated by whitespace or commas: a
+/// single document, or one per line as in JSON Lines.
+pub fn values(text: &str) -> Result<Vec<Json>, String> {
+    let mut parser = Parser {
+        chars: text.char_indices().peekable(),
+        text,
+    };
+    let mut values = Vec::new();
+    loop {
+        parser.skip_space();
+        if parser.chars.peek().is_none() {
+            return Ok(values);
+        }
+        values.push(parser.value()?);
+        parser.skip_space();
+        parser.chars.next_if(|(_, c)| *c == ',');
+    }
+}
+
+/// Parse one JSON document.
+pub fn parse(text: &str) -> Result<Json, String> {
+    match values(text)?.as_slice() {
+        [value] => Ok(value.clone()),
+        [] => Err("empty JSON document".to_string()),
+        _ => Err("expected a single JSON value".to_string()),
+    }
+}
+
+/// `s` as a quoted JSON string.
+pub fn string(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    format!("\"{}\"", out)
+}
@@ -0,0 +1,159 @@
+use chrono::NaiveDate;
+
+use crate::config::TaskFormat;
+use crate::task::{self, LineKind, Status, Task};
+
+/// A problem found on one line of the task file, with the repaired line when
+/// `task lint --fix` knows how to repair it.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub line: usize,
+    pub description: String,
+    pub fixed: Option<String>,
+    /// Only the layout differs; `--fix-dates` leaves these alone
+    pub cosmetic: bool,
+}
+
+/// A leading `[x]`, `[ ]`, `[-]` or `[>]` left in the description by
+/// repeated find-and-replace on the checkbox.
+fn strip_checkbox(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix('[')?;
+    let mut chars = rest.chars();
+    if !matches!(chars.next()?, ' ' | 'x' | 'X' | '-' | '>') {
+        return None;
+    }
+    Some(chars.as_str().strip_prefix(']')?.trim_start())
+}
+
+/// Dates that can't both be true of a task, and status markers repeated on
+/// one line. The task is repaired in place; returns what was wrong.
+fn repair(task: &mut Task) -> Vec<String> {
+    let mut found = Vec::new();
+
+    while let Some(rest) = strip_checkbox(&task.text) {
+        found.push(format!(
+            "repeated status marker '{}'",
+            &task.text[..task.text.len() - rest.len()].trim_end()
+        ));
+        task.text = rest.to_string();
+    }
+
+    match task.status {
+        Status::Cancelled => {
+            if let Some(done) = task.done.take() {
+                found.push(format!(
+                    "cancelled task carries a ✅ {} completion date",
+                    done
+                ));
+                task.cancelled = task.cancelled.or(Some(done));
+            }
+        }
+        Status::Done => {
+            if let Some(cancelled) = task.cancelled.take() {
+                found.push(format!(
+                    "completed task carries a ❌ {} cancellation date",
+                    cancelled
+                ));
+            }
+        }
+        Status::Pending | Status::Waiting | Status::Proposed => {
+            if task.done.take().is_some() || task.cancelled.take().is_some() {
+                found.push("open task carries a completion or cancellation date".to_string());
+            }
+        }
+    }
+
+    // The later creation date is the one a replace rewrote, so move it back
+    if let Some(created) = task.created {
+        for (what, date) in [("completed", task.done), ("cancelled", task.cancelled)] {
+            if let Some(date) = date.filter(|d| *d < created) {
+                found.push(format!(
+                    "{} on {} before it was created on {}",
+                    what, date, created
+                ));
+                task.created = Some(date);
+            }
+        }
+    }
+    found
+}
+
+/// Completion and cancellation dates a closed task lacks, which date-based
+/// reports need to place it. The creation date stands in (or `today` when
+/// there is none), since the real date can't be known.
+fn fill_missing(task: &mut Task, today: NaiveDate) -> Vec<String> {
+    let mut found = Vec::new();
+    let stand_in = task.created.unwrap_or(today);
+    match task.status {
+        Status::Done if task.done.is_none() => {
+            found.push("completed task has no ✅ completion date".to_string());
+            task.done = Some(stand_in);
+        }
+        Status::Cancelled if task.cancelled.is_none() => {
+            found.push("cancelled task has no ❌ cancellation date".to_string());
+            task.cancelled = Some(stand_in);
+        }
+        _ => {}
+    }
+    found
+}
+
+/// Check every line for checkbox items that aren't read as tasks, and every
+/// task line for impossible chronology, malformed or missing metadata and
+/// layout that differs from what `format` writes.
+pub fn check(lines: &[String], format: TaskFormat, today: NaiveDate) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let LineKind::Malformed { why, fixed } = task::classify(line) {
+            problems.push(Problem {
+                line: i,
+                description: format!("not read as a task: {}", why),
+                fixed,
+                cosmetic: false,
+            });
+            continue;
+        }
+        let Some(mut task) = Task::parse(line) else {
+            continue;
+        };
+        if let Err(e) = task::strict_check(line) {
+            problems.push(Problem {
+                line: i,
+                description: e,
+                fixed: None,
+                cosmetic: false,
+            });
+        }
+        if task.text.trim().is_empty() {
+            problems.push(Problem {
+                line: i,
+                description: "task has no description".to_string(),
+                fixed: None,
+                cosmetic: false,
+            });
+        }
+
+        let mut found = repair(&mut task);
+        found.extend(fill_missing(&mut task, today));
+        // Subtasks keep their indentation
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let canonical = format!("{}{}", indent, task.to_line(format));
+        if !found.is_empty() {
+            problems.push(Problem {
+                line: i,
+                description: found.join("; "),
+                fixed: Some(canonical),
+                cosmetic: false,
+            });
+        } else if canonical != *line {
+            problems.push(Problem {
+                line: i,
+                description: "not in canonical form (marker order, spacing or strikethrough)"
+                    .to_string(),
+                fixed: Some(canonical),
+                cosmetic: true,
+            });
+        }
+    }
+    problems
+}
@@ -1,22 +1,138 @@
-use std::env;
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
-
-use chrono::{Duration, Local, NaiveDate};
-use clap::{Parser, Subcommand};
-use once_cell::sync::Lazy;
-use regex::Regex;
-
-// Regex patterns for date extraction
-static DUE_DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"📅\s+(\d{4}-\d{2}-\d{2})").unwrap());
-static COMPLETION_DATE_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"✅\s+(\d{4}-\d{2}-\d{2})").unwrap());
-static DATE_PART_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(📅[^📋]*📋[^\s]*)").unwrap());
+mod alias;
+mod archive;
+mod backup;
+mod caldav;
+mod calendar;
+mod capture;
+mod color;
+mod config;
+mod context;
+mod crypt;
+mod dates;
+mod deps;
+mod diff;
+mod habits;
+mod help;
+mod history;
+mod hooks;
+mod ids;
+mod index;
+mod json;
+mod lint;
+mod nag;
+mod notify;
+mod order;
+mod pager;
+mod pick;
+mod pomo;
+mod proposals;
+mod recurrence;
+mod report;
+mod review;
+mod sandbox;
+mod scaffold;
+mod scan;
+mod sections;
+mod serve;
+mod status;
+mod storage;
+mod store;
+mod suggest;
+mod sync;
+mod task;
+mod taskwarrior;
+mod timer;
+mod todoist;
+mod trash;
+mod triage;
+mod urgency;
+mod waiting;
+mod watch;
+
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, Local, NaiveDate, NaiveTime};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use rand::seq::IndexedRandom;
+
+use config::{Config, TaskFormat, Undated};
+use storage::{get_task_dir, get_task_file, read_lines, write_lines};
+use store::{FileStore, TaskStore};
+use task::{Priority, Status, Task, parse_date};
+
+/// Exit status for bad arguments or input. Successful commands exit with 0,
+/// and a task file that can't be read or written with
+/// `storage::EXIT_STORAGE`.
+const EXIT_USAGE: i32 = 1;
+/// Exit status when a task number or line doesn't exist, or nothing matched.
+const EXIT_NOT_FOUND: i32 = 2;
 
 #[derive(Parser)]
 #[command(name = "task")]
 struct Cli {
+    /// Treat ambiguous input and unknown metadata as errors instead of guessing
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Disable coloured output (also honours the NO_COLOR environment variable)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Print a diff of the lines a command rewrites (bulk changes show one by default)
+    #[arg(long, global = true, conflicts_with = "no_diff")]
+    show_diff: bool,
+
+    /// Never print diffs of rewritten lines
+    #[arg(long, global = true)]
+    no_diff: bool,
+
+    /// How date-filtered views treat tasks without a due date (overrides the config)
+    #[arg(long, global = true, value_enum)]
+    undated: Option<Undated>,
+
+    /// Task file to use for this command (overrides TASK_FILE and any project list)
+    #[arg(long, global = true, value_name = "PATH")]
+    file: Option<PathBuf>,
+
+    /// Task list to use, kept in lists/<NAME>.md in the task directory (also
+    /// TASK_LIST); `default` is the main task file
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "file")]
+    list: Option<String>,
+
+    /// Run a listing once for every task list
+    #[arg(long, global = true, conflicts_with_all = ["file", "list"])]
+    all_lists: bool,
+
+    /// Print only the command's output: no banner or notices, for scripts
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Never show long listings through the pager
+    #[arg(long, global = true)]
+    no_pager: bool,
+
+    /// Order listings by this instead of their usual order (task numbers
+    /// stay the same)
+    #[arg(long, global = true, value_enum, value_name = "KEY")]
+    sort: Option<order::SortKey>,
+
+    /// List in the opposite order
+    #[arg(long, global = true)]
+    reverse: bool,
+
+    /// Show at most this many tasks in a listing
+    #[arg(long, global = true, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Put listed tasks under a header per group, with counts
+    #[arg(long, global = true, value_enum, value_name = "GROUP")]
+    group_by: Option<order::GroupBy>,
+
+    /// Only the tasks under this `## ` heading, and where `add` puts new ones
+    #[arg(long, global = true, value_name = "HEADING")]
+    section: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -25,15 +141,82 @@ struct Cli {
 enum Commands {
     #[command(alias = "a")]
     Add {
+        /// Due date, or `-` to add one task per line read from stdin
         date: Option<String>,
         text: Vec<String>,
+        #[arg(long, value_parser = parse_priority)]
+        priority: Option<Priority>,
+        #[arg(long, value_parser = parse_date_arg)]
+        start: Option<NaiveDate>,
+        #[arg(long, value_parser = parse_date_arg)]
+        scheduled: Option<NaiveDate>,
+        #[arg(long, value_name = "RULE")]
+        recur: Option<String>,
+        #[arg(long, value_parser = dates::parse_estimate, value_name = "DURATION")]
+        estimate: Option<i64>,
+        /// Block the new task until these pending tasks are completed
+        #[arg(long, value_name = "TASK_NUM", num_args = 1..)]
+        after: Vec<usize>,
+        /// Leave the task without a due date ("someday")
+        #[arg(long)]
+        no_due: bool,
+        /// Bot or integration adding the task; it waits for `task approve`
+        /// unless trusted (default: $TASK_SOURCE)
+        #[arg(long)]
+        source: Option<String>,
+    },
+
+    /// Add a task from natural text, e.g. "call the dentist tomorrow high
+    /// priority", typed or dictated
+    Capture {
+        /// The task; without it, one task per line is read until end of input
+        text: Vec<String>,
+        /// Record the text with the configured voice_command
+        #[arg(long, conflicts_with = "text")]
+        voice: bool,
+        /// Show the parsed task and ask before adding it
+        #[arg(long)]
+        confirm: bool,
+        /// Bot or integration adding the task (default: $TASK_SOURCE)
+        #[arg(long)]
+        source: Option<String>,
     },
 
     #[command(alias = "t")]
     Today,
 
     #[command(alias = "w")]
-    Week,
+    Week {
+        /// Leave out tasks that are already overdue (overrides the config)
+        #[arg(long)]
+        no_overdue: bool,
+        /// Sum estimated hours per day instead of listing the tasks
+        #[arg(long)]
+        workload: bool,
+    },
+
+    Due {
+        #[arg(long, value_parser = parse_date_arg, conflicts_with = "month")]
+        from: Option<NaiveDate>,
+        #[arg(long, value_parser = parse_date_arg, conflicts_with_all = ["next", "month"])]
+        to: Option<NaiveDate>,
+        #[arg(long, value_parser = dates::parse_span, value_name = "SPAN", conflicts_with = "month")]
+        next: Option<dates::Span>,
+        #[arg(long, value_name = "this|next|last|YYYY-MM")]
+        month: Option<String>,
+    },
+
+    /// Overdue, today, next 7 days and undated tasks on one screen
+    Agenda,
+
+    /// Pending tasks without a due date
+    Someday,
+
+    #[command(alias = "cal")]
+    Month {
+        #[arg(value_name = "YYYY-MM|YYYY-MM-DD")]
+        when: Option<String>,
+    },
 
     #[command(alias = "lw")]
     LastWeek {
@@ -41,401 +224,4691 @@ enum Commands {
         weeks: u32,
     },
 
+    /// What was completed and cancelled in the last X months (default: 1),
+    /// and which tasks created then are still pending
+    #[command(alias = "lm")]
+    LastMonth {
+        #[arg(default_value = "1")]
+        months: u32,
+    },
+
+    /// What was completed and cancelled between two dates, and which tasks
+    /// created then are still pending, e.g. for a quarter
+    Review {
+        #[arg(long, value_parser = parse_date_arg)]
+        from: NaiveDate,
+        /// Last day of the period (default: today)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+    },
+
     #[command(alias = "p")]
-    Pending,
+    Pending {
+        /// Include tasks whose start date hasn't arrived yet
+        #[arg(long)]
+        all: bool,
+        /// Only tasks added since the last review ('task report weekly')
+        #[arg(long)]
+        since_review: bool,
+        /// Show each task's urgency score, which orders the list
+        #[arg(long)]
+        show_urgency: bool,
+    },
 
     #[command(alias = "d")]
-    Done { task_nums: Vec<usize> },
+    Done {
+        #[command(flatten)]
+        selection: Selection,
+        /// Record the tasks as completed on this day instead of today
+        #[arg(long, value_parser = parse_date_arg, value_name = "DATE")]
+        on: Option<NaiveDate>,
+        /// A short note kept with the completed tasks
+        #[arg(long)]
+        note: Option<String>,
+    },
 
     #[command(alias = "c")]
-    Cancel { task_num: Option<usize> },
+    Cancel {
+        #[command(flatten)]
+        selection: Selection,
+        #[arg(long)]
+        reason: Option<String>,
+        /// Record the tasks as cancelled on this day instead of today
+        #[arg(long, value_parser = parse_date_arg, value_name = "DATE")]
+        on: Option<NaiveDate>,
+        /// A short note kept with the cancelled tasks
+        #[arg(long)]
+        note: Option<String>,
+    },
 
-    #[command(alias = "l", alias = "list")]
-    All,
-}
+    /// Make completed or cancelled tasks pending again, keeping their
+    /// created and due dates
+    Reopen {
+        /// Tasks as `task done` and `task cancel` list them, such as L12
+        #[arg(required = true, value_name = "LINE")]
+        lines: Vec<String>,
+    },
 
-fn get_task_file() -> PathBuf {
-    // First try to use TASK_FILE environment variable if set
-    if let Ok(path) = env::var("TASK_FILE") {
-        return PathBuf::from(path);
-    }
+    Postpone {
+        #[arg(required_unless_present = "overdue")]
+        task_num: Option<usize>,
+        #[arg(value_parser = dates::parse_offset, required_unless_present = "overdue")]
+        offset: Option<dates::Span>,
+        #[arg(long, value_parser = dates::parse_offset, value_name = "OFFSET", conflicts_with_all = ["task_num", "offset"])]
+        overdue: Option<dates::Span>,
+    },
 
-    // Determine home directory in a cross-platform way
-    let home = if cfg!(windows) {
-        // On Windows, try USERPROFILE first, then HOMEDRIVE+HOMEPATH
-        env::var("USERPROFILE").or_else(|_: env::VarError| -> Result<String, env::VarError> {
-            let drive = env::var("HOMEDRIVE").unwrap_or_else(|_| String::from("C:"));
-            let path = env::var("HOMEPATH").unwrap_or_else(|_| String::from("\\Users\\Default"));
-            Ok(format!("{}{}", drive, path))
-        }).unwrap_or_else(|_| String::from("."))
-    } else {
-        // On Unix systems (Linux, macOS), use HOME
-        env::var("HOME").unwrap_or_else(|_| String::from("."))
-    };
+    Promote {
+        #[arg(required = true)]
+        task_nums: Vec<usize>,
+        #[arg(long)]
+        top: bool,
+    },
 
-    // Create the task directory path (cross-platform)
-    let task_dir = if cfg!(windows) {
-        PathBuf::from(&home).join("AppData").join("Local").join("Task")
-    } else {
-        PathBuf::from(&home).join(".task")
-    };
+    Demote {
+        #[arg(required = true)]
+        task_nums: Vec<usize>,
+        #[arg(long)]
+        bottom: bool,
+    },
 
-    // Create directory if it doesn't exist
-    if !task_dir.exists() {
-        std::fs::create_dir_all(&task_dir).unwrap_or_else(|e| {
-            eprintln!("Error creating task directory: {}", e);
-            eprintln!("Please set TASK_FILE environment variable to a writable location.");
-            std::process::exit(1);
-        });
-    }
+    /// Put a task off to the configured default (`snooze` in config.toml:
+    /// tomorrow, a weekday or an offset), counting how often it was snoozed
+    Snooze {
+        task_num: usize,
+        /// Instead of the default: tomorrow, a weekday such as monday, or +3d
+        #[arg(value_name = "WHEN")]
+        until: Option<String>,
+    },
 
-    task_dir.join("work_log.md")
-}
+    Reschedule {
+        task_num: usize,
+        #[arg(value_parser = parse_date_arg)]
+        date: NaiveDate,
+    },
 
-fn read_lines(path: &PathBuf) -> Vec<String> {
-    if !path.exists() {
-        File::create(path).unwrap_or_else(|e| {
-            eprintln!("Error creating task file at {}: {}", path.display(), e);
-            eprintln!("Please set TASK_FILE environment variable to a writable location.");
-            std::process::exit(1);
-        });
-    }
-    BufReader::new(File::open(path).unwrap_or_else(|e| {
-        eprintln!("Error opening task file at {}: {}", path.display(), e);
-        eprintln!("Please check file permissions or set TASK_FILE environment variable.");
-        std::process::exit(1);
-    }))
-    .lines()
-    .collect::<Result<_, _>>()
-    .unwrap_or_else(|e| {
-        eprintln!("Error reading task file: {}", e);
-        std::process::exit(1);
-    })
-}
+    Note {
+        task_num: usize,
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
 
-fn write_lines(path: &PathBuf, lines: &[String]) {
-    let temp_path = path.with_extension("tmp");
-    let mut file = File::create(&temp_path).unwrap_or_else(|e| {
-        eprintln!("Error creating temporary file: {}", e);
-        eprintln!("Please check directory permissions or set TASK_FILE environment variable.");
-        std::process::exit(1);
-    });
+    Show {
+        #[arg(required_unless_present = "line")]
+        task_num: Option<usize>,
+        /// Show the task on this line of the file (as numbered by 'task all')
+        #[arg(long, conflicts_with = "task_num")]
+        line: Option<usize>,
+    },
 
-    for line in lines {
-        writeln!(file, "{}", line).unwrap_or_else(|e| {
-            eprintln!("Error writing to file: {}", e);
-            std::process::exit(1);
-        });
-    }
+    /// Check the task file for impossible dates, malformed metadata and
+    /// lines that look like tasks but aren't read as one
+    Lint {
+        /// Offer to repair each problem that has an obvious fix
+        #[arg(long)]
+        fix_dates: bool,
+        /// Also rewrite every task line in canonical form: marker order,
+        /// single spaces and strikethrough on cancelled tasks
+        #[arg(long)]
+        fix: bool,
+    },
 
-    file.sync_all().unwrap_or_else(|e| {
-        eprintln!("Error syncing file: {}", e);
-        std::process::exit(1);
-    });
+    Blocked,
 
-    fs::rename(temp_path, path).unwrap_or_else(|e| {
-        eprintln!("Error renaming temporary file: {}", e);
-        std::process::exit(1);
-    });
-}
+    /// Convert every task id (and the references to it) to another strategy
+    Ids {
+        #[arg(value_enum)]
+        strategy: ids::Strategy,
+    },
 
-fn extract_date(line: &str, regex: &Regex) -> Option<NaiveDate> {
-    regex
-        .captures(line)
-        .and_then(|cap| cap.get(1))
-        .and_then(|m| NaiveDate::parse_from_str(m.as_str(), "%Y-%m-%d").ok())
-}
+    /// Park a delegated task until a wake-up date
+    Wait {
+        task_num: usize,
+        #[arg(value_parser = parse_date_arg)]
+        until: NaiveDate,
+    },
 
-fn print_header() {
-    println!("📝 SIMPLE TASK MANAGER 📝");
-    println!("==========================\n");
-    println!(
-        "Emoji Legend: 📅 = Due date   📋 = Creation date   ✅ = Completion date   ❌ = Cancellation date\n"
-    );
-}
+    Waiting,
 
-fn main() {
-    let cli = Cli::parse();
-    let task_file = get_task_file();
+    /// Accept proposed tasks from bots and integrations into the pending
+    /// list; without numbers, list the ones awaiting review
+    Approve {
+        #[arg(value_parser = parse_task_nums, value_name = "TASK_NUMS")]
+        task_nums: Vec<TaskNums>,
+        #[arg(long, conflicts_with = "task_nums")]
+        all: bool,
+    },
 
-    print_header();
+    /// Turn down proposed tasks, cancelling them
+    Reject {
+        #[arg(value_parser = parse_task_nums, value_name = "TASK_NUMS", required = true)]
+        task_nums: Vec<TaskNums>,
+    },
 
-    match cli.command {
-        Some(Commands::Add { date, text }) => {
-            // Get today's date
-            let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
-
-            // Determine due date and task text
-            let (due_date, task_text) = match date {
-                // Date parameter is provided
-                Some(d) => {
-                    // Check if it's a properly formatted date
-                    if d.len() == 10 && d.chars().nth(4) == Some('-') {
-                        // It's a valid date format
-                        (d, text.join(" "))
-                    } else {
-                        // Not a date - it's actually part of the task text
-                        // Prepend it to the rest of the text
-                        let mut full_text = vec![d];
-                        full_text.extend(text);
-                        (today.clone(), full_text.join(" "))
-                    }
-                }
-                // No date parameter, just use today's date
-                None => (today.clone(), text.join(" ")),
-            };
+    Estimate {
+        task_num: usize,
+        #[arg(value_parser = dates::parse_estimate)]
+        duration: i64,
+    },
 
-            // Validate the task text
-            if task_text.is_empty() {
-                eprintln!("Error: Task cannot be empty.");
-                return;
-            }
+    Quickies {
+        /// Longest estimate that counts as quick
+        #[arg(long, value_parser = dates::parse_estimate, default_value = "10m")]
+        max: i64,
+        /// Go through them one by one, marking each as done or skipping it
+        #[arg(long)]
+        batch_done: bool,
+    },
 
-            let task_line = format!("- [ ] 📅 {} 📋 {} {}", due_date, today, task_text);
-            let mut lines = read_lines(&task_file);
-            lines.push(task_line);
-            write_lines(&task_file, &lines);
-            println!("Added task due 📅 {}: {}", due_date, task_text);
-        }
+    /// List pending tasks created more than some days ago, oldest first
+    Stale {
+        /// Age in days past which a task counts as stale
+        #[arg(long, default_value = "30")]
+        days: i64,
+        /// Cancel the stale tasks after confirming
+        #[arg(long)]
+        auto_cancel: bool,
+    },
 
-        Some(Commands::Today) => {
-            let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
-            println!("Tasks due today (📅 {}):", today);
-            let lines = read_lines(&task_file);
-            let mut found = false;
+    /// Tick several pending tasks in a checklist, then complete, cancel
+    /// or postpone them all at once
+    TriageDone,
 
-            for (i, line) in lines
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.contains("- [ ]"))
-            {
-                if let Some(cap) = DUE_DATE_RE.captures(line) {
-                    if cap.get(1).map_or("", |m| m.as_str()) == today {
-                        let display_line = line.strip_prefix("- ").unwrap_or(line);
-                        println!("{} - {}", i + 1, display_line);
-                        found = true;
-                    }
-                }
-            }
-            if !found {
-                println!("No tasks due today.");
-            }
-        }
+    /// Find a pending task by typing part of its text, then complete,
+    /// cancel, edit or show it
+    Pick {
+        /// Text to start the search with
+        query: Option<String>,
+    },
 
-        Some(Commands::Week) => {
-            let today = Local::now().date_naive();
-            let week_later = today + Duration::days(7);
-            println!("Tasks due in the next 7 days:");
-            let lines = read_lines(&task_file);
-            let mut found = false;
+    Start {
+        task_num: usize,
+    },
 
-            for (i, line) in lines
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.contains("- [ ]"))
-            {
-                if let Some(due_date) = extract_date(line, &DUE_DATE_RE) {
-                    if due_date >= today && due_date <= week_later {
-                        let display_line = line.strip_prefix("- ").unwrap_or(line);
-                        println!("{} - {}", i + 1, display_line);
-                        found = true;
-                    }
-                }
-            }
-            if !found {
-                println!("No tasks due this week.");
-            }
-        }
+    Stop,
 
-        Some(Commands::LastWeek { weeks }) => {
-            let today = Local::now().date_naive();
-            let weeks_ago = today - Duration::days(7 * weeks as i64);
-            println!("Tasks completed in the last {} week(s):", weeks);
-            let lines = read_lines(&task_file);
-            let mut found = false;
+    Pomo {
+        task_num: usize,
+        /// Minutes of focused work per pomodoro
+        #[arg(long, default_value = "25")]
+        work: u64,
+        /// Minutes of break after each pomodoro
+        #[arg(long = "break", default_value = "5")]
+        rest: u64,
+        #[arg(long, default_value = "1")]
+        rounds: u32,
+        /// Send a desktop notification when each work or break period ends
+        #[arg(long)]
+        notify: bool,
+    },
 
-            for (i, line) in lines
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.contains("- [x]"))
-            {
-                if let Some(completion_date) = extract_date(line, &COMPLETION_DATE_RE) {
-                    if completion_date >= weeks_ago && completion_date <= today {
-                        let display_line = line.strip_prefix("- ").unwrap_or(line);
-                        println!("{} - {}", i + 1, display_line);
-                        found = true;
-                    }
-                }
-            }
-            if !found {
-                println!("No tasks completed in the last {} week(s).", weeks);
-            }
-        }
+    Active,
 
-        Some(Commands::Pending) => {
-            println!("Pending tasks:");
-            let lines = read_lines(&task_file);
-            let mut pending = lines
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.contains("- [ ]"))
-                .collect::<Vec<_>>();
+    Time {
+        #[arg(long)]
+        week: bool,
+        #[arg(long, conflicts_with = "week")]
+        month: bool,
+    },
 
-            if pending.is_empty() {
-                println!("No pending tasks.");
-            } else {
-                pending.reverse();
-                for (i, (_, line)) in pending.iter().enumerate() {
-                    let display_line = line.strip_prefix("- ").unwrap_or(line);
-                    println!("{} - {}", i + 1, display_line);
-                }
-            }
-        }
+    #[command(alias = "l", alias = "list")]
+    All,
 
-        Some(Commands::Done { task_nums }) => {
-            let mut lines = read_lines(&task_file);
+    Wins {
+        #[arg(long)]
+        month: bool,
+        #[arg(long, value_name = "N")]
+        random: Option<usize>,
+    },
 
-            if task_nums.is_empty() {
-                println!("Completed tasks:");
-                let completed = lines
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, l)| l.contains("- [x]"))
-                    .collect::<Vec<_>>();
+    Cohorts {
+        #[arg(long, default_value = "8")]
+        weeks: u32,
+    },
 
-                if completed.is_empty() {
-                    println!("No completed tasks.");
-                } else {
-                    for (i, (_, line)) in completed.iter().enumerate() {
-                        let display_line = line.strip_prefix("- ").unwrap_or(line);
-                        println!("{} - {}", i + 1, display_line);
-                    }
-                }
-                return;
-            }
+    Stats {
+        #[arg(long, default_value = "8")]
+        weeks: u32,
+        #[arg(long, conflicts_with = "weeks")]
+        week: bool,
+    },
 
-            let mut pending = lines
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.contains("- [ ]"))
-                .map(|(i, _)| i)
-                .collect::<Vec<_>>();
+    /// Count overdue, due today and pending tasks, for a shell prompt or
+    /// status bar
+    Status {
+        /// One line such as "3 due today, 1 overdue"
+        #[arg(long)]
+        short: bool,
+        /// JSON for a waybar custom module
+        #[arg(long, conflicts_with = "short")]
+        json: bool,
+    },
 
-            pending.reverse();
+    /// Chart how many tasks were open each day, to see whether the backlog
+    /// is shrinking or growing
+    Burndown {
+        #[arg(long, default_value = "8")]
+        weeks: u32,
+    },
 
-            let completion_date = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    /// Show current and longest streaks for each recurring task, with the
+    /// days it was done
+    Habits {
+        #[arg(long, default_value = "4")]
+        weeks: u32,
+    },
 
-            for &task_num in &task_nums {
-                if task_num == 0 || task_num > pending.len() {
-                    println!(
-                        "Error: Task number out of range. Run 'task pending' to see available tasks."
-                    );
-                    continue;
-                }
+    Nag {
+        /// Only list critically overdue tasks, without notifications
+        #[arg(long)]
+        no_notify: bool,
+    },
 
-                let line_idx = pending[task_num - 1];
-                lines[line_idx] =
-                    lines[line_idx].replace("- [ ]", &format!("- [x] ✅ {}", completion_date));
-                println!("Task {} marked as completed", task_num);
-            }
+    Notify {
+        /// Also remind about tasks due within this many days (overrides config)
+        #[arg(long, value_name = "DAYS")]
+        lead: Option<i64>,
+        /// Only list what is due, without notifications
+        #[arg(long)]
+        no_notify: bool,
+    },
 
-            write_lines(&task_file, &lines);
-        }
+    /// Serve a small JSON API to list, add, complete and cancel tasks
+    Serve {
+        /// Address and port to listen on (default 127.0.0.1:7878)
+        #[arg(long, value_name = "ADDR")]
+        bind: Option<String>,
+        /// The token clients must send (overrides the config)
+        #[arg(long)]
+        token: Option<String>,
+        /// Only allow listing tasks
+        #[arg(long)]
+        read_only: bool,
+    },
 
-        Some(Commands::Cancel { task_num }) => {
-            let mut lines = read_lines(&task_file);
+    /// Keep the agenda on screen, redrawn whenever the task file changes
+    Watch {
+        /// Seconds between checks of the task file
+        #[arg(long, default_value = "2", value_name = "SECS")]
+        interval: u64,
+        /// Only redraw the agenda, without due-soon notifications
+        #[arg(long)]
+        no_notify: bool,
+    },
 
-            if task_num.is_none() {
-                println!("Cancelled tasks:");
-                let cancelled = lines
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, l)| l.contains("- [-] ❌"))
-                    .collect::<Vec<_>>();
+    Archive {
+        /// Only archive tasks with this #tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Archive file to append to (default: archive.md in the task directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
 
-                if cancelled.is_empty() {
-                    println!("No cancelled tasks.");
-                } else {
-                    for (i, (_, line)) in cancelled.iter().enumerate() {
-                        let display_line = line.strip_prefix("- ").unwrap_or(line);
-                        println!("{} - {}", i + 1, display_line);
-                    }
-                }
-                return;
-            }
+    Export {
+        /// Rewrite the exported tasks in this layout, e.g. obsidian-dataview,
+        /// or write them for another app
+        #[arg(value_enum)]
+        format: Option<ExportFormat>,
+        /// Only export tasks with this #tag
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long)]
+        out: PathBuf,
+    },
 
-            let task_num = task_num.unwrap();
-            let mut pending = lines
-                .iter()
-                .enumerate()
-                .filter(|(_, l)| l.contains("- [ ]"))
-                .map(|(i, _)| i)
-                .collect::<Vec<_>>();
+    /// Run a command in a sandbox with tasks as JSON on stdin, a minimal
+    /// environment and a time limit, logging its output to hooks.log
+    Shell {
+        /// Pass just this pending task instead of every pending task
+        #[arg(long, value_name = "TASK_NUM")]
+        task: Option<usize>,
+        /// Seconds before the command is killed
+        #[arg(long, default_value = "30")]
+        timeout: u64,
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
 
-            pending.reverse();
+    Report {
+        #[command(subcommand)]
+        kind: ReportKind,
+    },
 
-            if task_num == 0 || task_num > pending.len() {
-                eprintln!(
-                    "Error: Task number out of range. Run 'task pending' to see available tasks."
-                );
-                return;
-            }
+    /// Run a listing as it would have looked at the end of a past day, e.g.
+    /// `task as-of 2025-09-01 pending`
+    AsOf {
+        #[arg(value_parser = parse_date_arg)]
+        date: NaiveDate,
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
 
-            let line_idx = pending[task_num - 1];
-            let line = &lines[line_idx];
+    Sync {
+        #[command(subcommand)]
+        backend: SyncBackend,
+    },
 
-            let date_part = DATE_PART_RE
-                .captures(line)
-                .and_then(|cap| cap.get(1))
-                .map_or("", |m| m.as_str());
+    /// Bring tasks over from another app
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
 
-            let task_text = line.splitn(2, date_part).nth(1).unwrap_or("").trim();
+    /// Turn TODO and FIXME comments in a codebase into tasks, skipping those
+    /// an earlier scan already added
+    Scan {
+        /// Directory (or file) to scan
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+        /// Comment markers to look for, separated by |
+        #[arg(long, default_value = "TODO|FIXME")]
+        pattern: String,
+    },
 
-            let cancellation_date = Local::now().date_naive().format("%Y-%m-%d").to_string();
-            lines[line_idx] = format!(
-                "- [-] ❌ {} {} ~~{}~~",
-                cancellation_date, date_part, task_text
-            );
+    /// Run commands read from stdin, one per line, as a single change to
+    /// the task file: all of them or, if one fails, none
+    Batch,
 
-            write_lines(&task_file, &lines);
-            println!("Task {} marked as cancelled", task_num);
-        }
+    Init {
+        #[arg(long, value_enum)]
+        template: Option<scaffold::Template>,
+    },
 
-        Some(Commands::All) => {
-            println!("All tasks:");
-            let lines = read_lines(&task_file);
+    /// Print a man page for task, with every command's examples
+    Man,
 
-            if lines.is_empty() {
-                println!("No tasks found.");
-            } else {
-                for (i, line) in lines.iter().enumerate() {
-                    let display_line = line.strip_prefix("- ").unwrap_or(line);
-                    println!("{} - {}", i + 1, display_line);
-                }
-            }
-        }
+    /// Show the task lists and how many pending tasks each has
+    Lists,
 
-        None => {
-            println!("Usage: task [command] [args]");
-            println!("Commands:");
-            println!(
-                "  add|a [date] \"<text>\"  Add a new task with optional due date (YYYY-MM-DD), defaults to today"
-            );
-            println!("  today|t              List tasks due today");
-            println!("  week|w               List tasks due in the next 7 days");
-            println!(
-                "  lastweek|lw [weeks]  List tasks completed in the last X weeks (default: 1)"
-            );
-            println!("  pending|p            List all pending tasks");
-            println!("  done|d [num]         Mark task as complete or list completed tasks");
-            println!("  cancel|c [num]       Mark task as cancelled or list cancelled tasks");
-            println!("  all|list|l           List all tasks");
-            println!("");
-            println!("Examples:");
-            println!("  task add \"Buy groceries\"                 # Add task due today");
-            println!("  task add 2025-09-15 \"Finish project\"     # Add task with due date");
-            println!("  task pending                            # List pending tasks");
-            println!("  task done 2                             # Mark task #2 as complete");
-        }
+    /// Show the `## ` headings the task file is divided into and how many
+    /// pending tasks each has
+    Sections,
+
+    /// Move tasks, with their notes, to another task list
+    Move {
+        #[command(flatten)]
+        selection: Selection,
+        /// The list to move them to; `default` is the main task file
+        #[arg(long, value_name = "LIST")]
+        to: String,
+    },
+
+    /// Show where the task file, config file and backups are
+    Path,
+
+    /// Show the task file in use and why, how it's stored, its size and
+    /// task counts, for working out why two machines disagree
+    Info,
+
+    /// Keep listings and task numbers to the tasks matching a saved filter:
+    /// `context define work +work or +office`, `context work`, `context none`
+    Context {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+
+    /// Move tasks, with their notes, to the trash
+    Delete {
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// List deleted and cancelled tasks, numbered for `task restore`
+    Trash,
+
+    /// Put a task from `task trash` back as pending, or the task file back
+    /// as it was in a backup, e.g. `task restore 20251015-143000`
+    Restore {
+        /// A number from `task trash`, or a backup timestamp
+        #[arg(value_name = "NUM|TIMESTAMP")]
+        what: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupAction {
+    /// Backups of the task file taken before each change, newest first
+    List,
+}
+
+#[derive(Subcommand)]
+enum ReportKind {
+    /// Past seven days: completed by day, carried over, added and upcoming
+    Weekly {
+        #[arg(long)]
+        markdown: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportSource {
+    /// A project exported from Todoist as CSV
+    Todoist {
+        file: PathBuf,
+        /// Tag every imported task with this project (default: the file name)
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Tasks from Taskwarrior's `task export` JSON (default: stdin)
+    Taskwarrior { file: Option<PathBuf> },
+}
+
+/// What `task export` writes: the task file in one of its layouts, or
+/// another app's format.
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Obsidian,
+    Legacy,
+    #[value(alias = "obsidian-dataview")]
+    Dataview,
+    /// Todoist's CSV template, for its "Import from template"
+    Todoist,
+}
+
+#[derive(Subcommand)]
+enum SyncBackend {
+    /// Two-way merge with a task file on another machine, e.g.
+    /// user@host:~/.local/share/task
+    Ssh { remote: String },
+    /// Commit the task file to a git repository in the task directory and
+    /// pull/push a remote (default: git_remote under [sync])
+    Git { remote: Option<String> },
+    /// Two-way sync with a CalDAV task list (Nextcloud, Fastmail, ...)
+    /// (default: caldav_url under [sync])
+    Caldav { url: Option<String> },
+}
+
+/// Which pending tasks `done` and `cancel` act on: numbers and ranges from
+/// `task pending` (`2 5 7`, `1-4,7`) or text from a task, plus optional bulk
+/// selectors.
+#[derive(Args)]
+struct Selection {
+    /// Task numbers or ranges, or text that only one pending task contains
+    #[arg(value_parser = parse_task_arg, value_name = "TASK")]
+    tasks: Vec<TaskArg>,
+    /// Every pending task whose due date has passed
+    #[arg(long)]
+    all_overdue: bool,
+    /// Every pending task with this #tag
+    #[arg(long)]
+    tag: Option<String>,
+}
+
+#[derive(Clone)]
+struct TaskNums(Vec<usize>);
+
+/// A task given to `done` or `cancel`: numbers, or text to find it by.
+#[derive(Clone)]
+enum TaskArg {
+    Nums(TaskNums),
+    Text(String),
+}
+
+impl Selection {
+    fn is_empty(&self) -> bool {
+        self.tasks.is_empty() && !self.all_overdue && self.tag.is_none()
+    }
+
+    /// Resolve to `(task number, line index)` pairs, in the order given and
+    /// without duplicates. Out-of-range numbers are reported and skipped,
+    /// and the second value says whether there were any.
+    fn resolve(&self, lines: &[String], today: NaiveDate) -> (Vec<(usize, usize)>, bool) {
+        let pending = pending_indices(lines, today);
+        let mut nums = Vec::new();
+        let mut missing = false;
+
+        let given = self.tasks.iter().flat_map(|arg| match arg {
+            TaskArg::Nums(nums) => nums.0.iter().map(|&n| Ok(n)).collect::<Vec<_>>(),
+            TaskArg::Text(text) => vec![find_by_text(lines, &pending, text)],
+        });
+        for task_num in given {
+            let task_num = match task_num {
+                Ok(task_num) => task_num,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    missing = true;
+                    continue;
+                }
+            };
+            if task_line(&pending, task_num).is_none() {
+                eprintln!(
+                    "Error: Task number {} out of range. Run 'task pending' to see available tasks.",
+                    task_num
+                );
+                missing = true;
+                continue;
+            }
+            nums.push(task_num);
+        }
+
+        let tag = self.tag.as_deref().map(|t| t.trim_start_matches('#'));
+        for (i, &line_idx) in pending.iter().enumerate() {
+            let Some(task) = Task::parse(&lines[line_idx]) else {
+                continue;
+            };
+            let overdue = self.all_overdue && task.due.is_some_and(|d| d < today);
+            let tagged = tag.is_some_and(|tag| task.tags().contains(&tag));
+            if overdue || tagged {
+                nums.push(i + 1);
+            }
+        }
+
+        let mut seen = Vec::new();
+        let selected = nums
+            .into_iter()
+            .filter(|n| {
+                let new = !seen.contains(n);
+                seen.push(*n);
+                new
+            })
+            .map(|n| (n, pending[n - 1]))
+            .collect();
+        (selected, missing)
+    }
+}
+
+impl Commands {
+    /// Whether the command rewrites the task file and so must hold the lock.
+    fn mutates(&self) -> bool {
+        match self {
+            Commands::Add { .. }
+            | Commands::Postpone { .. }
+            | Commands::Reschedule { .. }
+            | Commands::Snooze { .. }
+            | Commands::Note { .. }
+            | Commands::Archive { .. }
+            | Commands::Estimate { .. }
+            | Commands::Wait { .. }
+            | Commands::Reject { .. }
+            | Commands::Ids { .. }
+            | Commands::Lint {
+                fix_dates: true, ..
+            }
+            | Commands::Lint { fix: true, .. }
+            | Commands::Quickies {
+                batch_done: true, ..
+            }
+            | Commands::Stale {
+                auto_cancel: true, ..
+            }
+            | Commands::TriageDone
+            | Commands::Pick { .. }
+            | Commands::Restore { .. }
+            | Commands::Reopen { .. }
+            | Commands::Move { .. }
+            | Commands::Delete { .. }
+            | Commands::Promote { .. }
+            | Commands::Demote { .. }
+            | Commands::Sync { .. }
+            | Commands::Import { .. }
+            | Commands::Scan { .. }
+            | Commands::Batch => true,
+            Commands::Done { selection, .. } | Commands::Cancel { selection, .. } => {
+                !selection.is_empty()
+            }
+            Commands::Approve { task_nums, all } => *all || !task_nums.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Whether the command can change the task file, so hooks should run
+    /// around its saves. Besides those that hold the lock throughout, this
+    /// includes commands that take it themselves only to write.
+    fn writes(&self) -> bool {
+        self.mutates() || matches!(self, Commands::Capture { .. } | Commands::Pomo { .. })
+    }
+
+    /// Whether the command only reads the task file, so `as-of` can replay
+    /// it on a past state.
+    fn replayable(&self) -> bool {
+        match self {
+            Commands::Today
+            | Commands::Week { .. }
+            | Commands::Due { .. }
+            | Commands::Agenda
+            | Commands::Someday
+            | Commands::Month { .. }
+            | Commands::LastWeek { .. }
+            | Commands::LastMonth { .. }
+            | Commands::Review { .. }
+            | Commands::Pending { .. }
+            | Commands::Show { .. }
+            | Commands::Blocked
+            | Commands::Sections
+            | Commands::Stale {
+                auto_cancel: false, ..
+            }
+            | Commands::Waiting
+            | Commands::All
+            | Commands::Wins { .. }
+            | Commands::Cohorts { .. }
+            | Commands::Stats { .. }
+            | Commands::Burndown { .. }
+            | Commands::Habits { .. } => true,
+            Commands::Done { .. } | Commands::Cancel { .. } => !self.mutates(),
+            _ => false,
+        }
+    }
+}
+
+/// Parse `3`, `1-4` or comma-separated mixes like `1-4,7`.
+fn parse_task_nums(s: &str) -> Result<TaskNums, String> {
+    let invalid = || {
+        format!(
+            "invalid task number or range '{}' (expected e.g. 3, 1-4 or 1-4,7)",
+            s
+        )
+    };
+    let mut nums = Vec::new();
+    for part in s.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let from = from.trim().parse::<usize>().map_err(|_| invalid())?;
+                let to = to.trim().parse::<usize>().map_err(|_| invalid())?;
+                if from > to {
+                    return Err(invalid());
+                }
+                nums.extend(from..=to);
+            }
+            None => nums.push(part.trim().parse::<usize>().map_err(|_| invalid())?),
+        }
+    }
+    Ok(TaskNums(nums))
+}
+
+/// Numbers and ranges as for [`parse_task_nums`]; anything else is text to
+/// find a task by.
+fn parse_task_arg(s: &str) -> Result<TaskArg, String> {
+    if s.chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '-' | ',' | ' '))
+    {
+        parse_task_nums(s).map(TaskArg::Nums)
+    } else {
+        Ok(TaskArg::Text(s.to_string()))
+    }
+}
+
+/// The number of the pending task whose text contains `text`, ignoring
+/// case. It must be the only one, unless its whole text is `text`;
+/// otherwise the error lists the candidates.
+fn find_by_text(lines: &[String], pending: &[usize], text: &str) -> Result<usize, String> {
+    let needle = text.trim().to_lowercase();
+    let found = pending
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &idx)| Task::parse(&lines[idx]).map(|t| (i + 1, t)))
+        .filter(|(_, t)| t.text.to_lowercase().contains(&needle))
+        .collect::<Vec<_>>();
+    let exact = found
+        .iter()
+        .filter(|(_, t)| t.text.to_lowercase() == needle)
+        .collect::<Vec<_>>();
+    if let [(task_num, _)] = found.as_slice() {
+        return Ok(*task_num);
+    }
+    if let [(task_num, _)] = exact.as_slice() {
+        return Ok(*task_num);
+    }
+    if found.is_empty() {
+        return Err(format!("No pending task matches \"{}\".", text));
+    }
+    let candidates = found
+        .iter()
+        .map(|(task_num, t)| format!("\n  {} - {}", task_num, t.text))
+        .collect::<String>();
+    Err(format!(
+        "\"{}\" matches {} pending tasks; give its number or more of its text:{}",
+        text,
+        found.len(),
+        candidates
+    ))
+}
+
+/// Where an added task comes from: `--source`, or `TASK_SOURCE` so an
+/// integration can mark everything it runs without changing commands.
+fn task_source(source: Option<String>) -> Option<String> {
+    source
+        .or_else(|| std::env::var("TASK_SOURCE").ok())
+        .filter(|s| !s.is_empty())
+}
+
+/// Due date, time and text of a task given to `task add` as an optional
+/// date (with a time as `2025-09-15T14:00`), then words of text. Exits on a
+/// malformed date.
+fn add_input(
+    date: Option<String>,
+    text: Vec<String>,
+    strict: bool,
+) -> (Option<NaiveDate>, Option<NaiveTime>, String) {
+    match date {
+        // Strict mode: an optional ISO date, then exactly one text argument
+        Some(d) if strict => match text.as_slice() {
+            [] => (None, None, d),
+            [t] => {
+                let Some((due, time)) = dates::parse_due(&d, parse_date) else {
+                    eprintln!(
+                        "Error: '{}' is not a YYYY-MM-DD date or YYYY-MM-DDTHH:MM time (strict mode).",
+                        d
+                    );
+                    std::process::exit(1);
+                };
+                (Some(due), time, t.clone())
+            }
+            _ => {
+                eprintln!("Error: Task text must be a single quoted argument (strict mode).");
+                std::process::exit(1);
+            }
+        },
+        // Date parameter is provided
+        Some(d) => {
+            let shaped = |s: &str| s.len() == 10 && s.chars().nth(4) == Some('-');
+            if let Some((due, time)) = dates::parse_due(&d, dates::parse_input) {
+                (Some(due), time, text.join(" "))
+            } else if shaped(&d) || d.split_once('T').is_some_and(|(day, _)| shaped(day)) {
+                // Shaped like a date, so it must be a real one
+                eprintln!(
+                    "Error: Invalid date '{}'. Use {}, with THH:MM after it for a time.",
+                    d,
+                    dates::expected()
+                );
+                std::process::exit(EXIT_USAGE);
+            } else {
+                // Not a date - it's actually part of the task text
+                // Prepend it to the rest of the text
+                let mut full_text = vec![d];
+                full_text.extend(text);
+                (None, None, full_text.join(" "))
+            }
+        }
+        // No date parameter, defaults to today's date below
+        None => (None, None, text.join(" ")),
+    }
+}
+
+/// Resolve numbers from the proposed list to `(number, line index)` pairs,
+/// or report the first one out of range.
+fn select_proposed(
+    queue: &[usize],
+    task_nums: &[TaskNums],
+    all: bool,
+) -> Option<Vec<(usize, usize)>> {
+    if all {
+        return Some(queue.iter().enumerate().map(|(i, &l)| (i + 1, l)).collect());
+    }
+    let mut selected = Vec::new();
+    for &n in task_nums.iter().flat_map(|t| &t.0) {
+        if n == 0 || n > queue.len() {
+            eprintln!(
+                "Error: Task number {} out of range. Run 'task approve' to see proposed tasks.",
+                n
+            );
+            return None;
+        }
+        if !selected.iter().any(|(m, _)| *m == n) {
+            selected.push((n, queue[n - 1]));
+        }
+    }
+    Some(selected)
+}
+
+fn parse_priority(s: &str) -> Result<Priority, String> {
+    Priority::from_name(s).ok_or_else(|| {
+        format!(
+            "unknown priority '{}' (expected highest, high, medium, normal, low or lowest)",
+            s
+        )
+    })
+}
+
+fn parse_date_arg(s: &str) -> Result<NaiveDate, String> {
+    dates::parse_input(s)
+        .ok_or_else(|| format!("invalid date '{}' (expected {})", s, dates::expected()))
+}
+
+/// The day to record a completion or cancellation on: `on` if given, which
+/// can't be later than today, else today.
+fn backdated(on: Option<NaiveDate>, today: NaiveDate) -> NaiveDate {
+    match on {
+        Some(date) if date > today => {
+            eprintln!(
+                "Error: --on {} is in the future; tasks can only be finished on or before today.",
+                dates::show(date)
+            );
+            std::process::exit(EXIT_USAGE);
+        }
+        Some(date) => date,
+        None => today,
+    }
+}
+
+/// Line indices of pending tasks, critically overdue ones first, then most
+/// urgent first (newest first among equals), with tasks whose start date
+/// hasn't arrived at the end. Task numbers accepted by `done` and `cancel`
+/// index into this list.
+fn pending_indices(lines: &[String], today: NaiveDate) -> Vec<usize> {
+    let shown = sections::shown(lines);
+    numbering(
+        lines,
+        today,
+        shown.as_deref(),
+        context::shows,
+        nag::is_highlighted,
+    )
+}
+
+/// [`pending_indices`] with the lines in the `--section` given by `shown`,
+/// the tasks the context shows by `shows` and those numbered first, being
+/// marked 🚨, by `highlighted`.
+fn numbering(
+    lines: &[String],
+    today: NaiveDate,
+    shown: Option<&[bool]>,
+    shows: impl Fn(&Task) -> bool,
+    highlighted: impl Fn(&Task) -> bool,
+) -> Vec<usize> {
+    let mut pending = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| shown.is_none_or(|s| s[*i]))
+        .filter_map(|(i, l)| Task::parse(l).map(|t| (i, t)))
+        .filter(|(_, t)| t.status == Status::Pending && shows(t))
+        .map(|(i, t)| (i, urgency::score(&t), t))
+        .collect::<Vec<_>>();
+    pending.reverse();
+    pending.sort_by(|(_, a_score, a), (_, b_score, b)| {
+        (a.is_deferred(today), !highlighted(a))
+            .cmp(&(b.is_deferred(today), !highlighted(b)))
+            .then(b_score.total_cmp(a_score))
+    });
+    pending.into_iter().map(|(i, _, _)| i).collect()
+}
+
+/// The line of the pending task listings number `task_num`, as every
+/// command taking a task number looks it up.
+fn task_line(pending: &[usize], task_num: usize) -> Option<usize> {
+    pending.get(task_num.checked_sub(1)?).copied()
+}
+
+/// The line of pending task `task_num`, exiting if there's no such task.
+fn pending_line(pending: &[usize], task_num: usize) -> usize {
+    task_line(pending, task_num).unwrap_or_else(|| {
+        eprintln!(
+            "Error: Task number {} out of range. Run 'task pending' to see available tasks.",
+            task_num
+        );
+        std::process::exit(EXIT_NOT_FOUND);
+    })
+}
+
+/// How listings refer to line `line_idx`: by its task number when it is a
+/// pending task, as `done`, `cancel` and the rest accept, otherwise as `L`
+/// and its line number, which `show --line` takes.
+fn task_ref(pending: &[usize], line_idx: usize) -> String {
+    match pending.iter().position(|&p| p == line_idx) {
+        Some(n) => (n + 1).to_string(),
+        None => format!("L{}", line_idx + 1),
+    }
+}
+
+fn tasks_with_status(lines: &[String], status: Status) -> Vec<(usize, &String, Task)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| Task::parse(l).map(|t| (i, l, t)))
+        .filter(|(_, _, t)| t.status == status)
+        .collect()
+}
+
+/// Ask a yes/no question on the terminal; pressing Enter accepts.
+fn confirm(question: &str) -> bool {
+    print!("{} [Y/n] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes")
+}
+
+/// Ask a yes/no/quit question on the terminal; anything but `y` skips, and
+/// `q` (or end of input) returns `None`.
+fn ask(question: &str) -> Option<bool> {
+    print!("{} [y/N/q] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => return None,
+        Ok(_) => {}
+    }
+    match answer.trim().to_lowercase().as_str() {
+        "q" | "quit" => None,
+        "y" | "yes" => Some(true),
+        _ => Some(false),
+    }
+}
+
+/// Apply `adjust` to the priority of each numbered pending task.
+fn change_priority(
+    store: &mut dyn TaskStore,
+    format: TaskFormat,
+    task_nums: &[usize],
+    today: NaiveDate,
+    diff: diff::Mode,
+    adjust: impl Fn(Priority) -> Priority,
+) {
+    let mut lines = store.read();
+    let before = lines.clone();
+    let pending = pending_indices(&lines, today);
+    let mut changed = false;
+    let mut missing = false;
+
+    for &task_num in task_nums {
+        let Some(line_idx) = task_line(&pending, task_num) else {
+            eprintln!(
+                "Error: Task number {} out of range. Run 'task pending' to see available tasks.",
+                task_num
+            );
+            missing = true;
+            continue;
+        };
+        let Some(mut task) = Task::parse(&lines[line_idx]) else {
+            continue;
+        };
+        let priority = adjust(task.priority);
+        if priority == task.priority {
+            println!("Task {} is already {} priority", task_num, priority.name());
+            continue;
+        }
+        task.priority = priority;
+        lines[line_idx] = task.to_line(format);
+        changed = true;
+        println!("Task {} is now {} priority", task_num, priority.name());
+    }
+
+    if changed {
+        store.write(&before, &lines, diff.wanted(task_nums.len() > 1));
+    }
+    if missing {
+        std::process::exit(EXIT_NOT_FOUND);
+    }
+}
+
+/// Change pending task `task_num` with `edit` and write it back, as the
+/// commands that set one task's fields do. What `edit` returns is passed on;
+/// an error from it is printed and nothing is written.
+fn edit_task<T>(
+    store: &mut dyn TaskStore,
+    task_num: usize,
+    today: NaiveDate,
+    format: TaskFormat,
+    show_diff: bool,
+    edit: impl FnOnce(&mut Task) -> Result<T, String>,
+) -> Option<T> {
+    let mut lines = store.read();
+    let line_idx = pending_line(&pending_indices(&lines, today), task_num);
+    let mut task = Task::parse(&lines[line_idx])?;
+    let edited = edit(&mut task).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(EXIT_USAGE);
+    });
+    let before = lines.clone();
+    lines[line_idx] = task.to_line(format);
+    store.write(&before, &lines, show_diff);
+    Some(edited)
+}
+
+/// Put `task` off by `rule` (`tomorrow`, a weekday, `+1w`…), counting the
+/// snooze. Returns the new due date and how often it's been snoozed.
+fn snooze(task: &mut Task, rule: &str, today: NaiveDate) -> Result<(NaiveDate, u32), String> {
+    // A task due later still moves on from its own date
+    let from = task.due.map_or(today, |due| due.max(today));
+    let new_due = dates::snooze_until(rule, from)?;
+    let snoozes = task.snoozes() + 1;
+    task.due = Some(new_due);
+    task.set_field("snoozed", &snoozes.to_string());
+    Ok((new_due, snoozes))
+}
+
+/// Print the tasks that were waiting on something in `before` but no longer
+/// are in `after`.
+fn announce_unblocked(before: &[String], after: &[String]) {
+    let parse = |lines: &[String]| {
+        lines
+            .iter()
+            .filter_map(|l| Task::parse(l))
+            .collect::<Vec<_>>()
+    };
+    let (previous, tasks) = (parse(before), parse(after));
+    for task in &tasks {
+        if deps::is_blocked(task, &previous) && !deps::is_blocked(task, &tasks) {
+            println!("🔓 Unblocked: {}", task.text);
+        }
+    }
+}
+
+/// Mark the selected `(task number, line index)` pairs as done, recording
+/// `note` if given, adding the next occurrence of recurring tasks and
+/// reporting what that unblocks.
+fn complete(
+    lines: &mut Vec<String>,
+    selected: &[(usize, usize)],
+    completion_date: NaiveDate,
+    note: Option<&str>,
+    format: TaskFormat,
+    task_dir: &Path,
+) {
+    let before = lines.clone();
+    let mut next_occurrences = Vec::new();
+
+    for &(task_num, line_idx) in selected {
+        let Some(mut task) = Task::parse(&lines[line_idx]) else {
+            continue;
+        };
+        task.status = Status::Done;
+        task.done = Some(completion_date);
+        if let Some(note) = note {
+            task.set_field("note", note);
+        }
+        lines[line_idx] = task.to_line(format);
+        println!("Task {} marked as completed", task_num);
+        stop_timer_for(task_dir, &task);
+
+        if let Some(next) = recurrence::next_task(&task, completion_date) {
+            if let Some(due) = next.due {
+                println!("Next occurrence due 📅 {}: {}", dates::show(due), next.text);
+            }
+            next_occurrences.push((line_idx, next.to_line(format)));
+        }
+    }
+
+    // Like Obsidian, place each new occurrence directly above the
+    // completed task. Insert from the bottom so indices stay valid.
+    next_occurrences.sort_by_key(|(line_idx, _)| std::cmp::Reverse(*line_idx));
+    for (line_idx, line) in next_occurrences {
+        lines.insert(line_idx, line);
+    }
+
+    announce_unblocked(&before, lines);
+}
+
+/// Mark the selected `(task number, line index)` pairs as cancelled,
+/// recording `reason` and `note` if given, and report what that unblocks.
+fn cancel(
+    lines: &mut [String],
+    selected: &[(usize, usize)],
+    cancellation_date: NaiveDate,
+    reason: Option<&str>,
+    note: Option<&str>,
+    format: TaskFormat,
+    task_dir: &Path,
+) {
+    let before = lines.to_vec();
+    for &(task_num, line_idx) in selected {
+        let Some(mut task) = Task::parse(&lines[line_idx]) else {
+            continue;
+        };
+        task.status = Status::Cancelled;
+        task.cancelled = Some(cancellation_date);
+        if let Some(reason) = reason {
+            task.set_field("reason", reason);
+        }
+        if let Some(note) = note {
+            task.set_field("note", note);
+        }
+        lines[line_idx] = task.to_line(format);
+        println!("Task {} marked as cancelled", task_num);
+        stop_timer_for(task_dir, &task);
+    }
+    announce_unblocked(&before, lines);
+}
+
+/// What `done` or `cancel` records on the tasks it closes.
+struct Closing<'a> {
+    /// `Done` or `Cancelled`
+    status: Status,
+    date: NaiveDate,
+    reason: Option<&'a str>,
+    note: Option<&'a str>,
+}
+
+/// Close the tasks `selection` picks in `store`, as they stand `today`
+/// whatever day is recorded. Returns whether any of the given tasks wasn't
+/// found, or `None` when none was, leaving the task file as it was.
+fn close(
+    store: &mut dyn TaskStore,
+    selection: &Selection,
+    closing: &Closing,
+    today: NaiveDate,
+    format: TaskFormat,
+    task_dir: &Path,
+    diff_mode: diff::Mode,
+) -> Option<bool> {
+    let mut lines = store.read();
+    let (selected, missing) = selection.resolve(&lines, today);
+    if selected.is_empty() {
+        return None;
+    }
+    let before = lines.clone();
+    if closing.status == Status::Cancelled {
+        cancel(
+            &mut lines,
+            &selected,
+            closing.date,
+            closing.reason,
+            closing.note,
+            format,
+            task_dir,
+        );
+    } else {
+        complete(
+            &mut lines,
+            &selected,
+            closing.date,
+            closing.note,
+            format,
+            task_dir,
+        );
+    }
+    store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+    Some(missing)
+}
+
+/// Stop the running timer if it was tracking `task`.
+fn stop_timer_for(task_dir: &Path, task: &Task) {
+    match timer::stop_for(task_dir, task, Local::now().naive_local()) {
+        Ok(Some(entry)) => println!(
+            "⏱ Stopped timer after {}",
+            timer::format_duration(entry.duration(Local::now().naive_local()))
+        ),
+        Ok(None) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Print pending tasks due within `from..=to`, returning whether any matched.
+fn print_due_between(
+    lines: &[String],
+    from: NaiveDate,
+    to: NaiveDate,
+    undated: Undated,
+    today: NaiveDate,
+) -> bool {
+    let due = tasks_with_status(lines, Status::Pending)
+        .into_iter()
+        .filter(|(_, _, task)| {
+            undated
+                .due(task, today)
+                .is_some_and(|due_date| due_date >= from && due_date <= to)
+        })
+        .collect::<Vec<_>>();
+    let found = !due.is_empty();
+    let pending = pending_indices(lines, today);
+    order::list(
+        due,
+        |(_, _, task)| Some(task),
+        |(i, line, _)| format!("{} - {}", task_ref(&pending, *i), display(line)),
+    );
+    found
+}
+
+/// List pending tasks without a due date under a date-filtered view, when the
+/// undated policy asks for a separate section.
+fn print_undated(lines: &[String], undated: Undated) {
+    if undated != Undated::Section {
+        return;
+    }
+    let tasks = tasks_with_status(lines, Status::Pending)
+        .into_iter()
+        .filter(|(_, _, task)| task.due.is_none())
+        .collect::<Vec<_>>();
+    if tasks.is_empty() {
+        return;
+    }
+    println!("\nNo date:");
+    let pending = pending_indices(lines, dates::today());
+    order::list(
+        tasks,
+        |(_, _, task)| Some(task),
+        |(i, line, _)| format!("{} - {}", task_ref(&pending, *i), display(line)),
+    );
+}
+
+/// Print what happened in `from..=to`: tasks completed and cancelled then,
+/// and the tasks created then that are still pending.
+fn print_period(lines: &[String], from: NaiveDate, to: NaiveDate) {
+    let within = |date: Option<NaiveDate>| date.is_some_and(|d| d >= from && d <= to);
+    let completed = tasks_with_status(lines, Status::Done)
+        .into_iter()
+        .filter(|(_, _, task)| within(task.done))
+        .collect::<Vec<_>>();
+    let cancelled = tasks_with_status(lines, Status::Cancelled)
+        .into_iter()
+        .filter(|(_, _, task)| within(task.cancelled))
+        .collect::<Vec<_>>();
+    let created = lines
+        .iter()
+        .filter_map(|l| Task::parse(l))
+        .filter(|task| within(task.created))
+        .count();
+    let open = tasks_with_status(lines, Status::Pending)
+        .into_iter()
+        .filter(|(_, _, task)| within(task.created))
+        .collect::<Vec<_>>();
+    println!(
+        "From {} to {}: {} completed, {} cancelled, {} of {} created still pending",
+        dates::show(from),
+        dates::show(to),
+        completed.len(),
+        cancelled.len(),
+        open.len(),
+        created
+    );
+
+    let pending = pending_indices(lines, dates::today());
+    for (title, rows, refs) in [
+        ("Completed", completed, &[][..]),
+        ("Cancelled", cancelled, &[][..]),
+        ("Created and still pending", open, &pending[..]),
+    ] {
+        println!("\n{} ({}):", title, rows.len());
+        order::list(
+            rows,
+            |(_, _, task)| Some(task),
+            |(i, line, _)| format!("{} - {}", task_ref(refs, *i), display(line)),
+        );
+    }
+}
+
+/// A numbered line of a pending listing. Blocked tasks are dimmed so the
+/// actionable ones stand out.
+fn pending_entry(task_num: usize, marker: &str, line: &str, task: &Task, tasks: &[Task]) -> String {
+    if deps::is_blocked(task, tasks) {
+        let text = dates::localize(strip_bullet(line));
+        format!("{} - {}🔒 {}", task_num, marker, color::blocked(&text))
+    } else {
+        format!("{} - {}{}", task_num, marker, display(line))
+    }
+}
+
+/// A line without its list bullet, `-`, `*` or `+`.
+fn strip_bullet(line: &str) -> &str {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|b| line.strip_prefix(b))
+        .unwrap_or(line)
+}
+
+fn display(line: &str) -> String {
+    let text = dates::localize(strip_bullet(line));
+    let shown = color::paint(line, &text);
+    match Task::parse(line) {
+        Some(task) if nag::is_highlighted(&task) => format!("🚨 {}", shown),
+        _ => shown,
+    }
+}
+
+fn describe_date(date: Option<NaiveDate>, today: NaiveDate) -> String {
+    let Some(date) = date else {
+        return "none".to_string();
+    };
+    let days = (date - today).num_days();
+    let relative = match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        d if d > 0 => format!("in {} days", d),
+        d => format!("{} days ago", -d),
+    };
+    format!("{} {} ({})", date.format("%a"), dates::show(date), relative)
+}
+
+/// Print every parsed detail of the task on `lines[line_idx]` for
+/// `task show`, including the raw line, to help explain why it does or
+/// doesn't turn up in a listing.
+fn show_task(title: &str, task: &Task, lines: &[String], line_idx: usize, today: NaiveDate) {
+    println!("{}: {}", title, task.text);
+    let status = match task.status {
+        Status::Pending => "pending",
+        Status::Done => "done",
+        Status::Cancelled => "cancelled",
+        Status::Waiting => "waiting",
+        Status::Proposed => "proposed",
+    };
+    println!("  Status:     {}", status);
+    if let Some(section) = sections::of(lines, line_idx) {
+        println!("  Section:    {}", section);
+    }
+    println!("  Priority:   {}", task.priority.name());
+    println!(
+        "  Recurs:     {}",
+        task.recurrence.as_deref().unwrap_or("no")
+    );
+
+    let dates = [
+        ("Created", task.created),
+        ("Start", task.start),
+        ("Scheduled", task.scheduled),
+        ("Due", task.due),
+        ("Done", task.done),
+        ("Cancelled", task.cancelled),
+    ];
+    for (label, date) in dates {
+        println!(
+            "  {:<11} {}",
+            format!("{}:", label),
+            describe_date(date, today)
+        );
+    }
+    if let Some(created) = task.created {
+        let end = task.done.or(task.cancelled).unwrap_or(today);
+        println!("  Age:        {} day(s)", (end - created).num_days());
+    }
+
+    let tags = task.tags();
+    if tags.is_empty() {
+        println!("  Tags:       none");
+    } else {
+        let tags = tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>();
+        println!("  Tags:       {}", tags.join(" "));
+    }
+    for (key, value) in &task.fields {
+        println!("  {:<11} {}", format!("{}:", key), value);
+    }
+
+    let notes = task::notes(lines, line_idx);
+    if !notes.is_empty() {
+        println!("  Notes:");
+        for note in notes {
+            println!("    {}", note);
+        }
+    }
+
+    println!("  Line:       {}", line_idx + 1);
+    println!("  Raw:        {}", lines[line_idx]);
+}
+
+/// Overdue, today, next 7 days and undated tasks, numbered as in `pending`.
+fn print_agenda(lines: &[String], today: NaiveDate, undated: Undated) {
+    let tasks = lines
+        .iter()
+        .filter_map(|l| Task::parse(l))
+        .collect::<Vec<_>>();
+    let mut sections = [
+        ("OVERDUE", Vec::new()),
+        ("TODAY", Vec::new()),
+        ("NEXT 7 DAYS", Vec::new()),
+        ("SOMEDAY", Vec::new()),
+    ];
+    // Numbered as in 'task pending', so 'done N' works from here
+    for (i, &line_idx) in pending_indices(lines, today).iter().enumerate() {
+        let Some(task) = Task::parse(&lines[line_idx]) else {
+            continue;
+        };
+        if task.is_deferred(today) {
+            continue;
+        }
+        let section = match undated.due(&task, today) {
+            Some(due) if due < today => 0,
+            Some(due) if due == today => 1,
+            Some(due) if due <= today + Duration::days(7) => 2,
+            Some(_) => continue,
+            None => 3,
+        };
+        sections[section].1.push((i + 1, line_idx, task));
+    }
+
+    for (title, mut entries) in sections {
+        entries.sort_by_key(|(_, _, t)| (std::cmp::Reverse(t.priority), t.due));
+        println!("{} ({})", title, entries.len());
+        for (task_num, line_idx, task) in &order::apply(entries, |(_, _, t)| Some(t)) {
+            println!(
+                "  {}",
+                pending_entry(*task_num, "", &lines[*line_idx], task, &tasks)
+            );
+        }
+        println!();
+    }
+}
+
+fn print_header(format: TaskFormat) {
+    let created = match format {
+        TaskFormat::Obsidian | TaskFormat::Dataview => "➕",
+        TaskFormat::Legacy => "📋",
+    };
+    println!("📝 SIMPLE TASK MANAGER 📝");
+    println!("==========================\n");
+    println!(
+        "Emoji Legend: 📅 = Due date   {} = Creation date   ✅ = Completion date   ❌ = Cancellation date",
+        created
+    );
+    println!(
+        "              ⏳ = Scheduled date   🛫 = Start date   🔁 = Recurrence   🔺⏫🔼🔽⏬ = Priority\n"
+    );
+}
+
+fn main() {
+    let task_dir = get_task_dir();
+    let config = Config::load(&task_dir);
+    // Date arguments are parsed with the configured format
+    if let Err(e) = dates::init(
+        &config.date_format,
+        config.weekday_names,
+        config.week_numbers,
+    ) {
+        eprintln!("Error in config file: {}", e);
+        std::process::exit(1);
+    }
+    storage::init(
+        config.partial_writes_kb,
+        config.compact_every,
+        config.max_removed_percent,
+    );
+    if let Err(e) = crypt::init(&config.encryption) {
+        eprintln!("Error in config file: {}", e);
+        std::process::exit(1);
+    }
+    let batched = std::env::var(storage::BATCH_VAR).is_ok();
+    if !batched {
+        backup::init(&task_dir, &config.backup);
+    }
+    let command = help::annotate(Cli::command());
+    let args =
+        alias::expand(std::env::args().collect(), &config.alias, &command).unwrap_or_else(|e| {
+            eprintln!("Error in config file: {}", e);
+            std::process::exit(1);
+        });
+    // clap uses 2 for usage errors, which means "not found" here
+    let matches = command.try_get_matches_from(args).unwrap_or_else(|e| {
+        let _ = e.print();
+        std::process::exit(if e.use_stderr() { EXIT_USAGE } else { 0 });
+    });
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // Listings taller than the terminal go through the pager, as in git;
+    // the command runs again with its output captured
+    if !cli.no_pager
+        && !batched
+        && cli.command.as_ref().is_none_or(Commands::replayable)
+        && let Some(pager) = pager::command(config.pager.as_deref())
+        && let Some(code) = pager::run(&pager)
+    {
+        std::process::exit(code);
+    }
+
+    if cli.all_lists {
+        if !cli.command.as_ref().is_some_and(Commands::replayable) {
+            eprintln!(
+                "Error: --all-lists only runs listings such as pending, today, due, all or stats."
+            );
+            std::process::exit(EXIT_USAGE);
+        }
+        let args = std::env::args()
+            .skip(1)
+            .filter(|a| a != "--all-lists")
+            .collect::<Vec<_>>();
+        let mut failed = false;
+        for (name, path) in storage::lists(&task_dir) {
+            println!("📂 {}", name);
+            let status = std::env::current_exe().and_then(|exe| {
+                std::process::Command::new(exe)
+                    .arg("--file")
+                    .arg(&path)
+                    .arg("--quiet")
+                    .args(&args)
+                    .status()
+            });
+            failed |= !status.is_ok_and(|s| s.success());
+            println!();
+        }
+        std::process::exit(if failed { EXIT_NOT_FOUND } else { 0 });
+    }
+
+    let list = cli
+        .list
+        .clone()
+        .or_else(|| std::env::var(storage::LIST_VAR).ok())
+        .filter(|l| !l.is_empty());
+    let task_file = match (&cli.file, list) {
+        (Some(path), _) => storage::explicit_task_file(path).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }),
+        (None, Some(name)) => {
+            let create = cli.command.as_ref().is_some_and(Commands::writes);
+            storage::list_file(&task_dir, &name, create).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(EXIT_NOT_FOUND);
+            })
+        }
+        (None, None) => get_task_file(&task_dir),
+    };
+
+    // Prompts ask for the status every few seconds, so while the task file
+    // hasn't changed it is answered without reading the file
+    if let Some(Commands::Status { short, json }) = &cli.command
+        && let Some(summary) = status::cached(&task_file, dates::today())
+    {
+        status::print(&summary, *short, *json);
+        return;
+    }
+
+    if cli.strict {
+        for (i, line) in read_lines(&task_file).iter().enumerate() {
+            if let Err(e) = task::strict_check(line) {
+                eprintln!(
+                    "Error: {}:{}: {} (strict mode)",
+                    task_file.display(),
+                    i + 1,
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let today = dates::today();
+    let diff_mode = diff::Mode::new(cli.show_diff, cli.no_diff);
+    let undated = cli.undated.unwrap_or(config.undated);
+    if let Err(e) = color::init(&config.theme, !cli.no_color, today)
+        .and_then(|_| nag::init(&config.nag, today))
+        .and_then(|_| urgency::init(&config.urgency, today))
+    {
+        eprintln!("Error in config file: {}", e);
+        std::process::exit(1);
+    }
+
+    let mut store = FileStore::new(&task_file, today, config.format, cli.quiet);
+
+    // Held until the end of main so concurrent invocations can't interleave
+    // their read-modify-write cycles
+    let _lock = cli.command.as_ref().filter(|c| c.mutates()).map(|_| {
+        storage::lock(
+            &task_file,
+            std::time::Duration::from_secs(config.lock_timeout),
+        )
+    });
+    if let Some(name) = matches.subcommand_name()
+        && cli.command.as_ref().is_some_and(Commands::writes)
+        && !batched
+    {
+        hooks::init(&task_dir, name);
+    }
+
+    // The banner heads the help screen, and every command only if asked
+    // for; `as-of` already printed it before replaying the command
+    let banner = cli.command.is_none()
+        || (config.banner
+            && !matches!(cli.command, Some(Commands::Man))
+            && std::env::var(dates::AS_OF_VAR).is_err());
+    if banner && !cli.quiet {
+        print_header(config.format);
+    }
+
+    order::init(cli.sort, cli.reverse, cli.limit, cli.group_by);
+    sections::init(cli.section.as_deref(), config.journal.then_some(today));
+
+    // Only listings read the task file through the context and section, so
+    // a filtered view is never written back
+    let active_context = context::init(&task_dir).unwrap_or_else(|e| {
+        eprintln!("Warning: {}; showing every task.", e);
+        None
+    });
+    if cli.command.as_ref().is_some_and(Commands::replayable) {
+        if active_context.is_some() || cli.section.is_some() {
+            storage::read_through_context(&task_file);
+        }
+        if let Some(active) = &active_context
+            && !cli.quiet
+        {
+            println!("🔎 Context {}: {}", active.name, active.filter);
+        }
+        if let Some(section) = &cli.section
+            && !cli.quiet
+        {
+            println!("📂 Section: {}", section);
+        }
+    }
+
+    match cli.command {
+        Some(Commands::Add {
+            date,
+            text,
+            priority,
+            start,
+            scheduled,
+            recur,
+            estimate,
+            after,
+            no_due,
+            source,
+        }) => {
+            // Get today's date
+            let today = dates::today();
+
+            // Determine due date and task text, one task per line of input
+            // for `task add -`
+            let from_stdin = date.as_deref() == Some("-") && text.is_empty();
+            let entries = if from_stdin {
+                let input = std::io::read_to_string(std::io::stdin()).unwrap_or_else(|e| {
+                    eprintln!("Error reading tasks from stdin: {}", e);
+                    std::process::exit(EXIT_USAGE);
+                });
+                input
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(|line| {
+                        if cli.strict {
+                            return add_input(Some(line.to_string()), Vec::new(), true);
+                        }
+                        let mut words = line.split_whitespace().map(String::from);
+                        add_input(words.next(), words.collect(), false)
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                vec![add_input(date, text, cli.strict)]
+            };
+
+            // Validate the task text
+            if from_stdin && entries.is_empty() {
+                eprintln!("Error: No tasks on stdin; give one per line.");
+                std::process::exit(EXIT_USAGE);
+            }
+            if entries.iter().any(|(_, _, text)| text.is_empty()) {
+                eprintln!("Error: Task cannot be empty.");
+                std::process::exit(EXIT_USAGE);
+            }
+
+            if let Some(rule) = &recur
+                && recurrence::next_occurrence(rule, today).is_none()
+            {
+                eprintln!(
+                    "Error: Unknown recurrence '{}'. Use e.g. \"every week\" or \"every 2 days when done\".",
+                    rule
+                );
+                std::process::exit(EXIT_USAGE);
+            }
+
+            // The file is only read when blockers need a 🆔; otherwise the
+            // new tasks are just appended
+            let mut lines = if after.is_empty() {
+                Vec::new()
+            } else {
+                store.read()
+            };
+            let before = lines.clone();
+
+            // Blockers are referenced by 🆔, giving them one if needed
+            let pending = pending_indices(&lines, today);
+            let mut depends_on = Vec::new();
+            for &task_num in &after {
+                let line_idx = pending_line(&pending, task_num);
+                let Some(mut blocker) = Task::parse(&lines[line_idx]) else {
+                    continue;
+                };
+                let id = match &blocker.id {
+                    Some(id) => id.clone(),
+                    None => {
+                        let taken = lines
+                            .iter()
+                            .filter_map(|l| Task::parse(l)?.id)
+                            .collect::<Vec<_>>();
+                        let id = ids::new_id(config.id_strategy, &blocker, &taken);
+                        blocker.id = Some(id.clone());
+                        lines[line_idx] = blocker.to_line(config.format);
+                        id
+                    }
+                };
+                depends_on.push(id);
+            }
+
+            if no_due && entries.iter().any(|(due, _, _)| due.is_some()) {
+                eprintln!("Error: --no-due can't be combined with a due date.");
+                std::process::exit(EXIT_USAGE);
+            }
+
+            // Without an explicit date, a deferred task falls due when it
+            // starts; otherwise offer a date based on how long similar tasks
+            // took in the past
+            let deferred_due = start.filter(|s| *s > today);
+            let source = task_source(source);
+            let mut added = Vec::new();
+            for (explicit_due, time, task_text) in entries {
+                let due_date = explicit_due.or(deferred_due).unwrap_or_else(|| {
+                    // Piped tasks have no one to ask
+                    if cli.strict || no_due || from_stdin {
+                        return today;
+                    }
+                    let history = if after.is_empty() {
+                        store.read()
+                    } else {
+                        lines.clone()
+                    };
+                    let tasks = history
+                        .iter()
+                        .filter_map(|l| Task::parse(l))
+                        .collect::<Vec<_>>();
+                    let Some(hint) = suggest::due_date(&tasks, &task_text, today) else {
+                        return today;
+                    };
+                    let question = format!(
+                        "Similar tasks took ~{} day(s); due {} {}?",
+                        hint.days,
+                        hint.due.format("%A"),
+                        dates::show(hint.due)
+                    );
+                    if std::io::stdin().is_terminal() {
+                        if confirm(&question) {
+                            return hint.due;
+                        }
+                    } else {
+                        println!("Tip: {} Pass the date to 'task add' to use it.", question);
+                    }
+                    today
+                });
+
+                let due_date = (!no_due).then_some(due_date);
+                let mut task = Task::new(task_text, due_date, today);
+                task.priority = priority.unwrap_or_default();
+                task.start = start;
+                task.scheduled = scheduled;
+                task.recurrence = recur.clone();
+                task.depends_on = depends_on.clone();
+                if let Some(minutes) = estimate {
+                    task.set_field("estimate", &dates::format_estimate(minutes));
+                }
+                if let Some(time) = time {
+                    task.set_field("time", &time.format("%H:%M").to_string());
+                }
+                let proposed = source
+                    .as_ref()
+                    .is_some_and(|s| proposals::propose(&mut task, s, &config.proposals));
+
+                added.push((task, proposed));
+            }
+            // All of them in one write, so a pipe can't be half-added
+            let new = added
+                .iter()
+                .map(|(task, _)| task.to_line(config.format))
+                .collect::<Vec<_>>();
+            if after.is_empty() {
+                store.append(&new, diff_mode.wanted(false));
+            } else {
+                sections::place(&mut lines, new);
+                store.write(&before, &lines, diff_mode.wanted(false));
+            }
+            for (task, proposed) in added {
+                if proposed {
+                    println!(
+                        "Proposed task awaiting review ('task approve'): {}",
+                        task.text
+                    );
+                    continue;
+                }
+                match (task.due, task.due_time()) {
+                    (Some(due_date), Some(time)) => println!(
+                        "Added task due 📅 {} {}: {}",
+                        dates::show(due_date),
+                        time.format("%H:%M"),
+                        task.text
+                    ),
+                    (Some(due_date), None) => {
+                        println!("Added task due 📅 {}: {}", dates::show(due_date), task.text)
+                    }
+                    (None, _) => println!("Added someday task: {}", task.text),
+                }
+            }
+        }
+
+        Some(Commands::Capture {
+            text,
+            voice,
+            confirm: review,
+            source,
+        }) => {
+            // Without text, capture one task per line until the end of input
+            let inputs = if voice {
+                let Some(command) = &config.voice_command else {
+                    eprintln!("Error: Set voice_command in config.toml to capture by voice.");
+                    std::process::exit(1);
+                };
+                println!("🎙  Listening...");
+                vec![capture::transcribe(command).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                })]
+            } else if text.is_empty() {
+                if review {
+                    eprintln!("Error: --confirm needs the task on the command line.");
+                    std::process::exit(1);
+                }
+                if std::io::stdin().is_terminal() && !cli.quiet {
+                    println!("Type one task per line; end with Ctrl-D.");
+                }
+                let input = std::io::read_to_string(std::io::stdin()).unwrap_or_else(|e| {
+                    eprintln!("Error reading tasks from stdin: {}", e);
+                    std::process::exit(1);
+                });
+                input
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(String::from)
+                    .collect()
+            } else {
+                vec![text.join(" ")]
+            };
+
+            let source = task_source(source);
+            let mut tasks = Vec::new();
+            for input in &inputs {
+                let parsed = capture::parse(input, today);
+                if parsed.text.is_empty() {
+                    eprintln!("Error: Task cannot be empty.");
+                    std::process::exit(1);
+                }
+                let mut task = Task::new(parsed.text, Some(parsed.due.unwrap_or(today)), today);
+                task.priority = parsed.priority;
+                let proposed = source
+                    .as_ref()
+                    .is_some_and(|s| proposals::propose(&mut task, s, &config.proposals));
+                tasks.push((task, proposed));
+            }
+            if tasks.is_empty() {
+                eprintln!("Error: No tasks on stdin; give one per line.");
+                std::process::exit(1);
+            }
+            if review {
+                if voice {
+                    println!("Heard: {}", inputs[0]);
+                }
+                let question = format!("Add {}?", display(&tasks[0].0.to_line(config.format)));
+                if !confirm(&question) {
+                    println!("Not added.");
+                    return;
+                }
+            }
+
+            // Recording and review can take a while, so lock only to write
+            let _lock = storage::lock(
+                &task_file,
+                std::time::Duration::from_secs(config.lock_timeout),
+            );
+            let new = tasks
+                .iter()
+                .map(|(task, _)| task.to_line(config.format))
+                .collect::<Vec<_>>();
+            store.append(&new, diff_mode.wanted(false));
+            for (task, proposed) in tasks {
+                if proposed {
+                    println!(
+                        "Proposed task awaiting review ('task approve'): {}",
+                        task.text
+                    );
+                    continue;
+                }
+                println!(
+                    "Added task due 📅 {}: {}",
+                    dates::show(task.due.unwrap_or(today)),
+                    task.text
+                );
+            }
+        }
+
+        Some(Commands::Today) => {
+            let today = dates::today();
+            println!("Tasks due today (📅 {}):", dates::show(today));
+            let lines = store.read_open();
+            // Tasks with a time come first, in the order of the day
+            let mut due_today = tasks_with_status(&lines, Status::Pending)
+                .into_iter()
+                .filter(|(_, _, task)| {
+                    undated.due(task, today) == Some(today) && !task.is_deferred(today)
+                })
+                .collect::<Vec<_>>();
+            due_today.sort_by_key(|(_, _, task)| (task.due_time().is_none(), task.due_time()));
+            let found = !due_today.is_empty();
+            let pending = pending_indices(&lines, today);
+            order::list(
+                due_today,
+                |(_, _, task)| Some(task),
+                |(i, line, _)| format!("{} - {}", task_ref(&pending, *i), display(line)),
+            );
+            if !found {
+                println!("No tasks due today.");
+            }
+            print_undated(&lines, undated);
+        }
+
+        Some(Commands::Week {
+            no_overdue,
+            workload,
+        }) => {
+            let today = dates::today();
+            let lines = store.read_open();
+            if workload {
+                let from = if config.week_overdue && !no_overdue {
+                    NaiveDate::MIN
+                } else {
+                    today
+                };
+                let week = today + Duration::days(7);
+                let tasks = tasks_with_status(&lines, Status::Pending)
+                    .into_iter()
+                    .filter_map(|(_, _, task)| {
+                        let due = undated
+                            .due(&task, today)
+                            .filter(|d| (from..=week).contains(d))?;
+                        Some((due, task))
+                    })
+                    .collect::<Vec<_>>();
+                report::workload(&tasks, today, week, config.workday_hours * 60);
+                return;
+            }
+            // Carried in from earlier weeks, so they're planned in too
+            if config.week_overdue && !no_overdue {
+                println!("Overdue:");
+                let yesterday = today - Duration::days(1);
+                if !print_due_between(&lines, NaiveDate::MIN, yesterday, undated, today) {
+                    println!("Nothing overdue.");
+                }
+                println!();
+            }
+            println!("Tasks due in the next 7 days:");
+            if !print_due_between(&lines, today, today + Duration::days(7), undated, today) {
+                println!("No tasks due this week.");
+            }
+            print_undated(&lines, undated);
+        }
+
+        Some(Commands::Due {
+            from,
+            to,
+            next,
+            month,
+        }) => {
+            let today = dates::today();
+            let (from, to) = match month {
+                Some(m) => dates::parse_month(&m, today).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }),
+                None => {
+                    let from = from.unwrap_or(today);
+                    let to = match (to, next) {
+                        (Some(to), _) => to,
+                        (None, Some(span)) => span.after(from).unwrap_or_else(|e| {
+                            eprintln!("Error: --next {}", e);
+                            std::process::exit(EXIT_USAGE);
+                        }),
+                        (None, None) => from + Duration::days(7),
+                    };
+                    (from, to)
+                }
+            };
+            if to < from {
+                eprintln!("Error: --to date is before --from date.");
+                std::process::exit(EXIT_USAGE);
+            }
+
+            println!(
+                "Tasks due from 📅 {} to 📅 {}:",
+                dates::show(from),
+                dates::show(to)
+            );
+            let lines = store.read_open();
+            if !print_due_between(&lines, from, to, undated, today) {
+                println!("No tasks due in this period.");
+            }
+            print_undated(&lines, undated);
+        }
+
+        Some(Commands::Someday) => {
+            println!("Someday (no due date):");
+            let lines = store.read();
+            let someday = pending_indices(&lines, today)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, idx)| Task::parse(&lines[idx]).map(|t| (i + 1, idx, t)))
+                .filter(|(_, _, t)| t.due.is_none())
+                .collect::<Vec<_>>();
+            if someday.is_empty() {
+                println!("No someday tasks.");
+            }
+            order::list(
+                someday,
+                |(_, _, t)| Some(t),
+                |(task_num, line_idx, _)| format!("{} - {}", task_num, display(&lines[*line_idx])),
+            );
+        }
+
+        Some(Commands::Agenda) => print_agenda(&store.read(), today, undated),
+
+        Some(Commands::Month { when }) => {
+            let lines = store.read();
+
+            // A full date drills down into that day
+            if let Some(day) = when.as_deref().and_then(dates::parse_input) {
+                println!("Tasks due on 📅 {} {}:", day.format("%A"), dates::show(day));
+                let pending = pending_indices(&lines, today);
+                let due = lines
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, line)| Task::parse(line).map(|t| (i, line, t)))
+                    .filter(|(_, _, t)| undated.due(t, today) == Some(day))
+                    .collect::<Vec<_>>();
+                let found = !due.is_empty();
+                order::list(
+                    due,
+                    |(_, _, t)| Some(t),
+                    |(i, line, _)| format!("{} - {}", task_ref(&pending, *i), display(line)),
+                );
+                if !found {
+                    println!("No tasks due on this day.");
+                }
+                print_undated(&lines, undated);
+                return;
+            }
+
+            let (first, _) = dates::parse_month(when.as_deref().unwrap_or("this"), today)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error: {} or a YYYY-MM-DD date", e);
+                    std::process::exit(1);
+                });
+            let tasks = lines
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .map(|t| Task {
+                    due: undated.due(&t, today),
+                    ..t
+                })
+                .collect::<Vec<_>>();
+            calendar::month(&tasks, first, today);
+            if undated == Undated::Section {
+                let count = tasks
+                    .iter()
+                    .filter(|t| t.status == Status::Pending && t.due.is_none())
+                    .count();
+                println!("{} pending task(s) have no due date.", count);
+            }
+        }
+
+        Some(Commands::LastWeek { weeks }) => {
+            let today = dates::today();
+            let weeks_ago = today - Duration::days(7 * weeks as i64);
+            println!("Tasks completed in the last {} week(s):", weeks);
+            let lines = store.read();
+            let completed = tasks_with_status(&lines, Status::Done)
+                .into_iter()
+                .filter(|(_, _, task)| task.done.is_some_and(|d| d >= weeks_ago && d <= today))
+                .collect::<Vec<_>>();
+            let found = !completed.is_empty();
+            order::list(
+                completed,
+                |(_, _, task)| Some(task),
+                |(i, line, _)| format!("{} - {}", task_ref(&[], *i), display(line)),
+            );
+            if !found {
+                println!("No tasks completed in the last {} week(s).", weeks);
+            }
+        }
+
+        Some(Commands::LastMonth { months }) => {
+            let from = today
+                .checked_sub_months(chrono::Months::new(months))
+                .unwrap_or(NaiveDate::MIN);
+            print_period(&store.read(), from, today);
+        }
+
+        Some(Commands::Review { from, to }) => {
+            let to = to.unwrap_or(today);
+            if from > to {
+                eprintln!(
+                    "Error: --from {} is after --to {}.",
+                    dates::show(from),
+                    dates::show(to)
+                );
+                std::process::exit(EXIT_USAGE);
+            }
+            print_period(&store.read(), from, to);
+        }
+
+        Some(Commands::Pending {
+            all,
+            since_review,
+            show_urgency,
+        }) => {
+            let review = review::last(&task_dir);
+            match &review {
+                Some(review) if since_review => println!(
+                    "Pending tasks added since the review on {}:",
+                    review.at.format("%Y-%m-%d %H:%M")
+                ),
+                None if since_review => {
+                    eprintln!("Error: No review yet. Run 'task report weekly' first.");
+                    std::process::exit(EXIT_USAGE);
+                }
+                _ => println!("Pending tasks:"),
+            }
+            let lines = store.read_open();
+            let pending = pending_indices(&lines, today);
+            let deferred = pending
+                .iter()
+                .filter(|&&i| Task::parse(&lines[i]).is_some_and(|t| t.is_deferred(today)))
+                .count();
+            // Deferred tasks sort last, so hiding them keeps the numbering
+            let shown = if all {
+                pending.len()
+            } else {
+                pending.len() - deferred
+            };
+
+            let tasks = lines
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            let listed = pending[..shown]
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &line_idx)| {
+                    let task = Task::parse(&lines[line_idx])?;
+                    let new = review.as_ref().is_some_and(|r| r.is_new(&task));
+                    (new || !since_review).then_some((i + 1, line_idx, task, new))
+                })
+                .collect::<Vec<_>>();
+            let listed_any = !listed.is_empty();
+            order::list(
+                listed,
+                |(_, _, task, _)| Some(task),
+                |(task_num, line_idx, task, new)| {
+                    let mut marker = if *new { "✨ " } else { "" }.to_string();
+                    if show_urgency {
+                        marker.insert_str(0, &format!("[{:.1}] ", urgency::score(task)));
+                    }
+                    pending_entry(*task_num, &marker, &lines[*line_idx], task, &tasks)
+                },
+            );
+            if !listed_any && since_review {
+                println!("Nothing new since the last review.");
+            } else if !listed_any {
+                println!("No pending tasks.");
+            }
+            if deferred > 0 && !all {
+                println!(
+                    "({} task(s) not started yet; 'task pending --all' shows them)",
+                    deferred
+                );
+            }
+            let proposed = proposals::queue(&lines).len();
+            if proposed > 0 {
+                println!(
+                    "📥 {} proposed task(s) awaiting review; 'task approve' lists them",
+                    proposed
+                );
+            }
+        }
+
+        Some(Commands::Done {
+            selection,
+            on,
+            note,
+        }) => {
+            if selection.is_empty() {
+                let lines = store.read();
+                if on.is_some() || note.is_some() {
+                    eprintln!("Error: --on and --note need the tasks to complete.");
+                    std::process::exit(EXIT_USAGE);
+                }
+                println!("Completed tasks:");
+                let completed = tasks_with_status(&lines, Status::Done);
+
+                if completed.is_empty() {
+                    println!("No completed tasks.");
+                } else {
+                    // Newest first, like 'task pending'
+                    let completed = completed.into_iter().rev().collect();
+                    order::list(
+                        completed,
+                        |(_, _, task)| Some(task),
+                        |(i, line, task)| {
+                            let mut entry = format!("{} - {}", task_ref(&[], *i), display(line));
+                            if let Some(note) = task.field("note") {
+                                entry.push_str(&format!("\n    ↳ Note: {}", note));
+                            }
+                            entry
+                        },
+                    );
+                }
+                return;
+            }
+
+            let closing = Closing {
+                status: Status::Done,
+                date: backdated(on, today),
+                reason: None,
+                note: note.as_deref(),
+            };
+            let Some(missing) = close(
+                &mut store,
+                &selection,
+                &closing,
+                today,
+                config.format,
+                &task_dir,
+                diff_mode,
+            ) else {
+                println!("No matching pending tasks.");
+                std::process::exit(EXIT_NOT_FOUND);
+            };
+            if missing {
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+        }
+
+        Some(Commands::Cancel {
+            selection,
+            reason,
+            on,
+            note,
+        }) => {
+            if selection.is_empty() {
+                let lines = store.read();
+                if reason.is_some() || on.is_some() || note.is_some() {
+                    eprintln!("Error: --reason, --on and --note need the tasks to cancel.");
+                    std::process::exit(EXIT_USAGE);
+                }
+                println!("Cancelled tasks:");
+                let cancelled = tasks_with_status(&lines, Status::Cancelled);
+
+                if cancelled.is_empty() {
+                    println!("No cancelled tasks.");
+                } else {
+                    let cancelled = cancelled.into_iter().rev().collect();
+                    order::list(
+                        cancelled,
+                        |(_, _, task)| Some(task),
+                        |(i, line, task)| {
+                            let mut entry = format!("{} - {}", task_ref(&[], *i), display(line));
+                            if let Some(reason) = task.field("reason") {
+                                entry.push_str(&format!("\n    ↳ Reason: {}", reason));
+                            }
+                            if let Some(note) = task.field("note") {
+                                entry.push_str(&format!("\n    ↳ Note: {}", note));
+                            }
+                            entry
+                        },
+                    );
+                }
+                return;
+            }
+
+            let closing = Closing {
+                status: Status::Cancelled,
+                date: backdated(on, today),
+                reason: reason.as_deref(),
+                note: note.as_deref(),
+            };
+            let Some(missing) = close(
+                &mut store,
+                &selection,
+                &closing,
+                today,
+                config.format,
+                &task_dir,
+                diff_mode,
+            ) else {
+                println!("No matching pending tasks.");
+                std::process::exit(EXIT_NOT_FOUND);
+            };
+            if missing {
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+        }
+
+        Some(Commands::Reopen { lines: refs }) => {
+            let mut lines = store.read();
+            let before = lines.clone();
+            let mut reopened = Vec::new();
+            for r in &refs {
+                let Ok(line) = r.strip_prefix(['L', 'l']).unwrap_or(r).parse::<usize>() else {
+                    eprintln!(
+                        "Error: '{}' isn't a line number. Use the L numbers 'task done' and 'task cancel' list.",
+                        r
+                    );
+                    std::process::exit(EXIT_USAGE);
+                };
+                let task = line
+                    .checked_sub(1)
+                    .and_then(|i| lines.get(i))
+                    .and_then(|l| Task::parse(l))
+                    .filter(|t| matches!(t.status, Status::Done | Status::Cancelled));
+                let Some(mut task) = task else {
+                    eprintln!(
+                        "Error: Line {} isn't a completed or cancelled task. Run 'task done' or 'task cancel' to see them.",
+                        line
+                    );
+                    std::process::exit(EXIT_NOT_FOUND);
+                };
+                trash::reopen(&mut task);
+                lines[line - 1] = task.to_line(config.format);
+                reopened.push((line - 1, task));
+            }
+            store.write(&before, &lines, diff_mode.wanted(refs.len() > 1));
+
+            let pending = pending_indices(&lines, today);
+            for (line_idx, task) in reopened {
+                println!(
+                    "Task {} reopened: {}",
+                    task_ref(&pending, line_idx),
+                    task.text
+                );
+                // Completing it added the next occurrence just above
+                let next = line_idx
+                    .checked_sub(1)
+                    .and_then(|i| Task::parse(&lines[i]))
+                    .filter(|n| {
+                        task.recurrence.is_some()
+                            && n.status == Status::Pending
+                            && n.text == task.text
+                            && n.recurrence == task.recurrence
+                    });
+                if next.is_some() {
+                    println!(
+                        "Its next occurrence is still pending as task {}; cancel it if it's no longer needed.",
+                        task_ref(&pending, line_idx - 1)
+                    );
+                }
+            }
+        }
+
+        Some(Commands::Postpone {
+            task_num,
+            offset,
+            overdue,
+        }) => {
+            let today = dates::today();
+            if let Some(offset) = overdue {
+                let mut lines = store.read();
+                let before = lines.clone();
+                let new_due = offset.after(today).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(EXIT_USAGE);
+                });
+                let mut moved = 0;
+                for line_idx in pending_indices(&lines, today) {
+                    let Some(mut task) = Task::parse(&lines[line_idx]) else {
+                        continue;
+                    };
+                    if task.due.is_some_and(|d| d < today) {
+                        task.due = Some(new_due);
+                        lines[line_idx] = task.to_line(config.format);
+                        moved += 1;
+                    }
+                }
+                if moved == 0 {
+                    println!("No overdue tasks.");
+                    return;
+                }
+                store.write(&before, &lines, diff_mode.wanted(true));
+                println!(
+                    "Postponed {} overdue task(s) to 📅 {}",
+                    moved,
+                    dates::show(new_due)
+                );
+                return;
+            }
+
+            let (Some(task_num), Some(offset)) = (task_num, offset) else {
+                return;
+            };
+            let postponed = edit_task(
+                &mut store,
+                task_num,
+                today,
+                config.format,
+                diff_mode.wanted(false),
+                |task| {
+                    let new_due = offset.after(task.due.unwrap_or(today))?;
+                    task.due = Some(new_due);
+                    Ok(new_due)
+                },
+            );
+            if let Some(new_due) = postponed {
+                println!("Task {} postponed to 📅 {}", task_num, dates::show(new_due));
+            }
+        }
+
+        Some(Commands::Promote { task_nums, top }) => {
+            let adjust = |p: Priority| if top { Priority::Highest } else { p.up() };
+            change_priority(
+                &mut store,
+                config.format,
+                &task_nums,
+                today,
+                diff_mode,
+                adjust,
+            );
+        }
+
+        Some(Commands::Demote { task_nums, bottom }) => {
+            let adjust = |p: Priority| if bottom { Priority::Lowest } else { p.down() };
+            change_priority(
+                &mut store,
+                config.format,
+                &task_nums,
+                today,
+                diff_mode,
+                adjust,
+            );
+        }
+
+        Some(Commands::Snooze { task_num, until }) => {
+            let rule = until.as_deref().unwrap_or(&config.snooze);
+            let snoozed = edit_task(
+                &mut store,
+                task_num,
+                today,
+                config.format,
+                diff_mode.wanted(false),
+                |task| snooze(task, rule, today),
+            );
+            if let Some((new_due, snoozes)) = snoozed {
+                println!(
+                    "Task {} snoozed to 📅 {} ({} time(s) so far)",
+                    task_num,
+                    dates::show(new_due),
+                    snoozes
+                );
+            }
+        }
+
+        Some(Commands::Reschedule { task_num, date }) => {
+            let rescheduled = edit_task(
+                &mut store,
+                task_num,
+                today,
+                config.format,
+                diff_mode.wanted(false),
+                |task| {
+                    task.due = Some(date);
+                    Ok(())
+                },
+            );
+            if rescheduled.is_some() {
+                println!("Task {} rescheduled to 📅 {}", task_num, dates::show(date));
+            }
+        }
+
+        Some(Commands::Note { task_num, text }) => {
+            let mut lines = store.read();
+            let pending = pending_indices(&lines, today);
+            let line_idx = pending_line(&pending, task_num);
+
+            let note = task::note_lines(&text.join(" "));
+            if note.is_empty() {
+                eprintln!("Error: Note cannot be empty.");
+                std::process::exit(EXIT_USAGE);
+            }
+            // New notes go below any the task already has
+            let at = line_idx + 1 + task::notes(&lines, line_idx).len();
+            let before = lines.clone();
+            lines.splice(at..at, note);
+            store.write(&before, &lines, diff_mode.wanted(false));
+            println!("Added note to task {}", task_num);
+        }
+
+        Some(Commands::Show { task_num, line }) => {
+            let lines = store.read();
+            let (title, line_idx) = match (task_num, line) {
+                (_, Some(line)) => {
+                    if line == 0 || line > lines.len() {
+                        eprintln!(
+                            "Error: Line {} out of range. Run 'task all' to see every line.",
+                            line
+                        );
+                        std::process::exit(EXIT_NOT_FOUND);
+                    }
+                    (format!("Line {}", line), line - 1)
+                }
+                (Some(task_num), None) => {
+                    let pending = pending_indices(&lines, today);
+                    (
+                        format!("Task {}", task_num),
+                        pending_line(&pending, task_num),
+                    )
+                }
+                (None, None) => return,
+            };
+
+            let Some(task) = Task::parse(&lines[line_idx]) else {
+                println!("Line {} is not a task: {}", line_idx + 1, lines[line_idx]);
+                return;
+            };
+            show_task(&title, &task, &lines, line_idx, today);
+        }
+
+        Some(Commands::Blocked) => {
+            println!("Blocked tasks:");
+            let lines = store.read();
+            let tasks = lines
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            let blocked = pending_indices(&lines, today)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, idx)| Task::parse(&lines[idx]).map(|t| (i + 1, idx, t)))
+                .filter(|(_, _, t)| deps::is_blocked(t, &tasks))
+                .collect::<Vec<_>>();
+            if blocked.is_empty() {
+                println!("No blocked tasks.");
+            }
+            for (task_num, line_idx, task) in order::apply(blocked, |(_, _, t)| Some(t)) {
+                println!("{} - {}", task_num, display(&lines[line_idx]));
+                let mut seen = task.id.iter().cloned().collect();
+                deps::print_chain(&task, &tasks, 1, &mut seen);
+            }
+        }
+
+        Some(Commands::Lint { fix_dates, fix }) => {
+            let mut lines = store.read();
+            let problems = lint::check(&lines, config.format, today);
+            if problems.is_empty() {
+                println!("No problems found in {}.", task_file.display());
+                return;
+            }
+
+            // Without a terminal to ask on, every fix is applied. Layout-only
+            // fixes are never asked about; the diff shows them.
+            let interactive = std::io::stdin().is_terminal();
+            let before = lines.clone();
+            let mut fixed = 0;
+            for problem in &problems {
+                println!("Line {}: {}", problem.line + 1, problem.description);
+                let wanted = fix || (fix_dates && !problem.cosmetic);
+                let Some(repaired) = problem.fixed.as_ref().filter(|_| wanted) else {
+                    continue;
+                };
+                if interactive && !problem.cosmetic {
+                    println!("  fix: {}", repaired);
+                    match ask("Apply this fix?") {
+                        Some(true) => {}
+                        Some(false) => continue,
+                        None => break,
+                    }
+                }
+                lines[problem.line] = repaired.clone();
+                fixed += 1;
+            }
+
+            let fixable = problems.iter().filter(|p| p.fixed.is_some()).count();
+            if !fix_dates && !fix {
+                println!(
+                    "{} problem(s), {} fixable with 'task lint --fix'.",
+                    problems.len(),
+                    fixable
+                );
+            } else if fixed > 0 {
+                store.write(&before, &lines, diff_mode.wanted(true));
+                println!("Fixed {} of {} problem(s).", fixed, problems.len());
+            } else {
+                println!("Nothing fixed.");
+            }
+        }
+
+        Some(Commands::Ids { strategy }) => {
+            let mut lines = store.read();
+            let before = lines.clone();
+            let changed = ids::convert(&mut lines, strategy, config.format);
+            if changed == 0 {
+                println!("No task ids to convert.");
+                return;
+            }
+            store.write(&before, &lines, diff_mode.wanted(true));
+            println!("Converted {} task id(s).", changed);
+            if strategy != config.id_strategy {
+                println!(
+                    "Set id_strategy = \"{}\" in config.toml to use it for new ids too.",
+                    strategy
+                        .to_possible_value()
+                        .map_or(String::new(), |v| v.get_name().to_string())
+                );
+            }
+        }
+
+        Some(Commands::Wait { task_num, until }) => {
+            let parked = edit_task(
+                &mut store,
+                task_num,
+                today,
+                config.format,
+                diff_mode.wanted(false),
+                |task| {
+                    if until <= today {
+                        return Err("The wake-up date must be after today.".to_string());
+                    }
+                    waiting::wait(task, until);
+                    Ok(task.text.clone())
+                },
+            );
+            if let Some(text) = parked {
+                println!(
+                    "Waiting on '{}' until {}",
+                    text,
+                    describe_date(Some(until), today)
+                );
+            }
+        }
+
+        Some(Commands::Waiting) => {
+            println!("Waiting for:");
+            let lines = store.read();
+            let mut waiting = tasks_with_status(&lines, Status::Waiting);
+            waiting.sort_by_key(|(_, _, t)| t.wakes());
+            let found = !waiting.is_empty();
+            order::list(
+                waiting,
+                |(_, _, t)| Some(t),
+                |(i, line, task)| {
+                    format!(
+                        "{} - {}\n    wakes {}",
+                        task_ref(&[], *i),
+                        display(line),
+                        describe_date(task.wakes(), today)
+                    )
+                },
+            );
+            if !found {
+                println!("No waiting tasks.");
+            }
+        }
+
+        Some(Commands::Approve { task_nums, all }) => {
+            let mut lines = store.read();
+            let queue = proposals::queue(&lines);
+            if task_nums.is_empty() && !all {
+                println!("Proposed tasks:");
+                for (i, &line_idx) in queue.iter().enumerate() {
+                    println!("{} - {}", i + 1, display(&lines[line_idx]));
+                }
+                if queue.is_empty() {
+                    println!("No tasks awaiting review.");
+                }
+                return;
+            }
+
+            let Some(selected) = select_proposed(&queue, &task_nums, all) else {
+                std::process::exit(EXIT_NOT_FOUND);
+            };
+            let before = lines.clone();
+            for (task_num, line_idx) in &selected {
+                let Some(mut task) = Task::parse(&lines[*line_idx]) else {
+                    continue;
+                };
+                task.status = Status::Pending;
+                lines[*line_idx] = task.to_line(config.format);
+                println!("Approved {}: {}", task_num, task.text);
+            }
+            store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+        }
+
+        Some(Commands::Reject { task_nums }) => {
+            let mut lines = store.read();
+            let queue = proposals::queue(&lines);
+            let Some(selected) = select_proposed(&queue, &task_nums, false) else {
+                std::process::exit(EXIT_NOT_FOUND);
+            };
+            let before = lines.clone();
+            for (task_num, line_idx) in &selected {
+                let Some(mut task) = Task::parse(&lines[*line_idx]) else {
+                    continue;
+                };
+                task.status = Status::Cancelled;
+                task.cancelled = Some(today);
+                lines[*line_idx] = task.to_line(config.format);
+                println!("Rejected {}: {}", task_num, task.text);
+            }
+            store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+        }
+
+        Some(Commands::Estimate { task_num, duration }) => {
+            let estimate = dates::format_estimate(duration);
+            let estimated = edit_task(
+                &mut store,
+                task_num,
+                today,
+                config.format,
+                diff_mode.wanted(false),
+                |task| {
+                    task.set_field("estimate", &estimate);
+                    Ok(())
+                },
+            );
+            if estimated.is_some() {
+                println!("Task {} estimated at {}", task_num, estimate);
+            }
+        }
+
+        Some(Commands::Quickies { max, batch_done }) => {
+            let mut lines = store.read();
+            let tasks = lines
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            let mut quickies = pending_indices(&lines, today)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, idx)| Task::parse(&lines[idx]).map(|t| (i + 1, idx, t)))
+                .filter(|(_, _, t)| t.estimate().is_some_and(|e| e <= max))
+                .filter(|(_, _, t)| !t.is_deferred(today) && !deps::is_blocked(t, &tasks))
+                .collect::<Vec<_>>();
+            // Oldest first: the ones that have been put off longest
+            quickies.sort_by_key(|(_, _, t)| t.created);
+            let quickies = order::apply(quickies, |(_, _, t)| Some(t));
+
+            println!(
+                "Quick tasks (estimated {} or less):",
+                dates::format_estimate(max)
+            );
+            if quickies.is_empty() {
+                println!(
+                    "Nothing quick to knock out. Add estimates with 'task estimate <num> 5m'."
+                );
+                return;
+            }
+            for (task_num, line_idx, _) in &quickies {
+                println!("{} - {}", task_num, display(&lines[*line_idx]));
+            }
+            if !batch_done {
+                return;
+            }
+
+            println!();
+            let mut selected = Vec::new();
+            for (task_num, line_idx, task) in &quickies {
+                match ask(&format!("Done with '{}'?", task.text)) {
+                    Some(true) => selected.push((*task_num, *line_idx)),
+                    Some(false) => {}
+                    None => break,
+                }
+            }
+            if selected.is_empty() {
+                println!("Nothing marked as done.");
+                return;
+            }
+            let before = lines.clone();
+            complete(&mut lines, &selected, today, None, config.format, &task_dir);
+            store.write(&before, &lines, diff_mode.wanted(false));
+            println!("🎉 Knocked out {} quick task(s)!", selected.len());
+        }
+
+        Some(Commands::Stale { days, auto_cancel }) => {
+            let mut lines = store.read();
+            let cutoff = today - Duration::days(days);
+            let mut stale = pending_indices(&lines, today)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, idx)| Task::parse(&lines[idx]).map(|t| (i + 1, idx, t)))
+                .filter(|(_, _, t)| t.created.is_some_and(|c| c < cutoff))
+                .collect::<Vec<_>>();
+            // Oldest first; ties keep their task numbers' order
+            stale.sort_by_key(|(_, _, t)| t.created);
+
+            println!("Pending tasks created more than {} days ago:", days);
+            if stale.is_empty() {
+                println!("Nothing stale. Tasks without a ➕ creation date are never listed.");
+                return;
+            }
+            let selected = order::apply(stale.clone(), |(_, _, t)| Some(t))
+                .into_iter()
+                .map(|(task_num, line_idx, _)| (task_num, line_idx))
+                .collect::<Vec<_>>();
+            order::list(
+                stale,
+                |(_, _, t)| Some(t),
+                |(task_num, line_idx, t)| {
+                    let age = t.created.map_or(0, |c| (today - c).num_days());
+                    format!(
+                        "{} - {} ({} days old)",
+                        task_num,
+                        display(&lines[*line_idx]),
+                        age
+                    )
+                },
+            );
+            if !auto_cancel {
+                return;
+            }
+
+            println!();
+            if !confirm(&format!("Cancel these {} task(s)?", selected.len())) {
+                println!("Nothing cancelled.");
+                return;
+            }
+            let before = lines.clone();
+            cancel(
+                &mut lines,
+                &selected,
+                today,
+                Some("stale"),
+                None,
+                config.format,
+                &task_dir,
+            );
+            store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+        }
+
+        Some(Commands::TriageDone) => {
+            let mut lines = store.read();
+            let pending = pending_indices(&lines, today);
+            let items = pending
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &idx)| Task::parse(&lines[idx]).map(|t| (i + 1, t)))
+                .map(|(task_num, task)| match task.due {
+                    Some(due) => format!("{:>3}  {} 📅 {}", task_num, task.text, dates::show(due)),
+                    None => format!("{:>3}  {}", task_num, task.text),
+                })
+                .collect::<Vec<_>>();
+            if items.is_empty() {
+                println!("No pending tasks.");
+                return;
+            }
+
+            let picked = triage::pick(&items).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(EXIT_USAGE);
+            });
+            let Some((action, picked)) = picked else {
+                println!("Nothing changed.");
+                return;
+            };
+            let selected = picked
+                .into_iter()
+                .map(|i| (i + 1, pending[i]))
+                .collect::<Vec<_>>();
+            let before = lines.clone();
+            match action {
+                triage::Action::Done => {
+                    complete(&mut lines, &selected, today, None, config.format, &task_dir)
+                }
+                triage::Action::Cancel => cancel(
+                    &mut lines,
+                    &selected,
+                    today,
+                    None,
+                    None,
+                    config.format,
+                    &task_dir,
+                ),
+                triage::Action::Postpone => {
+                    print!("Postpone {} task(s) by (e.g. +1d, +1w): ", selected.len());
+                    let _ = std::io::stdout().flush();
+                    let mut answer = String::new();
+                    let _ = std::io::stdin().read_line(&mut answer);
+                    let offset = dates::parse_offset(answer.trim()).unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(EXIT_USAGE);
+                    });
+                    for &(task_num, line_idx) in &selected {
+                        let Some(mut task) = Task::parse(&lines[line_idx]) else {
+                            continue;
+                        };
+                        let new_due = offset.after(task.due.unwrap_or(today)).unwrap_or_else(|e| {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(EXIT_USAGE);
+                        });
+                        task.due = Some(new_due);
+                        lines[line_idx] = task.to_line(config.format);
+                        println!("Task {} postponed to 📅 {}", task_num, dates::show(new_due));
+                    }
+                }
+            }
+            store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+        }
+
+        Some(Commands::Pick { query }) => {
+            let mut lines = store.read();
+            let pending = pending_indices(&lines, today);
+            let items = pending
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &idx)| Task::parse(&lines[idx]).map(|t| (i + 1, t)))
+                .map(|(task_num, task)| {
+                    let shown = match task.due {
+                        Some(due) => {
+                            format!("{:>3}  {} 📅 {}", task_num, task.text, dates::show(due))
+                        }
+                        None => format!("{:>3}  {}", task_num, task.text),
+                    };
+                    (task.text, shown)
+                })
+                .collect::<Vec<_>>();
+            if items.is_empty() {
+                println!("No pending tasks.");
+                return;
+            }
+
+            let picked = pick::pick(&items, query.as_deref().unwrap_or("")).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(EXIT_USAGE);
+            });
+            let Some((action, picked)) = picked else {
+                println!("Nothing changed.");
+                return;
+            };
+            let (task_num, line_idx) = (picked + 1, pending[picked]);
+            let before = lines.clone();
+            match action {
+                pick::Action::Done => complete(
+                    &mut lines,
+                    &[(task_num, line_idx)],
+                    today,
+                    None,
+                    config.format,
+                    &task_dir,
+                ),
+                pick::Action::Cancel => cancel(
+                    &mut lines,
+                    &[(task_num, line_idx)],
+                    today,
+                    None,
+                    None,
+                    config.format,
+                    &task_dir,
+                ),
+                pick::Action::Edit => {
+                    let Some(mut task) = Task::parse(&lines[line_idx]) else {
+                        return;
+                    };
+                    println!("{} - {}", task_num, task.text);
+                    print!("New text (Enter keeps it): ");
+                    let _ = std::io::stdout().flush();
+                    let mut answer = String::new();
+                    let _ = std::io::stdin().read_line(&mut answer);
+                    if answer.trim().is_empty() {
+                        println!("Nothing changed.");
+                        return;
+                    }
+                    task.text = answer.trim().to_string();
+                    lines[line_idx] = task.to_line(config.format);
+                    println!("Task {} is now: {}", task_num, task.text);
+                }
+                pick::Action::Show => {
+                    let Some(task) = Task::parse(&lines[line_idx]) else {
+                        return;
+                    };
+                    show_task(
+                        &format!("Task {}", task_num),
+                        &task,
+                        &lines,
+                        line_idx,
+                        today,
+                    );
+                    return;
+                }
+            }
+            store.write(&before, &lines, diff_mode.wanted(false));
+        }
+
+        Some(Commands::Start { task_num }) => {
+            let lines = store.read();
+            let pending = pending_indices(&lines, today);
+            let line_idx = pending_line(&pending, task_num);
+            let Some(task) = Task::parse(&lines[line_idx]) else {
+                return;
+            };
+
+            let now = Local::now().naive_local();
+            match timer::start(&task_dir, &task, now) {
+                Ok(stopped) => {
+                    if let Some(entry) = stopped {
+                        println!(
+                            "⏱ Stopped '{}' after {}",
+                            entry.text,
+                            timer::format_duration(entry.duration(now))
+                        );
+                    }
+                    println!("⏱ Started timer for task {}: {}", task_num, task.text);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(Commands::Pomo {
+            task_num,
+            work,
+            rest,
+            rounds,
+            notify,
+        }) => {
+            let lines = store.read();
+            let pending = pending_indices(&lines, today);
+            let line_idx = pending_line(&pending, task_num);
+            let Some(task) = Task::parse(&lines[line_idx]) else {
+                return;
+            };
+            let alert = |summary: &str| {
+                if notify && let Err(e) = notify::send(summary, &task.text, false) {
+                    eprintln!("Error: {}", e);
+                }
+            };
+
+            for round in 1..=rounds {
+                let label = format!("[{}/{}] {}", round, rounds, task.text);
+                pomo::countdown("🍅", &label, work);
+
+                // The countdown can take a while, so lock and re-read the
+                // file only now to record the pomodoro on the task
+                let _lock = storage::lock(
+                    &task_file,
+                    std::time::Duration::from_secs(config.lock_timeout),
+                );
+                let mut lines = store.read();
+                let found = lines.iter().position(|l| {
+                    Task::parse(l).is_some_and(|t| t.created == task.created && t.text == task.text)
+                });
+                if let Some(line_idx) = found
+                    && let Some(mut logged) = Task::parse(&lines[line_idx])
+                {
+                    let before = lines.clone();
+                    pomo::log(&mut logged, work);
+                    lines[line_idx] = logged.to_line(config.format);
+                    store.write(&before, &lines, diff_mode.wanted(false));
+                    println!(
+                        "🍅 Pomodoro {} done ({} on this task)",
+                        logged.pomodoros(),
+                        timer::format_duration(Duration::minutes(logged.focus()))
+                    );
+                }
+                drop(_lock);
+
+                alert("Pomodoro done, take a break");
+                if rest > 0 {
+                    pomo::countdown("☕", "Break", rest);
+                    if round < rounds {
+                        alert("Break over, back to work");
+                    }
+                }
+            }
+        }
+
+        Some(Commands::Stop) => {
+            let now = Local::now().naive_local();
+            match timer::stop(&task_dir, now) {
+                Ok(Some(entry)) => println!(
+                    "⏱ Stopped '{}' after {}",
+                    entry.text,
+                    timer::format_duration(entry.duration(now))
+                ),
+                Ok(None) => println!("No timer running."),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(Commands::Active) => match timer::active(&task_dir) {
+            Some(entry) => println!(
+                "⏱ {} - running for {} (since {})",
+                entry.text,
+                timer::format_duration(entry.duration(Local::now().naive_local())),
+                entry.start.format("%H:%M")
+            ),
+            None => println!("No timer running."),
+        },
+
+        Some(Commands::Time { week, month }) => {
+            let (since, period) = if week {
+                (Some(report::week_start(today)), "this week")
+            } else if month {
+                (Some(dates::month_bounds(today).0), "this month")
+            } else {
+                (None, "all time")
+            };
+            println!("Time tracked ({}):", period);
+            timer::summary(&timer::load(&task_dir), since, Local::now().naive_local());
+        }
+
+        Some(Commands::All) => {
+            println!("All tasks:");
+            let lines = store.read();
+
+            if lines.is_empty() {
+                println!("No tasks found.");
+            } else {
+                let pending = pending_indices(&lines, today);
+                // Blank lines include tasks hidden by the context; notes and
+                // headings would land anywhere once sorted
+                let rows = lines
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, line)| !line.trim().is_empty())
+                    .map(|(i, line)| (i, line, Task::parse(line)))
+                    .filter(|(_, _, task)| task.is_some() || !order::rearranges())
+                    .collect::<Vec<_>>();
+                order::list(
+                    rows,
+                    |(_, _, task)| task.as_ref(),
+                    |(i, line, _)| format!("{} - {}", task_ref(&pending, *i), display(line)),
+                );
+            }
+        }
+
+        Some(Commands::Wins { month, random }) => {
+            let today = dates::today();
+            let (since, period) = if month {
+                (today - Duration::days(30), "month")
+            } else {
+                (today - Duration::days(7), "week")
+            };
+            let lines = store.read();
+
+            let wins = tasks_with_status(&lines, Status::Done)
+                .into_iter()
+                .filter_map(|(_, l, t)| t.done.map(|d| (d, l, t.created)))
+                .filter(|(d, _, _)| *d >= since && *d <= today)
+                .collect::<Vec<_>>();
+
+            if wins.is_empty() {
+                println!("No wins this {} yet - go get one! 💪", period);
+                return;
+            }
+
+            println!("🎉 {} win(s) this {}! 🎉", wins.len(), period);
+            match random {
+                // Weight highlights by how long each task was open, so the
+                // long-standing ones resurface more often
+                Some(n) => {
+                    let highlights = wins
+                        .choose_multiple_weighted(&mut rand::rng(), n, |(done, _, created)| {
+                            let created = created.unwrap_or(*done);
+                            ((*done - created).num_days().max(0) + 1) as f64
+                        })
+                        .unwrap_or_else(|e| {
+                            eprintln!("Error picking highlights: {}", e);
+                            std::process::exit(1);
+                        });
+                    println!("Highlights:");
+                    for (done, line, _) in highlights {
+                        println!("  🏆 {} - {}", done.format("%a %d %b"), display(line));
+                    }
+                }
+                None => {
+                    for (done, line, _) in &wins {
+                        println!("  🏆 {} - {}", done.format("%a %d %b"), display(line));
+                    }
+                }
+            }
+        }
+
+        Some(Commands::Cohorts { weeks }) => {
+            let tasks = store
+                .read()
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            report::cohorts(&tasks, weeks, dates::today());
+        }
+
+        Some(Commands::Stats { weeks, week }) => {
+            let tasks = store
+                .read()
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            if week {
+                report::week_over_week(&tasks, dates::today());
+            } else {
+                let entries = timer::load(&task_dir);
+                let now = Local::now().naive_local();
+                report::stats(&tasks, &entries, weeks, dates::today(), now);
+            }
+        }
+
+        Some(Commands::Status { short, json }) => {
+            let summary = status::summarize(&store.read_open(), today);
+            status::store(&task_file, today, &summary);
+            status::print(&summary, short, json);
+        }
+
+        Some(Commands::Burndown { weeks }) => {
+            let tasks = store
+                .read()
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            report::burndown(&tasks, weeks, dates::today());
+        }
+
+        Some(Commands::Habits { weeks }) => {
+            // Completions archived with 'task archive' still count
+            let archive = storage::read_text(&task_dir.join("archive.md")).unwrap_or_default();
+            let tasks = archive
+                .lines()
+                .map(String::from)
+                .chain(store.read())
+                .filter_map(|l| Task::parse(&l))
+                .collect::<Vec<_>>();
+            habits::print(&tasks, weeks, dates::today());
+        }
+
+        Some(Commands::Nag { no_notify }) => {
+            let lines = store.read();
+            let critical = pending_indices(&lines, today)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, idx)| Task::parse(&lines[idx]).map(|t| (i + 1, idx, t)))
+                .filter(|(_, _, t)| nag::is_critical(t))
+                .collect::<Vec<_>>();
+
+            println!("Critically overdue tasks:");
+            if critical.is_empty() {
+                println!("Nothing critically overdue. 🎉");
+                return;
+            }
+            for (task_num, line_idx, task) in &critical {
+                let days = task.due.map_or(0, |d| (today - d).num_days());
+                println!(
+                    "{} - {} ({} days overdue)",
+                    task_num,
+                    display(&lines[*line_idx]),
+                    days
+                );
+            }
+
+            if !no_notify {
+                let tasks = critical.iter().map(|(_, _, t)| t).collect::<Vec<_>>();
+                match nag::remind(&tasks, &task_dir, Local::now().naive_local()) {
+                    Ok(0) => {}
+                    Ok(sent) => println!("Sent {} reminder(s).", sent),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+
+        Some(Commands::Notify { lead, no_notify }) => {
+            let tasks = store
+                .read()
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            let lead = lead.unwrap_or(config.notify.lead_days);
+            let now = Local::now().time();
+            let due = notify::due_soon(&tasks, today, now, lead, config.notify.lead_minutes);
+
+            println!("Tasks overdue or due within {} day(s):", lead);
+            if due.is_empty() {
+                println!("Nothing due. 🎉");
+                return;
+            }
+            for task in &due {
+                let due_date = task.due.unwrap_or(today);
+                match task.due_time() {
+                    Some(time) => println!(
+                        "  📅 {} {} - {}",
+                        dates::show(due_date),
+                        time.format("%H:%M"),
+                        task.text
+                    ),
+                    None => println!("  📅 {} - {}", dates::show(due_date), task.text),
+                }
+            }
+
+            if !no_notify {
+                match notify::remind(&due, &task_dir, today) {
+                    Ok(sent) if sent.is_empty() => println!("Already notified today."),
+                    Ok(sent) => println!("Sent reminders for {} task(s).", sent.len()),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+
+        Some(Commands::Serve {
+            bind,
+            token,
+            read_only,
+        }) => {
+            let bind = bind
+                .or(config.serve.bind.clone())
+                .unwrap_or_else(|| serve::DEFAULT_BIND.to_string());
+            let token = token.or(config.serve.token.clone());
+            let read_only = read_only || config.serve.read_only;
+            let origins = &config.serve.origins;
+            let served = serve::run(&bind, token.as_deref(), read_only, origins, |request| {
+                let path = request.path.iter().map(String::as_str).collect::<Vec<_>>();
+                let valid_num = |num: &str| num.parse::<usize>().is_ok_and(|n| n > 0);
+                let response = |args: Result<Vec<String>, String>, status| match args {
+                    Ok(args) => serve::replay(&task_file, &args, status),
+                    Err(e) => serve::Response::error(400, &e),
+                };
+                match (request.method.as_str(), path.as_slice()) {
+                    ("GET", ["tasks"]) => {
+                        let lines = store.read();
+                        let pending = pending_indices(&lines, dates::today());
+                        serve::list(&lines, &pending, request.query("all").is_some())
+                    }
+                    ("POST", ["tasks"]) => response(serve::add_args(&request.body), 201),
+                    ("POST", ["tasks", num, "done"]) if valid_num(num) => {
+                        response(Ok(vec!["done".to_string(), num.to_string()]), 200)
+                    }
+                    ("POST", ["tasks", num, "cancel"]) if valid_num(num) => {
+                        response(serve::cancel_args(num, &request.body), 200)
+                    }
+                    (_, ["tasks", ..]) => serve::Response::error(405, "method not allowed"),
+                    _ => serve::Response::error(404, "not found"),
+                }
+            });
+            if let Err(e) = served {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Watch {
+            interval,
+            no_notify,
+        }) => {
+            let interval = std::time::Duration::from_secs(interval.max(1));
+            watch::run(&task_file, interval, |today| {
+                println!(
+                    "👀 Watching {} (updated {}, Ctrl-C to stop)\n",
+                    task_file.display(),
+                    Local::now().format("%H:%M:%S")
+                );
+                let lines = store.read();
+                print_agenda(&lines, today, undated);
+                if no_notify {
+                    return;
+                }
+                let tasks = lines
+                    .iter()
+                    .filter_map(|l| Task::parse(l))
+                    .collect::<Vec<_>>();
+                let due = notify::due_soon(
+                    &tasks,
+                    today,
+                    Local::now().time(),
+                    config.notify.lead_days,
+                    config.notify.lead_minutes,
+                );
+                // Already-notified tasks are skipped, so redraws don't repeat them
+                match notify::remind(&due, &task_dir, today) {
+                    Ok(sent) if sent.is_empty() => {}
+                    Ok(sent) => println!("Sent reminders for {} task(s).", sent.len()),
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+            });
+        }
+
+        Some(Commands::Archive { tag, out }) => {
+            let lines = store.read();
+            let (kept, archived) = archive::split(&lines, |t| {
+                matches!(t.status, Status::Done | Status::Cancelled)
+                    && tag.as_deref().is_none_or(|tag| archive::has_tag(t, tag))
+            });
+            let count = archived.iter().filter(|l| Task::parse(l).is_some()).count();
+            if count == 0 {
+                println!("No completed or cancelled tasks to archive.");
+                return;
+            }
+
+            let out = out.unwrap_or_else(|| task_dir.join("archive.md"));
+            let heading = match &tag {
+                Some(tag) => format!("#{} archived {}", tag.trim_start_matches('#'), today),
+                None => format!("Archived {}", today),
+            };
+            // Write the archive first so a failure can't lose tasks
+            if let Err(e) = archive::append(&out, &heading, &archived, true) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            storage::allow_shrinking();
+            store.write(&lines, &kept, diff_mode.wanted(count > 1));
+            println!("Archived {} task(s) to {}", count, out.display());
+        }
+
+        Some(Commands::Export { format, tag, out }) => {
+            if out.exists() {
+                eprintln!("Error: {} already exists.", out.display());
+                std::process::exit(EXIT_USAGE);
+            }
+            let lines = store.read();
+            let (_, mut exported) = archive::split(&lines, |t| {
+                tag.as_deref().is_none_or(|tag| archive::has_tag(t, tag))
+            });
+            let layout = match format {
+                Some(ExportFormat::Todoist) => {
+                    let (csv, count) = todoist::export(&exported);
+                    if count == 0 {
+                        println!("No matching pending tasks to export.");
+                        return;
+                    }
+                    if let Err(e) = std::fs::write(&out, csv) {
+                        eprintln!("Error writing {}: {}", out.display(), e);
+                        std::process::exit(1);
+                    }
+                    println!(
+                        "Exported {} pending task(s) to {}; import it into a Todoist project with \"Import from template\".",
+                        count,
+                        out.display()
+                    );
+                    return;
+                }
+                Some(ExportFormat::Obsidian) => Some(TaskFormat::Obsidian),
+                Some(ExportFormat::Legacy) => Some(TaskFormat::Legacy),
+                Some(ExportFormat::Dataview) => Some(TaskFormat::Dataview),
+                None => None,
+            };
+            if let Some(format) = layout {
+                for line in exported.iter_mut() {
+                    if let Some(task) = Task::parse(line) {
+                        *line = task.to_line(format);
+                    }
+                }
+            }
+            let count = exported.iter().filter(|l| Task::parse(l).is_some()).count();
+            if count == 0 {
+                println!("No matching tasks to export.");
+                return;
+            }
+
+            let heading = match &tag {
+                Some(tag) => format!("Tasks tagged #{} ({})", tag.trim_start_matches('#'), today),
+                None => format!("Tasks ({})", today),
+            };
+            if let Err(e) = archive::append(&out, &heading, &exported, false) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            println!("Exported {} task(s) to {}", count, out.display());
+        }
+
+        Some(Commands::Shell {
+            task,
+            timeout,
+            command,
+        }) => {
+            let lines = store.read();
+            let pending = pending_indices(&lines, today)
+                .into_iter()
+                .filter_map(|i| Task::parse(&lines[i]))
+                .collect::<Vec<_>>();
+            let mut env = vec![
+                ("TASK_FILE", task_file.display().to_string()),
+                ("TASK_DIR", task_dir.display().to_string()),
+            ];
+            let input = match task {
+                Some(task_num) => {
+                    let Some(task) = task_num.checked_sub(1).and_then(|i| pending.get(i)) else {
+                        eprintln!(
+                            "Error: Task number out of range. Run 'task pending' to see available tasks."
+                        );
+                        std::process::exit(EXIT_NOT_FOUND);
+                    };
+                    env.push(("TASK_NUM", task_num.to_string()));
+                    sandbox::task_json(task)
+                }
+                None => format!(
+                    "[{}]",
+                    pending
+                        .iter()
+                        .map(sandbox::task_json)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            };
+
+            let outcome = sandbox::run(
+                &command,
+                &input,
+                &env,
+                std::time::Duration::from_secs(timeout),
+                &task_dir.join("hooks.log"),
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            });
+            print!("{}", outcome.stdout);
+            eprint!("{}", outcome.stderr);
+            if outcome.timed_out {
+                eprintln!("Error: '{}' timed out after {}s.", command[0], timeout);
+                std::process::exit(1);
+            }
+            if outcome.code != Some(0) {
+                eprintln!("Error: '{}' failed (see hooks.log).", command[0]);
+                std::process::exit(outcome.code.unwrap_or(1));
+            }
+        }
+
+        Some(Commands::Report { kind }) => {
+            let tasks = store
+                .read()
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            match kind {
+                ReportKind::Weekly { markdown } => {
+                    report::weekly(&tasks, dates::today(), markdown);
+                    // Running the weekly review marks everything as seen
+                    if let Err(e) = review::record(&tasks, &task_dir, Local::now().naive_local()) {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+
+        Some(Commands::AsOf { date, command }) => {
+            let args = std::iter::once("task".to_string()).chain(command.iter().cloned());
+            let replayed = Cli::try_parse_from(args).unwrap_or_else(|e| e.exit());
+            if replayed.file.is_some() || replayed.list.is_some() || replayed.all_lists {
+                eprintln!(
+                    "Error: Put --file or --list before 'as-of' to replay another task file."
+                );
+                std::process::exit(1);
+            }
+            if !replayed.command.as_ref().is_some_and(Commands::replayable) {
+                eprintln!(
+                    "Error: 'as-of' only replays listings such as pending, today, due, all or stats."
+                );
+                std::process::exit(1);
+            }
+            if date > today {
+                eprintln!("Error: {} is in the future.", dates::show(date));
+                std::process::exit(1);
+            }
+
+            let (lines, source) = history::as_of(&task_dir, &task_file, date, config.format);
+            match source {
+                history::Source::Commit(rev) => {
+                    println!("🕰  As of {} (git commit {}):", dates::show(date), rev)
+                }
+                history::Source::Rebuilt => println!(
+                    "🕰  As of {} (rebuilt from task dates; due dates as they are now):",
+                    dates::show(date)
+                ),
+            }
+            let snapshot =
+                std::env::temp_dir().join(format!("task-as-of-{}.md", std::process::id()));
+            write_lines(&snapshot, &lines);
+            let status = std::env::current_exe()
+                .and_then(|exe| {
+                    std::process::Command::new(exe)
+                        .args(&command)
+                        .env("TASK_FILE", &snapshot)
+                        .env_remove(storage::LIST_VAR)
+                        .env(dates::AS_OF_VAR, date.to_string())
+                        .status()
+                })
+                .unwrap_or_else(|e| {
+                    eprintln!("Error: could not replay the command: {}", e);
+                    std::process::exit(1);
+                });
+            let _ = std::fs::remove_file(&snapshot);
+            if !status.success() {
+                std::process::exit(status.code().unwrap_or(1));
+            }
+        }
+
+        Some(Commands::Sync { backend }) => match backend {
+            SyncBackend::Ssh { remote } => {
+                let lines = store.read();
+                let merged = sync::ssh(&task_dir, &remote, &lines).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                });
+                if merged != lines {
+                    store.write(&lines, &merged, diff_mode.wanted(false));
+                }
+                println!("Synced with {}", remote);
+            }
+            SyncBackend::Git { remote } => {
+                let lines = store.read();
+                let remote = remote.or(config.sync.git_remote.clone());
+                let conflicts = sync::git_sync(
+                    &task_dir,
+                    &task_file,
+                    remote.as_deref(),
+                    &config.sync.git_branch,
+                    &lines,
+                    &mut |merged| store.write(&lines, merged, diff_mode.wanted(false)),
+                )
+                .unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                });
+                if conflicts > 0 {
+                    println!(
+                        "Resolved {} conflicting task(s) in favour of the most recent change.",
+                        conflicts
+                    );
+                }
+                println!("Synced with git remote origin/{}", config.sync.git_branch);
+            }
+            SyncBackend::Caldav { url } => {
+                let lines = store.read();
+                let (merged, summary) = caldav::Account::from_config(&config.sync, url)
+                    .and_then(|account| {
+                        caldav::sync(&task_dir, &account, &lines, config.format, today)
+                    })
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    });
+                if merged != lines {
+                    store.write(&lines, &merged, diff_mode.wanted(false));
+                }
+                if summary.conflicts > 0 {
+                    println!(
+                        "Resolved {} conflicting task(s); completions win, otherwise local edits do.",
+                        summary.conflicts
+                    );
+                }
+                println!(
+                    "Synced with CalDAV: {} pulled, {} pushed, {} deleted",
+                    summary.pulled, summary.pushed, summary.deleted
+                );
+            }
+        },
+
+        Some(Commands::Import { source }) => match source {
+            ImportSource::Todoist { file, project } => {
+                let text = std::fs::read_to_string(&file).unwrap_or_else(|e| {
+                    eprintln!("Error reading {}: {}", file.display(), e);
+                    std::process::exit(1);
+                });
+                let project =
+                    project.or_else(|| file.file_stem().map(|s| s.to_string_lossy().into_owned()));
+                let imported = todoist::import(&text, project.as_deref(), today, config.format)
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error: {}: {}", file.display(), e);
+                        std::process::exit(1);
+                    });
+                if imported.tasks == 0 {
+                    println!("No tasks found in {}.", file.display());
+                    return;
+                }
+                let mut lines = store.read();
+                let before = lines.clone();
+                lines.extend(imported.lines);
+                store.write(&before, &lines, diff_mode.wanted(true));
+                println!("Imported {} task(s) from Todoist.", imported.tasks);
+                if imported.unparsed_dates > 0 {
+                    println!(
+                        "{} date(s) couldn't be read and were kept in a [todoist_date:: ...] field; set them with 'task reschedule'.",
+                        imported.unparsed_dates
+                    );
+                }
+            }
+            ImportSource::Taskwarrior { file } => {
+                let text = match &file {
+                    Some(file) => std::fs::read_to_string(file)
+                        .map_err(|e| format!("Error reading {}: {}", file.display(), e)),
+                    None => std::io::read_to_string(std::io::stdin())
+                        .map_err(|e| format!("Error reading stdin: {}", e)),
+                }
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+                let mut lines = store.read();
+                let imported = taskwarrior::import(&text, &lines, today, config.format)
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    });
+                if imported.tasks > 0 {
+                    let before = lines.clone();
+                    lines.extend(imported.lines);
+                    store.write(&before, &lines, diff_mode.wanted(true));
+                }
+                println!("Imported {} task(s) from Taskwarrior.", imported.tasks);
+                if imported.already > 0 {
+                    println!(
+                        "Skipped {} task(s) already imported (same uuid).",
+                        imported.already
+                    );
+                }
+            }
+        },
+
+        Some(Commands::Scan { dir, pattern }) => {
+            let mut lines = store.read();
+            // Deleted tasks stay gone too
+            let mut known = lines.clone();
+            known.extend(trash::load(&task_dir));
+            let scanned =
+                scan::scan(&dir, &pattern, &known, today, config.format).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                });
+            let added = scanned.lines.len();
+            if added > 0 {
+                let before = lines.clone();
+                lines.extend(scanned.lines);
+                store.write(&before, &lines, diff_mode.wanted(true));
+            }
+            println!("Added {} task(s) from {} file(s).", added, scanned.files);
+            if scanned.already > 0 {
+                println!("Skipped {} comment(s) already scanned.", scanned.already);
+            }
+        }
+
+        Some(Commands::Batch) => {
+            let input = std::io::read_to_string(std::io::stdin()).unwrap_or_else(|e| {
+                eprintln!("Error reading commands from stdin: {}", e);
+                std::process::exit(EXIT_USAGE);
+            });
+            // Check every command before running any, so a typo on the last
+            // line doesn't leave the others applied
+            let mut commands = Vec::new();
+            for (i, line) in input.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let args = alias::split(line)
+                    .and_then(|args| {
+                        let parsed = Cli::try_parse_from(
+                            std::iter::once("task".to_string()).chain(args.iter().cloned()),
+                        )
+                        .map_err(|e| {
+                            let e = e.to_string();
+                            let first = e.lines().next().unwrap_or_default();
+                            first.trim_start_matches("error: ").to_string()
+                        })?;
+                        if parsed.file.is_some() || parsed.list.is_some() || parsed.all_lists {
+                            return Err("put --file or --list before 'batch' instead".to_string());
+                        }
+                        match parsed.command {
+                            Some(
+                                Commands::Batch | Commands::Pick { .. } | Commands::TriageDone,
+                            ) => Err("interactive commands can't be batched".to_string()),
+                            Some(c) if c.mutates() || c.replayable() => Ok(args),
+                            _ => Err("only commands that change or list tasks can be batched"
+                                .to_string()),
+                        }
+                    })
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error: line {}: {}: {}", i + 1, line, e);
+                        std::process::exit(EXIT_USAGE);
+                    });
+                commands.push((line, args));
+            }
+            if commands.is_empty() {
+                eprintln!("Error: No commands on stdin; give one per line, such as 'done 3'.");
+                std::process::exit(EXIT_USAGE);
+            }
+
+            // The commands run one after another on a copy, which replaces
+            // the task file only once they have all succeeded
+            let lines = store.read();
+            let staging =
+                std::env::temp_dir().join(format!("task-batch-{}.md", std::process::id()));
+            if let Err(e) = std::fs::copy(&task_file, &staging) {
+                eprintln!("Error: could not copy the task file: {}", e);
+                std::process::exit(storage::EXIT_STORAGE);
+            }
+            let staging_lock = storage::lock_file(&staging);
+            let clean_up = || {
+                let _ = std::fs::remove_file(&staging);
+                let _ = std::fs::remove_file(&staging_lock);
+            };
+            for (line, args) in &commands {
+                let status = std::env::current_exe().and_then(|exe| {
+                    std::process::Command::new(exe)
+                        .arg("--file")
+                        .arg(&staging)
+                        .arg("--quiet")
+                        .args(args)
+                        .env(storage::BATCH_VAR, "1")
+                        .env_remove(storage::LIST_VAR)
+                        .stdin(std::process::Stdio::null())
+                        .status()
+                });
+                match status {
+                    Ok(status) if status.success() => {}
+                    failed => {
+                        clean_up();
+                        eprintln!("Error: '{}' failed; nothing was changed.", line);
+                        let code = failed.ok().and_then(|s| s.code()).unwrap_or(1);
+                        std::process::exit(code);
+                    }
+                }
+            }
+            let batched = storage::read_text(&staging)
+                .map(|text| text.lines().map(String::from).collect::<Vec<_>>());
+            clean_up();
+            let batched = batched.unwrap_or_else(|e| {
+                eprintln!(
+                    "Error: could not read back the batch: {}; nothing was changed.",
+                    e
+                );
+                std::process::exit(storage::EXIT_STORAGE);
+            });
+            if batched != lines {
+                // Each command was held to max_removed_percent on its own
+                storage::allow_shrinking();
+                store.write(&lines, &batched, diff_mode.wanted(false));
+            }
+            if !cli.quiet {
+                println!("Applied {} command(s) in one write.", commands.len());
+            }
+        }
+
+        Some(Commands::Init { template }) => {
+            let dir = std::env::current_dir()
+                .map(|d| d.join(storage::PROJECT_DIR))
+                .unwrap_or_else(|e| {
+                    eprintln!("Error reading current directory: {}", e);
+                    std::process::exit(1);
+                });
+            match scaffold::init(&dir, template, today, config.format) {
+                Ok(file) => {
+                    println!("Created {}", file.display());
+                    println!(
+                        "Commands run in this directory (or below) now use it; commit {} to share it.",
+                        storage::PROJECT_DIR
+                    );
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(Commands::Man) => print!("{}", help::man(&help::annotate(Cli::command()))),
+
+        Some(Commands::Context { args }) => {
+            let mut contexts = context::load(&task_dir);
+            let save_contexts = |contexts: &[context::Context]| {
+                context::save(&task_dir, contexts).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                })
+            };
+            let find = |contexts: &[context::Context], name: &str| {
+                contexts
+                    .iter()
+                    .position(|c| c.name == name)
+                    .unwrap_or_else(|| {
+                        eprintln!(
+                            "Error: No context '{}'. Run 'task context' to see them.",
+                            name
+                        );
+                        std::process::exit(EXIT_NOT_FOUND);
+                    })
+            };
+            match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                [] if contexts.is_empty() => println!(
+                    "No contexts yet. Define one with 'task context define <name> <filter>'."
+                ),
+                [] => {
+                    for c in &contexts {
+                        let marker = if c.active { "*" } else { " " };
+                        println!("{} {:<12} {}", marker, c.name, c.filter);
+                    }
+                }
+                ["define", name, ref filter @ ..] if !filter.is_empty() => {
+                    let valid = name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+                    if !valid || matches!(name, "define" | "delete" | "none") {
+                        eprintln!("Error: '{}' can't be used as a context name.", name);
+                        std::process::exit(EXIT_USAGE);
+                    }
+                    let filter = filter.join(" ");
+                    if let Err(e) = context::Filter::parse(&filter) {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(EXIT_USAGE);
+                    }
+                    match contexts.iter_mut().find(|c| c.name == name) {
+                        Some(existing) => existing.filter = filter.clone(),
+                        None => contexts.push(context::Context {
+                            name: name.to_string(),
+                            filter: filter.clone(),
+                            active: false,
+                        }),
+                    }
+                    save_contexts(&contexts);
+                    println!(
+                        "Context '{}' is {}. Switch to it with 'task context {}'.",
+                        name, filter, name
+                    );
+                }
+                ["define", ..] => {
+                    eprintln!("Error: Usage: task context define <name> <filter>");
+                    std::process::exit(EXIT_USAGE);
+                }
+                ["delete", name] => {
+                    let i = find(&contexts, name);
+                    contexts.remove(i);
+                    save_contexts(&contexts);
+                    println!("Deleted context '{}'.", name);
+                }
+                ["none"] => {
+                    contexts.iter_mut().for_each(|c| c.active = false);
+                    save_contexts(&contexts);
+                    println!("No context active; listings show every task.");
+                }
+                [name] => {
+                    let i = find(&contexts, name);
+                    contexts
+                        .iter_mut()
+                        .enumerate()
+                        .for_each(|(j, c)| c.active = i == j);
+                    save_contexts(&contexts);
+                    println!("Context '{}' active: {}", name, contexts[i].filter);
+                }
+                _ => {
+                    eprintln!(
+                        "Error: Usage: task context [<name> | none | define <name> <filter> | delete <name>]"
+                    );
+                    std::process::exit(EXIT_USAGE);
+                }
+            }
+        }
+
+        Some(Commands::Lists) => {
+            for (name, path) in storage::lists(&task_dir) {
+                let lines = read_lines(&path);
+                let pending = tasks_with_status(&lines, Status::Pending).len();
+                let marker = if path == task_file { "*" } else { " " };
+                println!("{} {:<16} {} pending", marker, name, pending);
+            }
+        }
+
+        Some(Commands::Sections) => {
+            let lines = store.read();
+            let mut counts: Vec<(Option<&str>, usize)> = Vec::new();
+            for (section, line) in sections::of_lines(&lines).into_iter().zip(&lines) {
+                let pending = Task::parse(line).is_some_and(|t| t.status == Status::Pending);
+                match counts.iter_mut().find(|(s, _)| *s == section) {
+                    Some((_, count)) => *count += usize::from(pending),
+                    None if section.is_some() || pending => {
+                        counts.push((section, usize::from(pending)))
+                    }
+                    None => {}
+                }
+            }
+            if counts.iter().all(|(s, _)| s.is_none()) {
+                println!(
+                    "No sections. Start one with a '## ' heading, or 'task add --section <name>'."
+                );
+                return;
+            }
+            for (section, count) in counts {
+                println!(
+                    "{:<24} {} pending",
+                    section.unwrap_or("(no section)"),
+                    count
+                );
+            }
+        }
+
+        Some(Commands::Move { selection, to }) => {
+            let target = storage::list_file(&task_dir, &to, true).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(EXIT_NOT_FOUND);
+            });
+            if target == task_file {
+                eprintln!("Error: The tasks are already in list '{}'.", to);
+                std::process::exit(EXIT_USAGE);
+            }
+            let mut lines = store.read();
+            let (selected, missing) = selection.resolve(&lines, today);
+            if selected.is_empty() {
+                println!("No matching pending tasks.");
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+            for &(task_num, line_idx) in &selected {
+                if let Some(task) = Task::parse(&lines[line_idx]) {
+                    println!("Task {} moved to list '{}': {}", task_num, to, task.text);
+                }
+            }
+
+            let before = lines.clone();
+            let mut indices = selected.iter().map(|(_, idx)| *idx).collect::<Vec<_>>();
+            indices.sort_unstable();
+            let mut moved = Vec::new();
+            for &idx in indices.iter().rev() {
+                let block = 1 + task::notes(&lines, idx).len();
+                moved.push(lines.drain(idx..idx + block).collect::<Vec<_>>());
+            }
+            // In file order, everything about them as it was
+            let moved = moved.into_iter().rev().flatten().collect::<Vec<_>>();
+
+            // The other list is written first, so a failed save leaves a
+            // copy rather than losing the tasks
+            {
+                let _lock =
+                    storage::lock(&target, std::time::Duration::from_secs(config.lock_timeout));
+                if !storage::append_lines(&target, &moved) {
+                    let mut other = read_lines(&target);
+                    other.extend(moved);
+                    write_lines(&target, &other);
+                }
+            }
+            storage::allow_shrinking();
+            store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+            if missing {
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+        }
+
+        Some(Commands::Path) => {
+            let config_file = storage::config_file(&task_dir);
+            let state = |path: &Path| {
+                if path.exists() {
+                    ""
+                } else {
+                    " (not created yet)"
+                }
+            };
+            println!("Task directory: {}", task_dir.display());
+            println!(
+                "Task file:      {}{}",
+                task_file.display(),
+                state(&task_file)
+            );
+            println!(
+                "Config file:    {}{}",
+                config_file.display(),
+                state(&config_file)
+            );
+            println!("Backups:        {}", backup::dir(&task_dir).display());
+        }
+
+        Some(Commands::Info) => {
+            let chosen_by = if cli.file.is_some() {
+                "--file".to_string()
+            } else if let Some(name) = cli
+                .list
+                .clone()
+                .or_else(|| std::env::var(storage::LIST_VAR).ok())
+                .filter(|l| !l.is_empty())
+            {
+                format!("list '{}'", name)
+            } else if std::env::var("TASK_FILE").is_ok() {
+                "TASK_FILE".to_string()
+            } else if storage::find_project_dir().is_some() {
+                "project list (task init)".to_string()
+            } else {
+                "default".to_string()
+            };
+            let bytes = std::fs::read(&task_file).unwrap_or_default();
+            let stored = match crypt::tool_of(&bytes) {
+                crypt::Tool::None => "plain text",
+                crypt::Tool::Age => "encrypted with age",
+                crypt::Tool::Gpg => "encrypted with GPG",
+            };
+            let size = match bytes.len() {
+                n if n < 1024 => format!("{} bytes", n),
+                n => format!("{:.1} KiB", n as f64 / 1024.0),
+            };
+            let modified = std::fs::metadata(&task_file)
+                .and_then(|m| m.modified())
+                .map(|t| {
+                    let t = chrono::DateTime::<Local>::from(t);
+                    format!("{} {}", dates::show(t.date_naive()), t.format("%H:%M:%S"))
+                })
+                .unwrap_or_else(|_| "never".to_string());
+            let lines = store.read();
+            let tasks = lines
+                .iter()
+                .filter_map(|l| Task::parse(l))
+                .collect::<Vec<_>>();
+            let count = |status| tasks.iter().filter(|t| t.status == status).count();
+            let config_file = storage::config_file(&task_dir);
+
+            println!("task {}", env!("CARGO_PKG_VERSION"));
+            println!("Task file:      {}", task_file.display());
+            println!("  Chosen by:    {}", chosen_by);
+            println!("  Stored as:    {}, {:?} layout", stored, config.format);
+            println!("  Size:         {}, {} line(s)", size, lines.len());
+            println!("  Modified:     {}", modified);
+            println!(
+                "  Tasks:        {} pending, {} waiting, {} proposed, {} done, {} cancelled",
+                count(Status::Pending),
+                count(Status::Waiting),
+                count(Status::Proposed),
+                count(Status::Done),
+                count(Status::Cancelled)
+            );
+            if let Some(active) = &active_context {
+                println!(
+                    "  Context:      {}: {} (hides tasks from listings)",
+                    active.name, active.filter
+                );
+            }
+            println!(
+                "Config file:    {}{}",
+                config_file.display(),
+                if config_file.exists() {
+                    ""
+                } else {
+                    " (not created yet)"
+                }
+            );
+            println!("Task directory: {}", task_dir.display());
+            println!(
+                "Backups:        {} ({} of this file)",
+                backup::dir(&task_dir).display(),
+                backup::list(&task_dir, &task_file).len()
+            );
+        }
+
+        Some(Commands::Backup {
+            action: BackupAction::List,
+        }) => {
+            let backups = backup::list(&task_dir, &task_file);
+            if backups.is_empty() {
+                println!("No backups of {} yet.", task_file.display());
+                return;
+            }
+            println!("Backups of {} (newest first):", task_file.display());
+            for b in &backups {
+                println!(
+                    "  {}  {} {}  {} task(s)",
+                    b.stamp,
+                    b.taken.format("%a"),
+                    dates::show(b.taken.date()),
+                    b.tasks()
+                );
+            }
+            println!("Put one back with 'task restore <timestamp>'.");
+        }
+
+        Some(Commands::Delete { selection }) => {
+            let mut lines = store.read();
+            let (selected, missing) = selection.resolve(&lines, today);
+            if selected.is_empty() {
+                println!("No matching pending tasks.");
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+            for &(task_num, line_idx) in &selected {
+                if let Some(task) = Task::parse(&lines[line_idx]) {
+                    println!("Task {} moved to the trash: {}", task_num, task.text);
+                    stop_timer_for(&task_dir, &task);
+                }
+            }
+
+            let before = lines.clone();
+            let mut trash = trash::load(&task_dir);
+            let indices = selected.iter().map(|(_, idx)| *idx).collect::<Vec<_>>();
+            trash::put(&mut lines, &mut trash, &indices, today, config.format);
+            // The trash is written first, so a failed save leaves a copy
+            // rather than losing the tasks
+            write_lines(&trash::path(&task_dir), &trash);
+            storage::allow_shrinking();
+            store.write(&before, &lines, diff_mode.wanted(selected.len() > 1));
+            if missing {
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+        }
+
+        Some(Commands::Trash) => {
+            let trash = trash::load(&task_dir);
+            let deleted = trash::entries(&trash);
+            let lines = store.read();
+            let cancelled = tasks_with_status(&lines, Status::Cancelled);
+            if deleted.is_empty() && cancelled.is_empty() {
+                println!("The trash is empty.");
+                return;
+            }
+            if !deleted.is_empty() {
+                println!("Deleted:");
+                for (i, idx) in deleted.iter().enumerate() {
+                    println!("{} - {}", i + 1, display(&trash[*idx]));
+                }
+            }
+            if !cancelled.is_empty() {
+                println!("Cancelled:");
+                for (i, (_, line, _)) in cancelled.iter().rev().enumerate() {
+                    println!("{} - {}", deleted.len() + i + 1, display(line));
+                }
+            }
+            println!("Put one back as pending with 'task restore <num>'.");
+        }
+
+        // Backup timestamps are at least a whole date; shorter numbers are
+        // from 'task trash'
+        Some(Commands::Restore { what }) if what.len() < 8 => {
+            let Ok(num) = what.parse::<usize>() else {
+                eprintln!(
+                    "Error: '{}' is neither a number from 'task trash' nor a backup timestamp.",
+                    what
+                );
+                std::process::exit(EXIT_USAGE);
+            };
+            let mut trash = trash::load(&task_dir);
+            let deleted = trash::entries(&trash);
+            let mut lines = store.read();
+            let before = lines.clone();
+            let cancelled = tasks_with_status(&lines, Status::Cancelled)
+                .into_iter()
+                .rev()
+                .map(|(i, _, _)| i)
+                .collect::<Vec<_>>();
+            if num == 0 || num > deleted.len() + cancelled.len() {
+                eprintln!(
+                    "Error: No task {} in the trash. Run 'task trash' to see them.",
+                    num
+                );
+                std::process::exit(EXIT_NOT_FOUND);
+            }
+
+            let restored = if num <= deleted.len() {
+                let mut block = trash::take(&mut trash, deleted[num - 1]);
+                let Some(mut task) = Task::parse(&block[0]) else {
+                    return;
+                };
+                trash::reopen(&mut task);
+                block[0] = task.to_line(config.format);
+                lines.extend(block);
+                task
+            } else {
+                let line_idx = cancelled[num - deleted.len() - 1];
+                let Some(mut task) = Task::parse(&lines[line_idx]) else {
+                    return;
+                };
+                trash::reopen(&mut task);
+                lines[line_idx] = task.to_line(config.format);
+                task
+            };
+            store.write(&before, &lines, diff_mode.wanted(false));
+            if num <= deleted.len() {
+                write_lines(&trash::path(&task_dir), &trash);
+            }
+            println!("Restored as pending: {}", restored.text);
+        }
+
+        Some(Commands::Restore { what: stamp }) => {
+            let found = backup::find(&task_dir, &task_file, &stamp).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(EXIT_NOT_FOUND);
+            });
+            let restored = storage::read_text(&found.path).unwrap_or_else(|e| {
+                eprintln!("Error reading {}: {}", found.path.display(), e);
+                std::process::exit(storage::EXIT_STORAGE);
+            });
+            let restored = restored.lines().map(String::from).collect::<Vec<_>>();
+            let lines = store.read();
+            if lines == restored {
+                println!("The task file already matches backup {}.", found.stamp);
+                return;
+            }
+            // The current version is backed up by the write itself
+            storage::allow_shrinking();
+            store.write(&lines, &restored, diff_mode.wanted(true));
+            println!(
+                "Restored {} from backup {}; the version it replaced was backed up.",
+                task_file.display(),
+                found.stamp
+            );
+        }
+
+        // The same command list and examples as `task -h`
+        None => {
+            let _ = help::annotate(Cli::command()).print_help();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn pick(nums: &[usize]) -> Selection {
+        Selection {
+            tasks: vec![TaskArg::Nums(TaskNums(nums.to_vec()))],
+            all_overdue: false,
+            tag: None,
+        }
+    }
+
+    /// A task directory with no timer running.
+    fn task_dir() -> PathBuf {
+        std::env::temp_dir().join("task-tests-no-such-dir")
+    }
+
+    fn close_in(store: &mut MemoryStore, nums: &[usize], closing: &Closing) -> Option<bool> {
+        let today = date("2025-09-15");
+        let mode = diff::Mode::new(false, true);
+        let format = TaskFormat::Obsidian;
+        close(
+            store,
+            &pick(nums),
+            closing,
+            today,
+            format,
+            &task_dir(),
+            mode,
+        )
+    }
+
+    fn done_on(day: &str) -> Closing<'static> {
+        Closing {
+            status: Status::Done,
+            date: date(day),
+            reason: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn added_task_can_be_completed() {
+        let today = date("2025-09-15");
+        let mut store = MemoryStore::new(&["# Work log"]);
+        let task = Task::new("Write report".to_string(), Some(today), today);
+        store.append(&[task.to_line(TaskFormat::Obsidian)], false);
+        assert_eq!(
+            store.lines,
+            [
+                "# Work log",
+                "- [ ] Write report ➕ 2025-09-15 📅 2025-09-15"
+            ]
+        );
+
+        assert_eq!(
+            close_in(&mut store, &[1], &done_on("2025-09-15")),
+            Some(false)
+        );
+        let done = Task::parse(&store.lines[1]).unwrap();
+        assert_eq!(done.status, Status::Done);
+        assert_eq!(done.done, Some(today));
+        assert_eq!(done.due, Some(today));
+        assert_eq!(store.writes, 2);
+    }
+
+    #[test]
+    fn cancel_records_reason_and_note() {
+        let mut store = MemoryStore::new(&["- [ ] Old idea"]);
+        let closing = Closing {
+            status: Status::Cancelled,
+            date: date("2025-09-14"),
+            reason: Some("superseded"),
+            note: Some("see #12"),
+        };
+        assert_eq!(close_in(&mut store, &[1], &closing), Some(false));
+        let task = Task::parse(&store.lines[0]).unwrap();
+        assert_eq!(task.status, Status::Cancelled);
+        assert_eq!(task.cancelled, Some(date("2025-09-14")));
+        assert_eq!(task.field("reason"), Some("superseded"));
+        assert_eq!(task.field("note"), Some("see #12"));
+    }
+
+    #[test]
+    fn completing_a_recurring_task_adds_the_next_above_it() {
+        let mut store = MemoryStore::new(&["- [ ] Water plants 🔁 every week 📅 2025-09-15"]);
+        close_in(&mut store, &[1], &done_on("2025-09-15"));
+        assert_eq!(store.lines.len(), 2);
+        let next = Task::parse(&store.lines[0]).unwrap();
+        assert_eq!(next.status, Status::Pending);
+        assert_eq!(next.due, Some(date("2025-09-22")));
+        assert_eq!(Task::parse(&store.lines[1]).unwrap().status, Status::Done);
+    }
+
+    #[test]
+    fn unknown_numbers_leave_the_file_alone() {
+        let mut store = MemoryStore::new(&["- [ ] Only task"]);
+        assert_eq!(close_in(&mut store, &[2], &done_on("2025-09-15")), None);
+        assert_eq!(store.writes, 0);
+        // A bad number among good ones is reported but the rest go ahead
+        assert_eq!(
+            close_in(&mut store, &[1, 2], &done_on("2025-09-15")),
+            Some(true)
+        );
+        assert_eq!(store.writes, 1);
+    }
+
+    /// Lines as listings read them under a context and section: what they
+    /// hide blanked out, as `storage::read_through_context` does.
+    fn viewed(lines: &[String], section: &str, filter: &context::Filter) -> Vec<String> {
+        let within = sections::within(lines, section);
+        lines
+            .iter()
+            .zip(within)
+            .map(|(line, within)| {
+                let hidden = Task::parse(line).is_some_and(|t| !filter.matches(&t));
+                if within && !hidden {
+                    line.clone()
+                } else {
+                    String::new()
+                }
+            })
+            .collect()
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn every_command_takes_the_number_listings_show() {
+        let today = date("2025-09-15");
+        let lines = lines(&[
+            "# Work log",
+            "- [ ] Draft plan 📅 2025-09-20",
+            "    a note",
+            "- [x] Old task ✅ 2025-09-01",
+            "- [ ] Fix bug ⏫ 📅 2025-09-16",
+            "- [-] Dropped ❌ 2025-09-02",
+            "- [ ] Call Sam",
+        ]);
+        let pending = pending_indices(&lines, today);
+        assert_eq!(pending.len(), 3);
+        for (i, &line_idx) in pending.iter().enumerate() {
+            let n = i + 1;
+            // What `pending`, `all` and `today` print for the task
+            assert_eq!(task_ref(&pending, line_idx), n.to_string());
+            // What `done`, `cancel` and the single-task commands look up
+            assert_eq!(task_line(&pending, n), Some(line_idx));
+            assert_eq!(
+                pick(&[n]).resolve(&lines, today),
+                (vec![(n, line_idx)], false)
+            );
+            let text = Task::parse(&lines[line_idx]).unwrap().text;
+            assert_eq!(find_by_text(&lines, &pending, &text), Ok(n));
+        }
+        // Lines that aren't pending tasks are referred to by line number
+        assert_eq!(task_ref(&pending, 3), "L4");
+        assert_eq!(task_line(&pending, 0), None);
+        assert_eq!(task_line(&pending, 4), None);
+    }
+
+    #[test]
+    fn edits_and_completions_hit_the_listed_task() {
+        let today = date("2025-09-15");
+        let mut store = MemoryStore::new(&[
+            "- [ ] Draft plan 📅 2025-09-20",
+            "- [ ] Fix bug ⏫ 📅 2025-09-16",
+            "- [ ] Call Sam",
+        ]);
+        let pending = pending_indices(&store.lines, today);
+        let listed = |n: usize| store.lines[pending[n - 1]].clone();
+        let (first, second) = (listed(1), listed(2));
+
+        let mode = diff::Mode::new(false, true);
+        change_priority(&mut store, TaskFormat::Obsidian, &[2], today, mode, |p| {
+            p.up()
+        });
+        let promoted = Task::parse(&store.lines[pending[1]]).unwrap();
+        let second = Task::parse(&second).unwrap();
+        assert_eq!(promoted.text, second.text);
+        assert_eq!(promoted.priority, second.priority.up());
+
+        // Promoting didn't change what number 1 is
+        close_in(&mut store, &[1], &done_on("2025-09-15"));
+        let done = Task::parse(&store.lines[pending[0]]).unwrap();
+        assert_eq!(done.text, Task::parse(&first).unwrap().text);
+        assert_eq!(done.status, Status::Done);
+    }
+
+    #[test]
+    fn single_task_edits_rewrite_only_that_line() {
+        let today = date("2025-09-15");
+        let mut store = MemoryStore::new(&[
+            "- [ ] Draft plan 📅 2025-09-20",
+            "    a note",
+            "- [ ] Call Sam",
+        ]);
+        // Newest first: 2 is "Draft plan"
+        let edited = edit_task(&mut store, 2, today, TaskFormat::Obsidian, false, |task| {
+            task.due = Some(date("2025-09-30"));
+            Ok(task.text.clone())
+        });
+        assert_eq!(edited.as_deref(), Some("Draft plan"));
+        assert_eq!(
+            store.lines,
+            [
+                "- [ ] Draft plan 📅 2025-09-30",
+                "    a note",
+                "- [ ] Call Sam",
+            ]
+        );
+        assert_eq!(store.writes, 1);
+
+        // Written in the configured layout
+        edit_task(&mut store, 1, today, TaskFormat::Dataview, false, |task| {
+            task.set_field("estimate", "15m");
+            Ok(())
+        });
+        assert_eq!(store.lines[2], "- [ ] Call Sam [estimate:: 15m]");
+    }
+
+    #[test]
+    fn snoozing_counts_and_never_lands_in_the_past() {
+        let today = date("2025-09-15");
+        let mut store = MemoryStore::new(&["- [ ] Overdue 📅 2025-09-01"]);
+        let snooze_by_a_day = |store: &mut MemoryStore| {
+            edit_task(store, 1, today, TaskFormat::Obsidian, false, |task| {
+                snooze(task, "+1d", today)
+            })
+        };
+        // An overdue task moves on from today, a later one from its date
+        assert_eq!(snooze_by_a_day(&mut store), Some((date("2025-09-16"), 1)));
+        assert_eq!(snooze_by_a_day(&mut store), Some((date("2025-09-17"), 2)));
+        let task = Task::parse(&store.lines[0]).unwrap();
+        assert_eq!(task.due, Some(date("2025-09-17")));
+        assert_eq!(task.snoozes(), 2);
+    }
+
+    #[test]
+    fn critical_tasks_number_first_and_deferred_ones_last() {
+        let today = date("2025-09-15");
+        let lines = lines(&[
+            "- [ ] Not started 🛫 2025-09-30 📅 2025-10-01",
+            "- [ ] Older 📅 2025-09-16",
+            "- [ ] Long overdue 🔽 📅 2025-09-01",
+            "- [ ] Newer",
+        ]);
+        let texts = |highlight| {
+            let config = nag::NagConfig {
+                highlight,
+                ..Default::default()
+            };
+            numbering(
+                &lines,
+                today,
+                None,
+                |_| true,
+                |t| nag::highlighted(&config, today, t),
+            )
+            .into_iter()
+            .map(|i| Task::parse(&lines[i]).unwrap().text)
+            .collect::<Vec<_>>()
+        };
+        // Newest first among the rest, urgency being unset here
+        assert_eq!(
+            texts(true),
+            ["Long overdue", "Newer", "Older", "Not started"]
+        );
+        assert_eq!(
+            texts(false),
+            ["Newer", "Long overdue", "Older", "Not started"]
+        );
+    }
+
+    #[test]
+    fn filtered_listings_number_as_commands_do() {
+        let today = date("2025-09-15");
+        let lines = lines(&[
+            "## Home",
+            "- [ ] Paint fence #diy",
+            "## Work",
+            "- [ ] Write spec #deep",
+            "- [ ] Reply to email",
+            "- [ ] Review PR #deep",
+            "## Errands",
+            "- [ ] Buy milk #deep",
+        ]);
+        let filter = context::Filter::parse("+deep").unwrap();
+        let within = sections::within(&lines, "work");
+
+        // `done` reads the whole file and filters as it numbers; listings
+        // read the file with the rest blanked out
+        let commands = numbering(
+            &lines,
+            today,
+            Some(&within),
+            |t| filter.matches(t),
+            |_| false,
+        );
+        let view = viewed(&lines, "work", &filter);
+        let listed = numbering(
+            &view,
+            today,
+            Some(&sections::within(&view, "work")),
+            |t| filter.matches(t),
+            |_| false,
+        );
+        assert_eq!(commands, listed);
+        assert_eq!(commands, [5, 3]);
+    }
+
+    #[test]
+    fn malformed_lines_are_kept_and_not_numbered() {
+        let mut store = MemoryStore::new(&[
+            "-[ ] Missing space",
+            "- [ ] First",
+            "random note",
+            "- [y] Odd box",
+            "- [ ] Second",
+        ]);
+        // Newest first: 1 is "Second", 2 is "First"
+        close_in(&mut store, &[2], &done_on("2025-09-15"));
+        assert_eq!(store.lines[0], "-[ ] Missing space");
+        assert_eq!(Task::parse(&store.lines[1]).unwrap().status, Status::Done);
+        assert_eq!(store.lines[2], "random note");
+        assert_eq!(store.lines[3], "- [y] Odd box");
+        assert_eq!(
+            Task::parse(&store.lines[4]).unwrap().status,
+            Status::Pending
+        );
     }
 }
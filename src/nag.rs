@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+
+use crate::notify;
+use crate::storage;
+use crate::task::{Status, Task};
+
+/// `[nag]` config: how many days past due a task becomes critical, with
+/// optional per-priority overrides such as `[nag.priority] high = 1`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NagConfig {
+    pub threshold: i64,
+    pub priority: HashMap<String, i64>,
+    /// Mark critical tasks 🚨 and number them first in every listing, not
+    /// only in `task nag`
+    pub highlight: bool,
+}
+
+impl Default for NagConfig {
+    fn default() -> Self {
+        NagConfig {
+            threshold: 3,
+            priority: HashMap::new(),
+            highlight: false,
+        }
+    }
+}
+
+struct Nagger {
+    config: NagConfig,
+    today: NaiveDate,
+}
+
+static NAGGER: OnceLock<Nagger> = OnceLock::new();
+
+pub fn init(config: &NagConfig, today: NaiveDate) -> Result<(), String> {
+    for name in config.priority.keys() {
+        if crate::task::Priority::from_name(name).is_none() {
+            return Err(format!("unknown priority '{}' in [nag.priority]", name));
+        }
+    }
+    let _ = NAGGER.set(Nagger {
+        config: config.clone(),
+        today,
+    });
+    Ok(())
+}
+
+/// Escalation level of a pending task: 1 once it is `threshold` days
+/// overdue, 2 at twice that and 3 (critical) at four times.
+pub fn level(task: &Task) -> Option<u8> {
+    let nagger = NAGGER.get()?;
+    level_under(&nagger.config, nagger.today, task)
+}
+
+/// [`level`] under `config` with today being `today`.
+fn level_under(config: &NagConfig, today: NaiveDate, task: &Task) -> Option<u8> {
+    if task.status != Status::Pending {
+        return None;
+    }
+    let overdue = (today - task.due?).num_days();
+    let threshold = config
+        .priority
+        .get(task.priority.name())
+        .copied()
+        .unwrap_or(config.threshold);
+    if overdue <= 0 || overdue < threshold {
+        return None;
+    }
+    let step = threshold.max(1);
+    Some(match overdue {
+        d if d >= threshold + 3 * step => 3,
+        d if d >= threshold + step => 2,
+        _ => 1,
+    })
+}
+
+pub fn is_critical(task: &Task) -> bool {
+    level(task).is_some()
+}
+
+/// Whether listings mark `task` 🚨 and number it first: it's critical and
+/// `highlight` is on.
+pub fn is_highlighted(task: &Task) -> bool {
+    NAGGER
+        .get()
+        .is_some_and(|n| highlighted(&n.config, n.today, task))
+}
+
+/// [`is_highlighted`] under `config` with today being `today`.
+pub fn highlighted(config: &NagConfig, today: NaiveDate, task: &Task) -> bool {
+    config.highlight && level_under(config, today, task).is_some()
+}
+
+/// How long to wait between reminders at each escalation level.
+fn interval(level: u8) -> Duration {
+    match level {
+        1 => Duration::hours(24),
+        2 => Duration::hours(4),
+        _ => Duration::hours(1),
+    }
+}
+
+fn key(task: &Task) -> String {
+    let created = task.created.map(|d| d.to_string()).unwrap_or_default();
+    format!("{}|{}", created, task.text)
+}
+
+/// Remind about every critically overdue task whose reminder interval has
+/// elapsed, remembering when each was last sent in `nag_state`. Returns the
+/// number of notifications sent.
+pub fn remind(tasks: &[&Task], task_dir: &Path, now: NaiveDateTime) -> Result<usize, String> {
+    let state_path = task_dir.join("nag_state");
+    let mut last_sent = storage::read_text(&state_path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let (time, key) = l.split_once('\t')?;
+            let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S").ok()?;
+            Some((key.to_string(), time))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut sent = 0;
+    let mut current = HashMap::new();
+    for task in tasks {
+        let Some(level) = level(task) else {
+            continue;
+        };
+        let key = key(task);
+        let last = last_sent.remove(&key);
+        if last.is_some_and(|t| now - t < interval(level)) {
+            current.insert(key, last.unwrap_or(now));
+            continue;
+        }
+
+        let days = task.due.map_or(0, |d| (now.date() - d).num_days());
+        let summary = if level >= 3 {
+            format!("CRITICAL: {} days overdue", days)
+        } else {
+            format!("Overdue by {} days", days)
+        };
+        notify::send(&summary, &task.text, level >= 2)?;
+        current.insert(key, now);
+        sent += 1;
+    }
+
+    // Tasks that were acted on drop out of the state file
+    let contents = current
+        .iter()
+        .map(|(key, time)| format!("{}\t{}\n", time.format("%Y-%m-%dT%H:%M:%S"), key))
+        .collect::<String>();
+    storage::write_text(&state_path, &contents)
+        .map_err(|e| format!("error saving nag state: {}", e))?;
+    Ok(sent)
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveTime};
+use notify_rust::Notification;
+use serde::Deserialize;
+
+use crate::storage;
+use crate::task::{Status, Task};
+
+/// `[notify]` config for `task notify`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Also remind about tasks due within this many days
+    pub lead_days: i64,
+    /// Remind about a task due at a time this many minutes before it
+    pub lead_minutes: i64,
+}
+
+// Past this many tasks a single summary notification is sent instead
+const MAX_INDIVIDUAL: usize = 5;
+
+/// Show a desktop notification. `critical` asks the notification server to
+/// keep it on screen where the platform supports urgency levels.
+pub fn send(summary: &str, body: &str, critical: bool) -> Result<(), String> {
+    let mut notification = Notification::new();
+    notification.appname("task").summary(summary).body(body);
+
+    #[cfg(any(all(unix, not(target_os = "macos")), target_os = "windows"))]
+    if critical {
+        notification.urgency(notify_rust::Urgency::Critical);
+    }
+    #[cfg(target_os = "macos")]
+    let _ = critical;
+
+    notification
+        .show()
+        .map(|_| ())
+        .map_err(|e| format!("could not send notification: {}", e))
+}
+
+fn key(task: &Task) -> String {
+    let created = task.created.map(|d| d.to_string()).unwrap_or_default();
+    format!("{}|{}", created, task.text)
+}
+
+fn when(task: &Task, due: NaiveDate, today: NaiveDate) -> String {
+    match (due - today).num_days() {
+        0 => match task.due_time() {
+            Some(time) => format!("Due at {}", time.format("%H:%M")),
+            None => "Due today".to_string(),
+        },
+        1 => "Due tomorrow".to_string(),
+        d if d > 0 => format!("Due in {} days ({})", d, due.format("%a %d %b")),
+        d => format!("Overdue by {} days", -d),
+    }
+}
+
+/// Pending tasks that are overdue, due today or due within `lead_days`,
+/// most overdue first. A task due today at a time only counts from
+/// `lead_minutes` before then, so its reminder comes when it matters.
+pub fn due_soon(
+    tasks: &[Task],
+    today: NaiveDate,
+    now: NaiveTime,
+    lead_days: i64,
+    lead_minutes: i64,
+) -> Vec<&Task> {
+    let horizon = today + chrono::Duration::days(lead_days.max(0));
+    let lead = chrono::Duration::minutes(lead_minutes.max(0));
+    let mut due = tasks
+        .iter()
+        .filter(|t| t.status == Status::Pending && t.due.is_some_and(|d| d <= horizon))
+        .filter(|t| match (t.due, t.due_time()) {
+            (Some(d), Some(time)) if d == today => now + lead >= time,
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+    due.sort_by_key(|t| (t.due, t.due_time()));
+    due
+}
+
+/// Send a notification for each task in `due` not already notified today,
+/// remembering what was sent in `notify_state` so frequent cron runs don't
+/// repeat themselves. Returns the tasks notified.
+pub fn remind<'a>(
+    due: &[&'a Task],
+    task_dir: &Path,
+    today: NaiveDate,
+) -> Result<Vec<&'a Task>, String> {
+    let state_path = task_dir.join("notify_state");
+    let state = storage::read_text(&state_path).unwrap_or_default();
+    let mut lines = state.lines();
+    let notified = match lines.next() {
+        Some(date) if date == today.to_string() => lines.map(String::from).collect(),
+        _ => HashSet::new(),
+    };
+
+    let fresh = due
+        .iter()
+        .copied()
+        .filter(|t| !notified.contains(&key(t)))
+        .collect::<Vec<_>>();
+    if fresh.len() > MAX_INDIVIDUAL {
+        let body = fresh
+            .iter()
+            .filter_map(|t| t.due.map(|d| format!("{}: {}", when(t, d, today), t.text)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        send(
+            &format!("{} tasks need attention", fresh.len()),
+            &body,
+            false,
+        )?;
+    } else {
+        for task in &fresh {
+            let Some(due) = task.due else { continue };
+            send(&when(task, due, today), &task.text, due < today)?;
+        }
+    }
+
+    let mut contents = format!("{}\n", today);
+    for key in notified.into_iter().chain(fresh.iter().map(|t| key(t))) {
+        contents.push_str(&key);
+        contents.push('\n');
+    }
+    storage::write_text(&state_path, &contents)
+        .map_err(|e| format!("error saving notify state: {}", e))?;
+    Ok(fresh)
+}
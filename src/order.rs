@@ -0,0 +1,189 @@
+use std::cmp::Reverse;
+use std::sync::OnceLock;
+
+use chrono::{Duration, NaiveDate};
+use clap::ValueEnum;
+
+use crate::dates;
+use crate::task::{Status, Task};
+use crate::urgency;
+
+/// What `--sort` orders a listing by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Earliest due first; undated tasks last
+    Due,
+    /// Oldest first; tasks without a creation date last
+    Created,
+    /// Highest priority first
+    Priority,
+    /// Alphabetically, ignoring case
+    Text,
+    /// Most urgent first, by the `[urgency]` weights
+    Urgency,
+}
+
+/// What `--group-by` puts listed tasks under headers by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Overdue, today, this week, later and no due date
+    Due,
+    /// Each tag, so a task with two tags is listed twice
+    Tag,
+    /// A `[project:: …]` field, or the top of a nested tag (`#home/garden`)
+    Project,
+    /// Pending, waiting, proposed, done and cancelled
+    Status,
+}
+
+struct Order {
+    sort: Option<SortKey>,
+    reverse: bool,
+    limit: Option<usize>,
+    group_by: Option<GroupBy>,
+}
+
+/// The `--sort`, `--reverse`, `--limit` and `--group-by` options, for every
+/// listing in this process.
+static ORDER: OnceLock<Order> = OnceLock::new();
+
+pub fn init(sort: Option<SortKey>, reverse: bool, limit: Option<usize>, group_by: Option<GroupBy>) {
+    let _ = ORDER.set(Order {
+        sort,
+        reverse,
+        limit,
+        group_by,
+    });
+}
+
+/// Whether `--sort` or `--group-by` was given, so a listing should drop
+/// rows that aren't tasks rather than have them land out of place.
+pub fn rearranges() -> bool {
+    ORDER
+        .get()
+        .is_some_and(|o| o.sort.is_some() || o.group_by.is_some())
+}
+
+/// `rows` of a listing sorted, reversed and cut to the limit as asked.
+/// Without `--sort`, the listing's own order is kept. Rows keep the task
+/// numbers they were given, so they still work with `done` and the rest.
+pub fn apply<T>(mut rows: Vec<T>, task: impl Fn(&T) -> Option<&Task>) -> Vec<T> {
+    let Some(order) = ORDER.get() else {
+        return rows;
+    };
+    // Stable sorts, so ties stay in the listing's order
+    match order.sort {
+        Some(SortKey::Due) => rows.sort_by_key(|r| {
+            let due = task(r).and_then(|t| t.due.map(|d| (d, t.due_time())));
+            (due.is_none(), due)
+        }),
+        Some(SortKey::Created) => rows.sort_by_key(|r| {
+            let created = task(r).and_then(|t| t.created);
+            (created.is_none(), created)
+        }),
+        Some(SortKey::Priority) => rows.sort_by_key(|r| Reverse(task(r).map(|t| t.priority))),
+        Some(SortKey::Text) => rows.sort_by_key(|r| task(r).map(|t| t.text.to_lowercase())),
+        Some(SortKey::Urgency) => rows.sort_by(|a, b| {
+            let score = |r| task(r).map_or(f64::MIN, urgency::score);
+            score(b).total_cmp(&score(a))
+        }),
+        None => {}
+    }
+    if order.reverse {
+        rows.reverse();
+    }
+    if let Some(limit) = order.limit {
+        rows.truncate(limit);
+    }
+    rows
+}
+
+/// The project a task belongs to: its `[project:: …]` field, or the top
+/// level of its first nested tag.
+fn project(task: &Task) -> Option<String> {
+    if let Some(project) = task.field("project") {
+        return Some(project.to_string());
+    }
+    task.tags()
+        .iter()
+        .find_map(|t| t.split_once('/'))
+        .map(|(top, _)| top.to_lowercase())
+}
+
+/// The groups a task is listed under, as a sort key and a header.
+fn groups(task: &Task, group_by: GroupBy, today: NaiveDate) -> Vec<(usize, String)> {
+    match group_by {
+        GroupBy::Due => {
+            let bucket = match task.due {
+                Some(due) if due < today => (0, "Overdue"),
+                Some(due) if due == today => (1, "Today"),
+                Some(due) if due <= today + Duration::days(7) => (2, "This week"),
+                Some(_) => (3, "Later"),
+                None => (4, "No due date"),
+            };
+            vec![(bucket.0, bucket.1.to_string())]
+        }
+        GroupBy::Tag => {
+            let tags = task.tags();
+            if tags.is_empty() {
+                return vec![(1, "No tag".to_string())];
+            }
+            let mut keys = tags
+                .iter()
+                .map(|t| (0, format!("#{}", t.to_lowercase())))
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            keys
+        }
+        GroupBy::Project => match project(task) {
+            Some(project) => vec![(0, project)],
+            None => vec![(1, "No project".to_string())],
+        },
+        GroupBy::Status => {
+            let status = match task.status {
+                Status::Pending => (0, "Pending"),
+                Status::Waiting => (1, "Waiting"),
+                Status::Proposed => (2, "Proposed"),
+                Status::Done => (3, "Done"),
+                Status::Cancelled => (4, "Cancelled"),
+            };
+            vec![(status.0, status.1.to_string())]
+        }
+    }
+}
+
+/// Print `rows` of a listing, ordered by [`apply`], each as `line` gives
+/// it. With `--group-by`, they go under a header per group, with counts.
+pub fn list<T>(rows: Vec<T>, task: impl Fn(&T) -> Option<&Task>, line: impl Fn(&T) -> String) {
+    let rows = apply(rows, &task);
+    let Some(group_by) = ORDER.get().and_then(|o| o.group_by) else {
+        for row in &rows {
+            println!("{}", line(row));
+        }
+        return;
+    };
+    let today = dates::today();
+    let mut grouped: Vec<((usize, String), Vec<&T>)> = Vec::new();
+    for row in &rows {
+        let Some(t) = task(row) else {
+            continue;
+        };
+        for key in groups(t, group_by, today) {
+            match grouped.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, members)) => members.push(row),
+                None => grouped.push((key, vec![row])),
+            }
+        }
+    }
+    grouped.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (i, ((_, header), members)) in grouped.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{} ({})", header, members.len());
+        for row in members {
+            println!("  {}", line(row).replace('\n', "\n  "));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::triage;
+
+/// Set for the run whose output is being captured for the pager, so it
+/// doesn't try to page it again.
+const PAGED_VAR: &str = "TASK_PAGED";
+
+/// The pager to use: the configured one, else `$PAGER`, else `less`. None
+/// when paging is off (an empty `pager` in the config) or output isn't
+/// going to a terminal.
+pub fn command(configured: Option<&str>) -> Option<String> {
+    if std::env::var(PAGED_VAR).is_ok() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let pager = match configured {
+        Some(pager) => pager.to_string(),
+        None => std::env::var("PAGER").unwrap_or_else(|_| "less".to_string()),
+    };
+    Some(pager).filter(|p| !p.trim().is_empty())
+}
+
+/// The terminal's rows and columns.
+fn size() -> Option<(usize, usize)> {
+    let size = triage::stty(&["size"]).ok()?;
+    let (rows, cols) = size.split_once(' ')?;
+    Some((rows.parse().ok()?, cols.parse().ok()?))
+}
+
+/// Characters `line` takes up on screen, leaving out colour codes.
+fn width(line: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+/// Run this command again with its output captured, then show the output
+/// through `pager` if it doesn't fit on the screen, or as it is if it does.
+/// Returns the command's exit status, or `None` if it couldn't be run this
+/// way and should just run as usual.
+pub fn run(pager: &str) -> Option<i32> {
+    let (rows, cols) = size()?;
+    let mut child = Command::new(std::env::current_exe().ok()?);
+    child.args(std::env::args_os().skip(1)).env(PAGED_VAR, "1");
+    // Keep the colours the terminal would have got
+    if std::env::var_os("NO_COLOR").is_none() {
+        child.env("CLICOLOR_FORCE", "1");
+    }
+    let output = child.stderr(Stdio::inherit()).output().ok()?;
+    let code = output.status.code().unwrap_or(1);
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let height = text
+        .lines()
+        .map(|l| width(l).div_ceil(cols.max(1)).max(1))
+        .sum::<usize>();
+    // The last row is left for the shell prompt
+    if height < rows {
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(&output.stdout);
+        let _ = stdout.flush();
+        return Some(code);
+    }
+
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut paging = Command::new(shell);
+    paging.args([flag, pager]).stdin(Stdio::piped());
+    // As git does: show colours, and don't clear the screen on quitting
+    if std::env::var_os("LESS").is_none() {
+        paging.env("LESS", "FRX");
+    }
+    let Ok(mut paging) = paging.spawn() else {
+        // No pager after all; the output still has to go somewhere
+        let _ = std::io::stdout().write_all(&output.stdout);
+        return Some(code);
+    };
+    if let Some(mut stdin) = paging.stdin.take() {
+        // The pager closing early (quitting before the end) isn't an error
+        let _ = stdin.write_all(&output.stdout);
+    }
+    let _ = paging.wait();
+    Some(code)
+}
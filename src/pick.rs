@@ -0,0 +1,214 @@
+use std::io::{IsTerminal, Read, Write};
+
+use crate::triage::RawMode;
+
+/// What to do with the picked task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Done,
+    Cancel,
+    Edit,
+    Show,
+}
+
+// Matches shown at once; the best ones come first
+const WINDOW: usize = 10;
+
+/// How well one word of a query matches `text`, both lowercased: its
+/// characters in order, with runs and word starts scoring higher and gaps
+/// lower.
+fn word_score(word: &[char], text: &[char]) -> Option<i64> {
+    let mut best = None;
+    for start in (0..text.len()).filter(|&i| text[i] == word[0]) {
+        let mut score = 0;
+        let mut prev: Option<usize> = None;
+        let mut at = start;
+        for &c in word {
+            // Later starts leave even less text, so they can't match either
+            let Some(found) = text[at..].iter().position(|&t| t == c) else {
+                return best;
+            };
+            let i = at + found;
+            score += 1;
+            if i == 0 || !text[i - 1].is_alphanumeric() {
+                score += 8;
+            }
+            match prev {
+                Some(p) if p + 1 == i => score += 5,
+                Some(p) => score -= (i - p - 1).min(5) as i64,
+                None => {}
+            }
+            prev = Some(i);
+            at = i + 1;
+        }
+        best = best.max(Some(score));
+    }
+    best
+}
+
+/// How well `query` matches `text`, ignoring case, or `None` if it doesn't.
+/// Each word of the query must appear in order, though not necessarily
+/// together, as in skim or fzf: "grcr" matches "Buy groceries".
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text = text
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    query.split_whitespace().try_fold(0, |total, word| {
+        let word = word
+            .chars()
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<_>>();
+        Some(total + word_score(&word, &text)?)
+    })
+}
+
+/// Indices of the `items` (text, shown) whose text matches `query`, best
+/// first; ties keep their order.
+fn matches(items: &[(String, String)], query: &str) -> Vec<usize> {
+    let mut scored = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (text, _))| score(query, text).map(|s| (i, s)))
+        .collect::<Vec<_>>();
+    scored.sort_by_key(|(_, s)| std::cmp::Reverse(*s));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
+
+enum Key {
+    Up,
+    Down,
+    Enter,
+    Backspace,
+    Char(char),
+    Quit,
+}
+
+fn read_key(input: &mut impl Read) -> Key {
+    let mut next = || {
+        let mut b = [0u8; 1];
+        input.read(&mut b).ok().filter(|n| *n == 1).map(|_| b[0])
+    };
+    let Some(byte) = next() else {
+        return Key::Quit;
+    };
+    match byte {
+        // Arrow keys arrive as ESC [ A/B; ESC followed by anything else quits
+        0x1b => match (next(), next()) {
+            (Some(b'['), Some(b'A')) => Key::Up,
+            (Some(b'['), Some(b'B')) => Key::Down,
+            _ => Key::Quit,
+        },
+        // Ctrl-P and Ctrl-N
+        0x10 => Key::Up,
+        0x0e => Key::Down,
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        // Ctrl-C and Ctrl-D
+        0x03 | 0x04 => Key::Quit,
+        b if b < 0x80 => Key::Char(b as char),
+        // The rest of a UTF-8 character
+        b => {
+            let len = b.leading_ones() as usize;
+            let mut bytes = vec![b];
+            bytes.extend((1..len).map_while(|_| next()));
+            match std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| s.chars().next())
+            {
+                Some(c) => Key::Char(c),
+                None => Key::Char(char::REPLACEMENT_CHARACTER),
+            }
+        }
+    }
+}
+
+fn draw(
+    out: &mut impl Write,
+    items: &[(String, String)],
+    query: &str,
+    found: &[usize],
+    cursor: usize,
+    status: &str,
+) {
+    let _ = write!(out, "\r\x1b[J> {}\r\n", query);
+    for (i, &item) in found.iter().enumerate().take(WINDOW) {
+        let pointer = if i == cursor { ">" } else { " " };
+        let _ = write!(out, "{} {}\r\n", pointer, items[item].1);
+    }
+    let _ = write!(out, "{}/{}  {}", found.len(), items.len(), status);
+    let rows = found.len().min(WINDOW) + 1;
+    // Back to the query line, with the cursor after the query
+    let _ = write!(out, "\r\x1b[{}A\x1b[{}C", rows, query.chars().count() + 2);
+    let _ = out.flush();
+}
+
+/// Let the user narrow `items` (text to match, line to show) down by typing
+/// part of the text, pick one and choose what to do with it. Returns the
+/// action and the index of the picked item, or `None` if they quit.
+pub fn pick(items: &[(String, String)], query: &str) -> Result<Option<(Action, usize)>, String> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err("pick needs a terminal".to_string());
+    }
+    if items.is_empty() {
+        return Ok(None);
+    }
+
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    let mut query = query.to_string();
+    let mut cursor = 0;
+    let picked = {
+        let _raw = RawMode::enter()?;
+        'search: loop {
+            let found = matches(items, &query);
+            cursor = cursor.min(found.len().min(WINDOW).saturating_sub(1));
+            draw(
+                &mut stdout,
+                items,
+                &query,
+                &found,
+                cursor,
+                "type to search  ↑/↓ move  enter pick  ctrl-c quit",
+            );
+            match read_key(&mut stdin) {
+                Key::Up => cursor = cursor.saturating_sub(1),
+                Key::Down => cursor += 1,
+                Key::Backspace => {
+                    query.pop();
+                    cursor = 0;
+                }
+                Key::Char(c) if !c.is_control() => {
+                    query.push(c);
+                    cursor = 0;
+                }
+                Key::Char(_) => {}
+                Key::Enter if found.is_empty() => {}
+                Key::Enter => loop {
+                    draw(
+                        &mut stdout,
+                        items,
+                        &query,
+                        &found,
+                        cursor,
+                        "d done  c cancel  e edit  s show  q back",
+                    );
+                    let action = match read_key(&mut stdin) {
+                        Key::Char('d') => Action::Done,
+                        Key::Char('c') => Action::Cancel,
+                        Key::Char('e') => Action::Edit,
+                        Key::Char('s') => Action::Show,
+                        Key::Char('q') | Key::Quit => continue 'search,
+                        _ => continue,
+                    };
+                    break 'search Some((action, found[cursor]));
+                },
+                Key::Quit => break None,
+            }
+        }
+    };
+    // Clear the picker, leaving the screen as it was
+    let _ = write!(stdout, "\r\x1b[J");
+    let _ = stdout.flush();
+    Ok(picked)
+}
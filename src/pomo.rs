@@ -0,0 +1,41 @@
+use std::io::{IsTerminal, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::dates;
+use crate::task::Task;
+
+/// Count down `minutes` on the terminal, redrawing `mm:ss` in place once a
+/// second. Without a terminal it just waits.
+pub fn countdown(icon: &str, label: &str, minutes: u64) {
+    let total = Duration::from_secs(minutes * 60);
+    let started = Instant::now();
+    let interactive = std::io::stdout().is_terminal();
+    if !interactive {
+        println!("{} {} ({} min)", icon, label, minutes);
+    }
+
+    while let Some(left) = total.checked_sub(started.elapsed()) {
+        if interactive {
+            let secs = left.as_secs();
+            print!("\r{} {:02}:{:02}  {}  ", icon, secs / 60, secs % 60, label);
+            let _ = std::io::stdout().flush();
+        }
+        thread::sleep(left.min(Duration::from_secs(1)));
+        if left <= Duration::from_secs(1) {
+            break;
+        }
+    }
+    if interactive {
+        println!("\r{} 00:00  {}  ", icon, label);
+    }
+}
+
+/// Add one finished pomodoro of `minutes` to the task's `[pomodoros:: n]`
+/// and `[focus:: 1h15m]` annotations.
+pub fn log(task: &mut Task, minutes: u64) {
+    let count = task.pomodoros() + 1;
+    let focus = task.focus() + minutes as i64;
+    task.set_field("pomodoros", &count.to_string());
+    task.set_field("focus", &dates::format_estimate(focus));
+}
@@ -0,0 +1,46 @@
+use serde::Deserialize;
+
+use crate::task::{Status, Task};
+
+/// `[proposals]` config: which automated writers skip the review queue.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProposalConfig {
+    /// Sources whose tasks are approved as soon as they're added
+    pub trusted_sources: Vec<String>,
+    /// Tags that approve a task from any source, e.g. `shopping`
+    pub trusted_tags: Vec<String>,
+}
+
+impl ProposalConfig {
+    fn trusts(&self, source: &str, task: &Task) -> bool {
+        self.trusted_sources.iter().any(|s| s == source)
+            || task.tags().iter().any(|t| {
+                self.trusted_tags
+                    .iter()
+                    .any(|trusted| trusted.trim_start_matches('#') == *t)
+            })
+    }
+}
+
+/// Record where a task added by a bot or integration came from and hold it
+/// for review unless a rule trusts it. Returns whether it was queued.
+pub fn propose(task: &mut Task, source: &str, config: &ProposalConfig) -> bool {
+    task.set_field("source", source);
+    if config.trusts(source, task) {
+        return false;
+    }
+    task.status = Status::Proposed;
+    true
+}
+
+/// Line indices of proposed tasks, in file order. Numbers given to
+/// `approve` and `reject` index into this list.
+pub fn queue(lines: &[String]) -> Vec<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| Task::parse(l).is_some_and(|t| t.status == Status::Proposed))
+        .map(|(i, _)| i)
+        .collect()
+}
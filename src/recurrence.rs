@@ -0,0 +1,86 @@
+use chrono::{Duration, Months, NaiveDate};
+
+use crate::task::{Status, Task};
+
+/// Compute the date after `from` for an Obsidian-style rule such as
+/// `every day`, `every 2 weeks`, `every month` or `every year when done`.
+pub fn next_occurrence(rule: &str, from: NaiveDate) -> Option<NaiveDate> {
+    let rule = rule.trim().to_lowercase();
+    let rule = rule.strip_suffix("when done").unwrap_or(&rule).trim();
+    let mut words = rule.strip_prefix("every")?.split_whitespace();
+
+    let first = words.next()?;
+    let (count, unit) = match first.parse::<u32>() {
+        Ok(n) => (n, words.next()?),
+        Err(_) => (1, first),
+    };
+    if count == 0 || words.next().is_some() {
+        return None;
+    }
+
+    match unit.trim_end_matches('s') {
+        "day" => from.checked_add_signed(Duration::days(count as i64)),
+        "week" => from.checked_add_signed(Duration::weeks(count as i64)),
+        "month" => from.checked_add_months(Months::new(count)),
+        "year" => from.checked_add_months(Months::new(count * 12)),
+        _ => None,
+    }
+}
+
+// FNV-1a: unlike std's hasher it gives the same value on every machine and
+// Rust version, which occurrence IDs need to match up across synced copies.
+pub fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Deterministic ID for the occurrence of `task`'s series that follows the
+/// slot dated `after`. Two machines completing the same occurrence on
+/// different days derive the same ID, so sync can tell the copies apart from
+/// genuinely new tasks.
+fn occurrence_id(task: &Task, rule: &str, after: NaiveDate) -> String {
+    let series = stable_hash(&format!("{}|{}", task.text, rule.trim().to_lowercase()));
+    format!("{:08x}-{}", series as u32, after.format("%Y%m%d"))
+}
+
+/// Build the next pending instance of a recurring task that was completed on
+/// `today`. All dates move by the same offset so their spacing is kept.
+pub fn next_task(task: &Task, today: NaiveDate) -> Option<Task> {
+    let rule = task.recurrence.as_deref()?;
+    let when_done = rule.trim().to_lowercase().ends_with("when done");
+
+    let reference = task.due.or(task.scheduled).or(task.start);
+    let base = match reference {
+        Some(date) if !when_done => date,
+        _ => today,
+    };
+    let next = next_occurrence(rule, base)?;
+    let offset = next - reference.unwrap_or(today);
+    let shift = |d: Option<NaiveDate>| d.map(|d| d + offset);
+
+    let mut next_task = Task {
+        status: Status::Pending,
+        created: Some(today),
+        start: shift(task.start),
+        scheduled: shift(task.scheduled),
+        due: if reference.is_some() {
+            shift(task.due)
+        } else {
+            Some(next)
+        },
+        done: None,
+        cancelled: None,
+        // Dependents wait on the completed occurrence, not the new one
+        id: None,
+        ..task.clone()
+    };
+    // Focus time and a completion note belong to the occurrence they were
+    // spent on or written for
+    next_task
+        .fields
+        .retain(|(k, _)| k != "pomodoros" && k != "focus" && k != "note");
+    let slot = reference.or(task.created).unwrap_or(today);
+    next_task.set_field("occurrence", &occurrence_id(task, rule, slot));
+    Some(next_task)
+}
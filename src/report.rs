@@ -0,0 +1,758 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+
+use crate::dates;
+use crate::task::{Status, Task};
+use crate::timer::{self, Entry, format_duration};
+
+// Snoozed this often, a task is probably never going to be done as it stands
+const CHRONIC_SNOOZES: u32 = 3;
+
+/// Monday of the week containing `date`.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn percent(part: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{}%", part * 100 / total)
+}
+
+#[derive(Default)]
+struct Cohort {
+    done: usize,
+    cancelled: usize,
+    pending: usize,
+}
+
+impl Cohort {
+    fn total(&self) -> usize {
+        self.done + self.cancelled + self.pending
+    }
+}
+
+/// Group tasks by the week they were created in and show how each cohort has
+/// since been resolved.
+pub fn cohorts(tasks: &[Task], weeks: u32, today: NaiveDate) {
+    let oldest = week_start(today) - Duration::weeks(weeks.saturating_sub(1) as i64);
+    let mut by_week: BTreeMap<NaiveDate, Cohort> = BTreeMap::new();
+    let mut undated = 0;
+
+    for task in tasks {
+        let Some(created) = task.created else {
+            undated += 1;
+            continue;
+        };
+        let week = week_start(created);
+        if week < oldest {
+            continue;
+        }
+        let cohort = by_week.entry(week).or_default();
+        match task.status {
+            Status::Done => cohort.done += 1,
+            Status::Cancelled => cohort.cancelled += 1,
+            Status::Pending | Status::Waiting | Status::Proposed => cohort.pending += 1,
+        }
+    }
+
+    println!("Task cohorts by creation week (last {} week(s)):", weeks);
+    if by_week.is_empty() {
+        println!("No tasks created in this period.");
+        return;
+    }
+
+    println!(
+        "{:<12} {:>7} {:>6} {:>10} {:>8}",
+        "Week of", "Created", "Done", "Cancelled", "Pending"
+    );
+    for (week, cohort) in &by_week {
+        let total = cohort.total();
+        println!(
+            "{:<12} {:>7} {:>6} {:>10} {:>8}",
+            week.format("%Y-%m-%d").to_string(),
+            total,
+            percent(cohort.done, total),
+            percent(cohort.cancelled, total),
+            percent(cohort.pending, total)
+        );
+    }
+
+    let pending = by_week.values().map(|c| c.pending).sum::<usize>();
+    let total = by_week.values().map(Cohort::total).sum::<usize>();
+    println!(
+        "\n{} of {} tasks from this period are still pending ({}).",
+        pending,
+        total,
+        percent(pending, total)
+    );
+    if undated > 0 {
+        println!("{} task(s) without a creation date were skipped.", undated);
+    }
+}
+
+// Rows of the burndown chart, and the most columns it gets; longer periods
+// put several days in a column
+const CHART_HEIGHT: usize = 10;
+const CHART_WIDTH: usize = 84;
+
+/// Chart how many tasks were open on each day of the last `weeks` weeks,
+/// worked out from when each was created and resolved, so it shows whether
+/// the backlog is shrinking or growing.
+pub fn burndown(tasks: &[Task], weeks: u32, today: NaiveDate) {
+    let days = weeks.max(1) as i64 * 7;
+    let start = today - Duration::days(days - 1);
+    let mut spans = Vec::new();
+    let mut undated = 0;
+    for task in tasks {
+        let resolved = task.done.or(task.cancelled);
+        match (task.status, task.created) {
+            // Never accepted into the list
+            (Status::Proposed, _) => {}
+            (Status::Done | Status::Cancelled, Some(created)) if resolved.is_some() => {
+                spans.push((created, resolved))
+            }
+            (Status::Pending | Status::Waiting, Some(created)) => spans.push((created, None)),
+            _ => undated += 1,
+        }
+    }
+    let open_on = |day: NaiveDate| {
+        spans
+            .iter()
+            .filter(|(created, resolved)| *created <= day && resolved.is_none_or(|r| r > day))
+            .count()
+    };
+
+    // Each column shows the count on its last day
+    let step = (days as usize).div_ceil(CHART_WIDTH);
+    let columns = (0..(days as usize).div_ceil(step))
+        .rev()
+        .map(|n| open_on(today - Duration::days((n * step) as i64)))
+        .collect::<Vec<_>>();
+    let max = columns.iter().copied().max().unwrap_or(0);
+
+    println!("Open tasks over the last {} week(s):", weeks.max(1));
+    if max == 0 {
+        println!("No open tasks in this period.");
+    } else {
+        let label_width = max.to_string().len();
+        let eighths = columns
+            .iter()
+            .map(|&v| (v * CHART_HEIGHT * 8 + max / 2) / max)
+            .collect::<Vec<_>>();
+        for row in 0..CHART_HEIGHT {
+            let floor = (CHART_HEIGHT - 1 - row) * 8;
+            let label = match row {
+                0 => max.to_string(),
+                _ if row == CHART_HEIGHT - 1 => "0".to_string(),
+                _ => String::new(),
+            };
+            let cells = eighths
+                .iter()
+                .map(|&e| {
+                    [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'][e.saturating_sub(floor).min(8)]
+                })
+                .collect::<String>();
+            println!("  {:>w$} ┤{}", label, cells.trim_end(), w = label_width);
+        }
+        let (first, last) = (dates::show(start), dates::show(today));
+        let gap = columns
+            .len()
+            .saturating_sub(first.len() + last.len())
+            .max(1);
+        println!(
+            "  {:w$}  {}{}{}",
+            "",
+            first,
+            " ".repeat(gap),
+            last,
+            w = label_width
+        );
+    }
+
+    let (before, now) = (open_on(start - Duration::days(1)), open_on(today));
+    let created = spans
+        .iter()
+        .filter(|(c, _)| *c >= start && *c <= today)
+        .count();
+    let resolved = spans
+        .iter()
+        .filter(|(_, r)| r.is_some_and(|r| r >= start && r <= today))
+        .count();
+    let verdict = match now.cmp(&before) {
+        std::cmp::Ordering::Less => "shrinking",
+        std::cmp::Ordering::Equal => "holding steady",
+        std::cmp::Ordering::Greater => "growing",
+    };
+    println!(
+        "\n{} open now, {} at the start: {} created and {} resolved. The backlog is {}.",
+        now, before, created, resolved, verdict
+    );
+    if undated > 0 {
+        println!(
+            "{} task(s) without a creation or completion date were skipped.",
+            undated
+        );
+    }
+}
+
+fn bar(value: usize, max: usize, width: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    "█".repeat((value * width).div_ceil(max))
+}
+
+/// Overall counts, completion rate and lead time, a per-week completion
+/// chart, the most used tags, chronically snoozed tasks, how estimates compared with the time logged
+/// in `entries`, and when each tag's open tasks should be done.
+pub fn stats(tasks: &[Task], entries: &[Entry], weeks: u32, today: NaiveDate, now: NaiveDateTime) {
+    let count = |status| tasks.iter().filter(|t| t.status == status).count();
+    let (pending, done, cancelled) = (
+        count(Status::Pending) + count(Status::Waiting),
+        count(Status::Done),
+        count(Status::Cancelled),
+    );
+
+    println!("Task statistics:");
+    println!("  Pending:   {}", pending);
+    println!("  Done:      {}", done);
+    println!("  Cancelled: {}", cancelled);
+    println!("  Completion rate: {}", percent(done, tasks.len()));
+
+    let lead_times = tasks
+        .iter()
+        .filter_map(|t| Some((t.done? - t.created?).num_days()))
+        .filter(|d| *d >= 0)
+        .collect::<Vec<_>>();
+    if !lead_times.is_empty() {
+        let average = lead_times.iter().sum::<i64>() as f64 / lead_times.len() as f64;
+        println!("  Average days from creation to completion: {:.1}", average);
+    }
+
+    let pomodoros = tasks.iter().map(Task::pomodoros).sum::<u32>();
+    if pomodoros > 0 {
+        let focus = tasks.iter().map(Task::focus).sum::<i64>();
+        println!(
+            "  Focus time: {} over {} pomodoro(s)",
+            format_duration(Duration::minutes(focus)),
+            pomodoros
+        );
+    }
+
+    println!("\nCompleted per week (last {} week(s)):", weeks);
+    let this_week = week_start(today);
+    let per_week = (0..weeks)
+        .rev()
+        .map(|n| {
+            let start = this_week - Duration::weeks(n as i64);
+            let end = start + Duration::days(6);
+            let completed = tasks
+                .iter()
+                .filter(|t| t.done.is_some_and(|d| d >= start && d <= end))
+                .count();
+            (start, completed)
+        })
+        .collect::<Vec<_>>();
+    let max = per_week.iter().map(|(_, c)| *c).max().unwrap_or(0);
+    for (start, completed) in &per_week {
+        let row = format!(
+            "  {} {:>3} {}",
+            start.format("%Y-%m-%d"),
+            completed,
+            bar(*completed, max, 40)
+        );
+        println!("{}", row.trim_end());
+    }
+
+    let mut tags: HashMap<&str, usize> = HashMap::new();
+    for task in tasks {
+        for tag in task.tags() {
+            *tags.entry(tag).or_default() += 1;
+        }
+    }
+    if !tags.is_empty() {
+        let mut tags = tags.into_iter().collect::<Vec<_>>();
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        println!("\nBusiest tags:");
+        for (tag, n) in tags.iter().take(5) {
+            println!("  #{:<20} {}", tag, n);
+        }
+    }
+
+    let mut snoozed = tasks
+        .iter()
+        .filter(|t| t.status == Status::Pending && t.snoozes() >= CHRONIC_SNOOZES)
+        .collect::<Vec<_>>();
+    if !snoozed.is_empty() {
+        snoozed.sort_by_key(|t| std::cmp::Reverse(t.snoozes()));
+        println!(
+            "\nSnoozed {} or more times (do, delegate or drop?):",
+            CHRONIC_SNOOZES
+        );
+        for task in snoozed.iter().take(5) {
+            println!("  {:>3}×  {}", task.snoozes(), task.text);
+        }
+    }
+
+    estimate_accuracy(tasks, entries, now);
+    forecast(tasks, weeks, today);
+}
+
+/// Estimated against actual time for completed tasks that have both, from
+/// `task start`/`stop` and `task pomo`, with the worst overruns.
+fn estimate_accuracy(tasks: &[Task], entries: &[Entry], now: NaiveDateTime) {
+    let mut compared = tasks
+        .iter()
+        .filter(|t| t.status == Status::Done)
+        .filter_map(|t| {
+            let estimate = Duration::minutes(t.estimate()?);
+            let actual = timer::tracked(entries, t, now) + Duration::minutes(t.focus());
+            (actual > Duration::zero()).then_some((t, estimate, actual))
+        })
+        .collect::<Vec<_>>();
+    if compared.is_empty() {
+        return;
+    }
+    let estimated = compared.iter().fold(Duration::zero(), |sum, c| sum + c.1);
+    let actual = compared.iter().fold(Duration::zero(), |sum, c| sum + c.2);
+
+    println!(
+        "\nEstimates vs. tracked time ({} completed task(s)):",
+        compared.len()
+    );
+    println!(
+        "  Estimated {}, took {} ({} of the estimates)",
+        format_duration(estimated),
+        format_duration(actual),
+        percent(
+            actual.num_minutes() as usize,
+            estimated.num_minutes() as usize
+        )
+    );
+    compared.sort_by_key(|(_, estimate, actual)| std::cmp::Reverse(*actual - *estimate));
+    let over = compared
+        .iter()
+        .filter(|(_, estimate, actual)| actual > estimate)
+        .take(3)
+        .collect::<Vec<_>>();
+    if !over.is_empty() {
+        println!("  Furthest over:");
+        for (task, estimate, actual) in over {
+            println!(
+                "    +{:<8} {} (estimated {})",
+                format_duration(*actual - *estimate),
+                task.text,
+                format_duration(*estimate)
+            );
+        }
+    }
+}
+
+/// Estimated work per day from `from` to `to` for `tasks`, each with the
+/// date it counts on, and a bar per day. Days over `capacity` minutes are
+/// flagged; tasks due before `from` get a row of their own.
+pub fn workload(tasks: &[(NaiveDate, Task)], from: NaiveDate, to: NaiveDate, capacity: i64) {
+    struct Row {
+        label: String,
+        minutes: i64,
+        tasks: usize,
+        unestimated: usize,
+    }
+    let row = |label: String, on: &dyn Fn(NaiveDate) -> bool| {
+        let day = tasks.iter().filter(|(d, _)| on(*d)).collect::<Vec<_>>();
+        Row {
+            label,
+            minutes: day.iter().filter_map(|(_, t)| t.estimate()).sum(),
+            tasks: day.len(),
+            unestimated: day.iter().filter(|(_, t)| t.estimate().is_none()).count(),
+        }
+    };
+    let mut rows = Vec::new();
+    if tasks.iter().any(|(d, _)| *d < from) {
+        rows.push(row("Overdue".to_string(), &|d| d < from));
+    }
+    let mut day = from;
+    while day <= to {
+        let label = format!("{} {}", day.format("%a"), dates::show(day));
+        rows.push(row(label, &|d| d == day));
+        day += Duration::days(1);
+    }
+
+    println!(
+        "Estimated work per day ({} a day):",
+        format_duration(Duration::minutes(capacity))
+    );
+    let width = rows
+        .iter()
+        .map(|r| r.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max = rows
+        .iter()
+        .map(|r| r.minutes)
+        .max()
+        .unwrap_or(0)
+        .max(capacity);
+    for r in &rows {
+        let mut line = format!(
+            "  {:<width$}  {:>8}  {:<20}",
+            r.label,
+            format_duration(Duration::minutes(r.minutes)),
+            bar(r.minutes as usize, max as usize, 20),
+        );
+        if r.tasks > 0 {
+            line.push_str(&format!("  {} task(s)", r.tasks));
+        }
+        if r.unestimated > 0 {
+            line.push_str(&format!(", {} unestimated", r.unestimated));
+        }
+        if r.minutes > capacity {
+            line.push_str(&format!(
+                "  ⚠ over by {}",
+                format_duration(Duration::minutes(r.minutes - capacity))
+            ));
+        }
+        println!("{}", line.trim_end());
+    }
+
+    let unestimated = rows.iter().map(|r| r.unestimated).sum::<usize>();
+    if unestimated > 0 {
+        println!(
+            "{} task(s) have no estimate; add one with 'task estimate <num> 1h'.",
+            unestimated
+        );
+    }
+}
+
+/// When a tagged project should run out of open tasks at the pace its tasks
+/// were completed over recent weeks.
+struct Eta {
+    remaining: usize,
+    /// Tasks completed per week, on average
+    pace: f64,
+    expected: NaiveDate,
+    /// At one standard deviation above and below the average pace; a slow
+    /// pace of zero or less has no end date
+    early: NaiveDate,
+    late: Option<NaiveDate>,
+}
+
+fn eta(tasks: &[Task], tag: &str, weeks: u32, today: NaiveDate) -> Option<Eta> {
+    let tagged = tasks
+        .iter()
+        .filter(|t| t.tags().contains(&tag))
+        .collect::<Vec<_>>();
+    let remaining = tagged
+        .iter()
+        .filter(|t| matches!(t.status, Status::Pending | Status::Waiting))
+        .count();
+    if remaining == 0 || weeks == 0 {
+        return None;
+    }
+
+    // Rolling seven-day windows ending today, so a fresh week doesn't drag
+    // the pace down
+    let per_week = (0..weeks as i64)
+        .map(|n| {
+            let end = today - Duration::weeks(n);
+            let start = end - Duration::days(6);
+            tagged
+                .iter()
+                .filter(|t| t.done.is_some_and(|d| d >= start && d <= end))
+                .count() as f64
+        })
+        .collect::<Vec<_>>();
+    let pace = per_week.iter().sum::<f64>() / weeks as f64;
+    if pace <= 0.0 {
+        return None;
+    }
+    let variance = per_week.iter().map(|n| (n - pace).powi(2)).sum::<f64>() / weeks as f64;
+    let spread = variance.sqrt();
+
+    let finish = |pace: f64| today + Duration::days((remaining as f64 / pace * 7.0).ceil() as i64);
+    Some(Eta {
+        remaining,
+        pace,
+        expected: finish(pace),
+        early: finish(pace + spread),
+        late: (pace - spread > 0.0).then(|| finish(pace - spread)),
+    })
+}
+
+/// Projected finish dates for every tag with open tasks and recent progress.
+fn forecast(tasks: &[Task], weeks: u32, today: NaiveDate) {
+    let mut tags = tasks.iter().flat_map(|t| t.tags()).collect::<Vec<_>>();
+    tags.sort();
+    tags.dedup();
+    let mut etas = tags
+        .into_iter()
+        .filter_map(|tag| Some((tag, eta(tasks, tag, weeks, today)?)))
+        .collect::<Vec<_>>();
+    if etas.is_empty() {
+        return;
+    }
+    etas.sort_by_key(|(_, eta)| eta.expected);
+
+    println!("\nForecast (at the pace of the last {} week(s)):", weeks);
+    for (tag, eta) in etas {
+        let late = eta.late.map_or("?".to_string(), dates::show);
+        println!(
+            "  #{:<20} {} left at {:.1}/week, finishes ~{} ({} to {})",
+            tag,
+            eta.remaining,
+            eta.pace,
+            dates::show(eta.expected),
+            dates::show(eta.early),
+            late
+        );
+    }
+}
+
+/// Headings and bullets for either markdown or plain text output.
+struct Doc {
+    markdown: bool,
+}
+
+impl Doc {
+    fn title(&self, text: &str) {
+        if self.markdown {
+            println!("## {}\n", text);
+        } else {
+            println!("{}\n{}\n", text, "=".repeat(text.chars().count()));
+        }
+    }
+
+    fn section(&self, text: &str, count: usize) {
+        if self.markdown {
+            println!("### {} ({})\n", text, count);
+        } else {
+            println!("{} ({}):", text.to_uppercase(), count);
+        }
+    }
+
+    fn subsection(&self, text: &str) {
+        if self.markdown {
+            println!("**{}**\n", text);
+        } else {
+            println!("  {}", text);
+        }
+    }
+
+    fn item(&self, text: &str, nested: bool) {
+        match (self.markdown, nested) {
+            (true, _) => println!("- {}", text),
+            (false, false) => println!("  - {}", text),
+            (false, true) => println!("    - {}", text),
+        }
+    }
+
+    fn none(&self) {
+        if self.markdown {
+            println!("_None_");
+        } else {
+            println!("  None");
+        }
+    }
+
+    fn end_section(&self) {
+        println!();
+    }
+}
+
+fn summary(task: &Task) -> String {
+    match task.due {
+        Some(due) => format!("{} (📅 {})", task.text, due.format("%Y-%m-%d")),
+        None => task.text.clone(),
+    }
+}
+
+/// A review of the past seven days for pasting into a standup or weekly
+/// review: what got done, what was dropped, what was added and what's next.
+pub fn weekly(tasks: &[Task], today: NaiveDate, markdown: bool) {
+    let doc = Doc { markdown };
+    let since = today - Duration::days(6);
+    let horizon = today + Duration::days(7);
+    let in_window = |d: Option<NaiveDate>| d.is_some_and(|d| d >= since && d <= today);
+
+    doc.title(&format!(
+        "Weekly review: {} to {}",
+        since.format("%Y-%m-%d"),
+        today.format("%Y-%m-%d")
+    ));
+
+    let mut completed = tasks
+        .iter()
+        .filter(|t| t.status == Status::Done && in_window(t.done))
+        .collect::<Vec<_>>();
+    completed.sort_by_key(|t| t.done);
+    doc.section("Completed", completed.len());
+    let mut by_day: BTreeMap<NaiveDate, Vec<&Task>> = BTreeMap::new();
+    for task in completed {
+        by_day
+            .entry(task.done.unwrap_or(today))
+            .or_default()
+            .push(task);
+    }
+    for (day, day_tasks) in &by_day {
+        doc.subsection(&day.format("%A %d %B").to_string());
+        for task in day_tasks {
+            match task.field("note") {
+                Some(note) => doc.item(&format!("{} - {}", task.text, note), true),
+                None => doc.item(&task.text, true),
+            }
+        }
+        if markdown {
+            println!();
+        }
+    }
+    if by_day.is_empty() {
+        doc.none();
+        doc.end_section();
+    } else if !markdown {
+        doc.end_section();
+    }
+
+    let dropped = tasks
+        .iter()
+        .filter(|t| t.status == Status::Cancelled && in_window(t.cancelled))
+        .collect::<Vec<_>>();
+    if !dropped.is_empty() {
+        doc.section("Dropped", dropped.len());
+        for task in &dropped {
+            let why = [task.field("reason"), task.field("note")]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            if why.is_empty() {
+                doc.item(&task.text, false);
+            } else {
+                doc.item(&format!("{} - {}", task.text, why.join("; ")), false);
+            }
+        }
+        doc.end_section();
+    }
+
+    let carried = tasks
+        .iter()
+        .filter(|t| t.status == Status::Pending && t.created.is_some_and(|c| c < since))
+        .collect::<Vec<_>>();
+    doc.section("Carried over", carried.len());
+    if carried.is_empty() {
+        doc.none();
+    }
+    for task in &carried {
+        doc.item(&summary(task), false);
+    }
+    doc.end_section();
+
+    let added = tasks
+        .iter()
+        .filter(|t| in_window(t.created))
+        .collect::<Vec<_>>();
+    doc.section("Newly added", added.len());
+    if added.is_empty() {
+        doc.none();
+    }
+    for task in &added {
+        let text = match task.status {
+            Status::Pending => summary(task),
+            Status::Done => format!("{} (done)", task.text),
+            Status::Cancelled => format!("{} (cancelled)", task.text),
+            Status::Waiting => format!("{} (waiting)", task.text),
+            Status::Proposed => format!("{} (proposed)", task.text),
+        };
+        doc.item(&text, false);
+    }
+    doc.end_section();
+
+    let mut upcoming = tasks
+        .iter()
+        .filter(|t| t.status == Status::Pending && t.due.is_some_and(|d| d <= horizon))
+        .collect::<Vec<_>>();
+    upcoming.sort_by_key(|t| t.due);
+    doc.section("Upcoming deadlines", upcoming.len());
+    if upcoming.is_empty() {
+        doc.none();
+    }
+    for task in &upcoming {
+        let text = match task.due {
+            Some(due) if due < today => format!("{} (overdue)", summary(task)),
+            _ => summary(task),
+        };
+        doc.item(&text, false);
+    }
+}
+
+/// Whether a task was open and past its due date at the end of `day`.
+fn overdue_on(task: &Task, day: NaiveDate) -> bool {
+    let open = task.created.is_none_or(|c| c <= day)
+        && task.done.is_none_or(|d| d > day)
+        && task.cancelled.is_none_or(|d| d > day);
+    open && task.due.is_some_and(|due| due < day)
+}
+
+fn trend(current: usize, previous: usize) -> String {
+    let delta = current as i64 - previous as i64;
+    match delta {
+        0 => "±0 →".to_string(),
+        d if d > 0 => format!("+{} ↑", d),
+        d => format!("{} ↓", d),
+    }
+}
+
+/// Compare the last seven days against the seven days before them.
+pub fn week_over_week(tasks: &[Task], today: NaiveDate) {
+    let this_start = today - Duration::days(6);
+    let last_end = this_start - Duration::days(1);
+    let last_start = last_end - Duration::days(6);
+
+    let between = |d: Option<NaiveDate>, from: NaiveDate, to: NaiveDate| {
+        d.is_some_and(|d| d >= from && d <= to)
+    };
+    let count = |f: &dyn Fn(&Task) -> bool| tasks.iter().filter(|t| f(t)).count();
+
+    let rows = [
+        (
+            "Completed",
+            count(&|t| between(t.done, this_start, today)),
+            count(&|t| between(t.done, last_start, last_end)),
+        ),
+        (
+            "Added",
+            count(&|t| between(t.created, this_start, today)),
+            count(&|t| between(t.created, last_start, last_end)),
+        ),
+        (
+            "Cancelled",
+            count(&|t| between(t.cancelled, this_start, today)),
+            count(&|t| between(t.cancelled, last_start, last_end)),
+        ),
+        (
+            "Overdue",
+            count(&|t| overdue_on(t, today)),
+            count(&|t| overdue_on(t, last_end)),
+        ),
+    ];
+
+    println!(
+        "This week ({} to {}) vs previous week:",
+        this_start.format("%Y-%m-%d"),
+        today.format("%Y-%m-%d")
+    );
+    println!(
+        "  {:<10} {:>9} {:>9} {:>8}",
+        "", "This week", "Previous", "Change"
+    );
+    for (label, current, previous) in rows {
+        println!(
+            "  {:<10} {:>9} {:>9} {:>8}",
+            label,
+            current,
+            previous,
+            trend(current, previous)
+        );
+    }
+}
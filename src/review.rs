@@ -0,0 +1,50 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+use crate::storage;
+use crate::task::Task;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// The tasks that existed at the last review (`task report weekly`), so
+/// later listings can point out what's new since.
+pub struct Review {
+    pub at: NaiveDateTime,
+    seen: HashSet<String>,
+}
+
+fn key(task: &Task) -> String {
+    let created = task.created.map(|d| d.to_string()).unwrap_or_default();
+    format!("{}|{}", created, task.text)
+}
+
+impl Review {
+    /// Whether `task` was added after this review.
+    pub fn is_new(&self, task: &Task) -> bool {
+        !self.seen.contains(&key(task))
+    }
+}
+
+/// The last review recorded in `review_state`, if any.
+pub fn last(task_dir: &Path) -> Option<Review> {
+    let state = storage::read_text(&task_dir.join("review_state")).ok()?;
+    let mut lines = state.lines();
+    let at = NaiveDateTime::parse_from_str(lines.next()?, TIME_FORMAT).ok()?;
+    Some(Review {
+        at,
+        seen: lines.map(String::from).collect(),
+    })
+}
+
+/// Remember every task in `tasks` as reviewed at `now`.
+pub fn record(tasks: &[Task], task_dir: &Path, now: NaiveDateTime) -> Result<(), String> {
+    let mut contents = format!("{}\n", now.format(TIME_FORMAT));
+    for task in tasks {
+        contents.push_str(&key(task));
+        contents.push('\n');
+    }
+    storage::write_text(&task_dir.join("review_state"), &contents)
+        .map_err(|e| format!("error saving review state: {}", e))
+}
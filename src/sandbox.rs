@@ -0,0 +1,164 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate};
+
+use crate::json;
+use crate::task::{Status, Task};
+
+/// Variables passed through from the caller's environment; everything else
+/// is dropped so a command can't pick up credentials or tokens by accident.
+const INHERITED: &[&str] = &["PATH", "HOME", "LANG", "TZ"];
+
+/// How a sandboxed command ended.
+#[derive(Debug)]
+pub struct Outcome {
+    /// Exit code, or `None` if it was killed at the timeout or by a signal
+    pub code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// `task` as a JSON object, the form commands read on stdin.
+pub fn task_json(task: &Task) -> String {
+    let date =
+        |d: Option<NaiveDate>| d.map_or("null".to_string(), |d| json::string(&d.to_string()));
+    let text = |s: Option<&String>| s.map_or("null".to_string(), |s| json::string(s));
+    let list = |items: Vec<String>| format!("[{}]", items.join(","));
+    let status = match task.status {
+        Status::Pending => "pending",
+        Status::Done => "done",
+        Status::Cancelled => "cancelled",
+        Status::Waiting => "waiting",
+        Status::Proposed => "proposed",
+    };
+    let fields = task
+        .fields
+        .iter()
+        .map(|(k, v)| format!("{}:{}", json::string(k), json::string(v)))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"text\":{},\"status\":\"{}\",\"priority\":\"{}\",\"created\":{},\"start\":{},\"scheduled\":{},\"due\":{},\"done\":{},\"cancelled\":{},\"recurrence\":{},\"id\":{},\"depends_on\":{},\"tags\":{},\"fields\":{{{}}}}}",
+        json::string(&task.text),
+        status,
+        task.priority.name(),
+        date(task.created),
+        date(task.start),
+        date(task.scheduled),
+        date(task.due),
+        date(task.done),
+        date(task.cancelled),
+        text(task.recurrence.as_ref()),
+        text(task.id.as_ref()),
+        list(task.depends_on.iter().map(|id| json::string(id)).collect()),
+        list(task.tags().into_iter().map(json::string).collect()),
+        fields.join(",")
+    )
+}
+
+/// Run `command` (program and arguments, no shell) with `input` on stdin, a
+/// minimal environment plus `env`, and a time limit. Both output streams are
+/// captured and appended to `log` along with how the command ended.
+pub fn run(
+    command: &[String],
+    input: &str,
+    env: &[(&str, String)],
+    timeout: Duration,
+    log: &Path,
+) -> Result<Outcome, String> {
+    let (program, args) = command.split_first().ok_or("no command given")?;
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .env_clear()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for name in INHERITED {
+        if let Ok(value) = std::env::var(name) {
+            cmd.env(name, value);
+        }
+    }
+    cmd.envs(env.iter().map(|(k, v)| (k, v)));
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("could not run '{}': {}", program, e))?;
+
+    // Feed and drain the pipes on their own threads so a command that
+    // ignores stdin or writes a lot can't block us
+    let mut stdin = child.stdin.take().ok_or("could not open stdin")?;
+    let input = input.to_string();
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let drain = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut out = Vec::new();
+            let _ = pipe.read_to_end(&mut out);
+            String::from_utf8_lossy(&out).into_owned()
+        })
+    };
+    let stdout = drain(Box::new(
+        child.stdout.take().ok_or("could not open stdout")?,
+    ));
+    let stderr = drain(Box::new(
+        child.stderr.take().ok_or("could not open stderr")?,
+    ));
+
+    let started = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait().map_err(|e| e.to_string())?;
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let _ = writer.join();
+    let outcome = Outcome {
+        code: if timed_out { None } else { status.code() },
+        timed_out,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+    record(log, command, &outcome, started.elapsed())?;
+    Ok(outcome)
+}
+
+fn record(log: &Path, command: &[String], outcome: &Outcome, took: Duration) -> Result<(), String> {
+    let ended = match (outcome.timed_out, outcome.code) {
+        (true, _) => "timed out".to_string(),
+        (false, Some(code)) => format!("exit {}", code),
+        (false, None) => "killed by signal".to_string(),
+    };
+    let mut entry = format!(
+        "[{}] {} ({}, {:.1}s)\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        command.join(" "),
+        ended,
+        took.as_secs_f64()
+    );
+    for (name, output) in [("stdout", &outcome.stdout), ("stderr", &outcome.stderr)] {
+        for line in output.lines() {
+            entry.push_str(&format!("  {}: {}\n", name, line));
+        }
+    }
+    if let Some(dir) = log.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("error creating task directory: {}", e))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)
+        .map_err(|e| format!("error opening {}: {}", log.display(), e))?;
+    file.write_all(entry.as_bytes())
+        .map_err(|e| format!("error writing {}: {}", log.display(), e))
+}
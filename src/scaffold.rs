@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, NaiveDate};
+use clap::ValueEnum;
+
+use crate::config::TaskFormat;
+use crate::task::{Priority, Task};
+
+/// Starter task sets for `task init --template`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Template {
+    /// Two-week sprint: planning, check-ins, review and retro
+    Sprint,
+    /// Release checklist from feature freeze to announcement
+    Release,
+}
+
+impl Template {
+    /// Task text, days after today it's due, and priority.
+    fn tasks(self) -> &'static [(&'static str, i64, Priority)] {
+        match self {
+            Template::Sprint => &[
+                ("Sprint planning #sprint", 0, Priority::High),
+                ("Mid-sprint check-in #sprint", 6, Priority::Normal),
+                (
+                    "Groom backlog for next sprint #sprint",
+                    10,
+                    Priority::Normal,
+                ),
+                ("Sprint review and demo #sprint", 13, Priority::High),
+                ("Sprint retrospective #sprint", 13, Priority::Normal),
+            ],
+            Template::Release => &[
+                ("Freeze features #release", 0, Priority::High),
+                ("Update changelog #release", 1, Priority::Normal),
+                ("Bump version number #release", 1, Priority::Normal),
+                ("Run full test suite #release", 2, Priority::High),
+                ("Tag the release #release", 3, Priority::Normal),
+                ("Publish release notes #release", 3, Priority::Normal),
+                ("Announce the release #release", 4, Priority::Low),
+            ],
+        }
+    }
+}
+
+const CONFIG_STUB: &str = "\
+# Settings for this project's task list. Uncomment to override the defaults.
+# format = \"legacy\"  # or \"dataview\"
+# lock_timeout = 10
+# partial_writes_kb = 256
+# compact_every = 100
+# max_removed_percent = 50
+# undated = \"exclude\"
+# week_overdue = true
+# journal = false
+# workday_hours = 8
+# snooze = \"monday\"
+# banner = false
+# id_strategy = \"random\"
+# date_format = \"%d/%m/%Y\"
+# voice_command = \"~/bin/dictate\"
+# pager = \"less -FRX\"
+# alias.standup = \"report weekly --markdown\"
+# alias.in = \"add --no-due\"
+#
+# [nag]
+# threshold = 3
+# highlight = false
+#
+# [urgency]
+# due = 12.0
+# priority = 6.0
+# age = 2.0
+# tagged = 1.0
+#
+# [urgency.tags]
+# next = 15.0
+#
+# [notify]
+# lead_days = 2
+# lead_minutes = 15
+#
+# [proposals]
+# trusted_sources = [\"calendar-sync\"]
+#
+# [serve]
+# bind = \"0.0.0.0:7878\"
+# token = \"change-me\"
+# origins = [\"https://dash.example.com\"]
+#
+# [backup]
+# keep = 50
+# keep_days = 30
+#
+# [encryption]
+# tool = \"age\"
+# recipients = [\"age1...\"]
+# identity = \"~/.config/age/keys.txt\"
+#
+# [sync]
+# git_remote = \"git@example.com:team/tasks.git\"
+# caldav_url = \"https://cloud.example.com/remote.php/dav/calendars/me/tasks/\"
+# caldav_user = \"me\"
+# caldav_password_command = \"secret-tool lookup service task-caldav\"
+#
+# [theme]
+# overdue = \"red\"
+";
+
+// Per-machine state that shouldn't be committed with the shared tasks
+const GITIGNORE: &str = "\
+*.tmp
+contexts
+hooks.log
+nag_state
+notify_state
+review_state
+time_log
+sync/
+";
+
+/// Create a project-local task directory at `dir` with an empty or
+/// template-derived task file, a config stub and a `.gitignore`. Returns the
+/// path of the new task file.
+pub fn init(
+    dir: &Path,
+    template: Option<Template>,
+    today: NaiveDate,
+    format: TaskFormat,
+) -> Result<PathBuf, String> {
+    let task_file = dir.join("work_log.md");
+    if task_file.exists() {
+        return Err(format!("{} already exists", task_file.display()));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("error creating {}: {}", dir.display(), e))?;
+
+    let mut contents = String::new();
+    for &(text, days, priority) in template.map_or(&[][..], Template::tasks) {
+        let mut task = Task::new(text.to_string(), Some(today + Duration::days(days)), today);
+        task.priority = priority;
+        contents.push_str(&task.to_line(format));
+        contents.push('\n');
+    }
+
+    let write = |path: PathBuf, contents: &str| {
+        fs::write(&path, contents).map_err(|e| format!("error writing {}: {}", path.display(), e))
+    };
+    write(task_file.clone(), &contents)?;
+    let config = dir.join("config.toml");
+    if !config.exists() {
+        write(config, CONFIG_STUB)?;
+    }
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        write(gitignore, GITIGNORE)?;
+    }
+    Ok(task_file)
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::config::TaskFormat;
+use crate::recurrence::stable_hash;
+use crate::task::Task;
+
+/// Directories never worth scanning: build output and vendored code.
+const SKIP_DIRS: &[&str] = &["target", "node_modules", "vendor"];
+
+/// The result of scanning a directory for marker comments.
+pub struct Scanned {
+    /// New task lines, in file order
+    pub lines: Vec<String>,
+    pub files: usize,
+    /// Comments skipped because their task was imported by an earlier scan
+    pub already: usize,
+}
+
+/// Files under `dir`, sorted so a scan always adds tasks in the same order.
+/// Hidden entries and [`SKIP_DIRS`] are left out.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("error reading {}: {}", dir.display(), e))?;
+    let mut paths = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if !SKIP_DIRS.contains(&name.as_ref()) {
+                walk(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The first marker from `markers` in `line` as a whole word, with the text
+/// after it.
+fn find_marker<'a>(line: &'a str, markers: &[&'a str]) -> Option<(&'a str, &'a str)> {
+    let word_char = |c: char| c.is_alphanumeric() || c == '_';
+    markers
+        .iter()
+        .filter_map(|&marker| {
+            line.match_indices(marker).find_map(|(at, _)| {
+                let rest = &line[at + marker.len()..];
+                let alone = !line[..at].ends_with(word_char) && !rest.starts_with(word_char);
+                alone.then_some((at, marker, rest))
+            })
+        })
+        .min_by_key(|(at, _, _)| *at)
+        .map(|(_, marker, rest)| (marker, rest))
+}
+
+/// Comment text after a marker: without an `(owner)`, the `:` and the
+/// comment's closing `*/` or `-->`.
+fn comment_text(rest: &str) -> String {
+    let rest = rest.trim_start();
+    let rest = match rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+        Some((_, after)) => after,
+        None => rest,
+    };
+    rest.trim_start_matches([':', '-', ' '])
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim()
+        .to_string()
+}
+
+/// Tasks for the `pattern` comments (alternatives separated by `|`, such
+/// as `TODO|FIXME`) in the files under `dir`. Each task is tagged with its
+/// marker and keeps its place in a `[code:: file:line]` field. A hash of
+/// the file and the comment goes in a `[scan:: …]` field, so comments
+/// already in `existing` are skipped, even once their task is done.
+pub fn scan(
+    dir: &Path,
+    pattern: &str,
+    existing: &[String],
+    today: NaiveDate,
+    format: TaskFormat,
+) -> Result<Scanned, String> {
+    let markers = pattern
+        .split('|')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .collect::<Vec<_>>();
+    if markers.is_empty() {
+        return Err("the pattern has no markers to look for".to_string());
+    }
+    let mut files = Vec::new();
+    if dir.is_file() {
+        files.push(dir.to_path_buf());
+    } else {
+        walk(dir, &mut files)?;
+    }
+
+    let mut known = existing
+        .iter()
+        .filter_map(|l| Task::parse(l)?.field("scan").map(String::from))
+        .collect::<HashSet<_>>();
+    let mut scanned = Scanned {
+        lines: Vec::new(),
+        files: 0,
+        already: 0,
+    };
+    for path in files {
+        // Binary and non-UTF-8 files have no comments to find
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let shown = path.display().to_string();
+        let shown = shown.strip_prefix("./").unwrap_or(&shown);
+        let mut found = false;
+        for (i, line) in contents.lines().enumerate() {
+            let Some((marker, rest)) = find_marker(line, &markers) else {
+                continue;
+            };
+            found = true;
+            let hash = format!(
+                "{:08x}",
+                stable_hash(&format!("{}|{}", shown, line.trim())) as u32
+            );
+            if !known.insert(hash.clone()) {
+                scanned.already += 1;
+                continue;
+            }
+            let text = match comment_text(rest) {
+                text if text.is_empty() => format!("{} in {}", marker, shown),
+                text => text,
+            };
+            let mut task = Task::new(format!("{} #{}", text, marker.to_lowercase()), None, today);
+            task.set_field("code", &format!("{}:{}", shown, i + 1));
+            task.set_field("scan", &hash);
+            scanned.lines.push(task.to_line(format));
+        }
+        if found {
+            scanned.files += 1;
+        }
+    }
+    Ok(scanned)
+}
@@ -0,0 +1,119 @@
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+
+/// The `--section` given: listings show only its tasks and new tasks go
+/// into it.
+static SELECTED: OnceLock<String> = OnceLock::new();
+
+/// With `journal = true`, the day's heading, which new tasks go under
+/// when no `--section` is given.
+static JOURNAL: OnceLock<String> = OnceLock::new();
+
+pub fn init(section: Option<&str>, journal_day: Option<NaiveDate>) {
+    if let Some(name) = section {
+        let _ = SELECTED.set(name.trim().to_string());
+    }
+    if let Some(day) = journal_day {
+        let _ = JOURNAL.set(day.format("%Y-%m-%d").to_string());
+    }
+}
+
+/// The section new tasks go into, if not at the end of the file: the
+/// `--section`, else the journal's day.
+pub fn target() -> Option<&'static str> {
+    SELECTED.get().or(JOURNAL.get()).map(String::as_str)
+}
+
+/// `line`'s level and text if it's a markdown heading, e.g. `(2, "Q4
+/// planning")` for `## Q4 planning`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?.trim();
+    (level > 0 && !text.is_empty()).then_some((level, text))
+}
+
+/// Whether `line` starts or ends a section: a `#` or `##` heading. Deeper
+/// headings are part of the section they're in.
+pub fn is_boundary(line: &str) -> bool {
+    heading(line).is_some_and(|(level, _)| level <= 2)
+}
+
+/// The section each of `lines` is in: the `##` heading above it, or none
+/// above the first one and under a `#` heading.
+pub fn of_lines(lines: &[String]) -> Vec<Option<&str>> {
+    let mut current = None;
+    lines
+        .iter()
+        .map(|line| {
+            if let Some((level, text)) = heading(line).filter(|(level, _)| *level <= 2) {
+                current = (level == 2).then_some(text);
+            }
+            current
+        })
+        .collect()
+}
+
+/// The section line `idx` of `lines` is in.
+pub fn of(lines: &[String], idx: usize) -> Option<&str> {
+    of_lines(&lines[..=idx]).pop().flatten()
+}
+
+/// Whether `section` is the one named `selected`, ignoring case.
+fn is_selected(section: Option<&str>, selected: &str) -> bool {
+    section.is_some_and(|s| s.to_lowercase() == selected.to_lowercase())
+}
+
+/// Which of `lines` are in the `--section`, or `None` without one.
+pub fn shown(lines: &[String]) -> Option<Vec<bool>> {
+    SELECTED.get().map(|selected| within(lines, selected))
+}
+
+/// Which of `lines` are in the section named `name`.
+pub fn within(lines: &[String], name: &str) -> Vec<bool> {
+    of_lines(lines)
+        .into_iter()
+        .map(|s| is_selected(s, name))
+        .collect()
+}
+
+/// `lines` with everything outside the `--section` blanked out, so line
+/// numbers stay as they are in the file.
+pub fn view(mut lines: Vec<String>) -> Vec<String> {
+    let Some(shown) = shown(&lines) else {
+        return lines;
+    };
+    for (line, shown) in lines.iter_mut().zip(shown) {
+        if !shown {
+            line.clear();
+        }
+    }
+    lines
+}
+
+/// Put `new` at the end of the [`target`] section, after its last task or
+/// note, starting the section at the end of the file if there's no such
+/// heading. Without one they go at the end of the file.
+pub fn place(lines: &mut Vec<String>, new: Vec<String>) {
+    let Some(selected) = target() else {
+        lines.extend(new);
+        return;
+    };
+    let sections = of_lines(lines);
+    let Some(start) = sections.iter().position(|s| is_selected(*s, selected)) else {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(format!("## {}", selected));
+        lines.extend(new);
+        return;
+    };
+    let end = (start + 1..lines.len())
+        .find(|&i| is_boundary(&lines[i]))
+        .unwrap_or(lines.len());
+    let at = (start + 1..end)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty())
+        .map_or(start + 1, |i| i + 1);
+    lines.splice(at..at, new);
+}
@@ -0,0 +1,377 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use chrono::Local;
+use serde::Deserialize;
+
+use crate::json::{self, Json};
+use crate::sandbox;
+use crate::task::Task;
+
+/// `[serve]` config for `task serve`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServeConfig {
+    /// Address and port to listen on (default 127.0.0.1:7878)
+    pub bind: Option<String>,
+    /// Token clients send as `Authorization: Bearer <token>`
+    pub token: Option<String>,
+    /// Only allow listing tasks
+    pub read_only: bool,
+    /// Web pages allowed to call the API from a browser, such as
+    /// `https://dash.example.com`
+    pub origins: Vec<String>,
+}
+
+pub const DEFAULT_BIND: &str = "127.0.0.1:7878";
+
+// Requests with a larger body are refused
+const MAX_BODY: usize = 64 * 1024;
+
+/// A parsed HTTP request.
+pub struct Request {
+    pub method: String,
+    /// Decoded path segments, `/tasks/3/done` as `["tasks", "3", "done"]`
+    pub path: Vec<String>,
+    query: Vec<(String, String)>,
+    token: Option<String>,
+    /// The `Origin` header a browser sends with a cross-origin request
+    origin: Option<String>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A JSON response.
+pub struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, body: String) -> Response {
+        Response { status, body }
+    }
+
+    pub fn error(status: u16, message: &str) -> Response {
+        Response::json(status, format!("{{\"error\":{}}}", json::string(message)))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok());
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, Response> {
+    let bad = |_| Response::error(400, "malformed request");
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(bad)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request"));
+    };
+
+    let mut length = 0;
+    let mut token = None;
+    let mut origin = None;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).map_err(bad)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        match name.trim().to_lowercase().as_str() {
+            "content-length" => {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?
+            }
+            "authorization" => {
+                token = value.trim().strip_prefix("Bearer ").map(String::from);
+            }
+            "origin" => origin = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    if length > MAX_BODY {
+        return Err(Response::error(413, "request body too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(bad)?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (decode(k), decode(v))
+        })
+        .collect::<Vec<_>>();
+    Ok(Request {
+        method: method.to_uppercase(),
+        path: path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(decode)
+            .collect(),
+        query,
+        token,
+        origin,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+/// Send `response`, letting a browser hand it to the page when the request
+/// came from `allowed`, an origin in the `origins` config.
+fn write_response(mut stream: &TcpStream, response: &Response, allowed: Option<&str>) {
+    let cors = allowed.map_or(String::new(), |origin| {
+        format!(
+            "Access-Control-Allow-Origin: {}\r\nVary: Origin\r\n\
+             Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n",
+            origin
+        )
+    });
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         {}Connection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        cors,
+        response.body
+    );
+}
+
+/// Compare tokens without stopping at the first difference.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Answer requests on `bind` one at a time with `handle`, logging each to
+/// stdout. Requests without `token` are refused, even from this machine, so
+/// a web page open in a browser can't read or change tasks; so is anything
+/// but `GET` when `read_only`. Only pages from `origins` get cross-origin
+/// responses. Runs until interrupted.
+pub fn run(
+    bind: &str,
+    token: Option<&str>,
+    read_only: bool,
+    origins: &[String],
+    mut handle: impl FnMut(&Request) -> Response,
+) -> Result<(), String> {
+    let Some(token) = token else {
+        return Err(format!(
+            "refusing to listen on {} without a token; pass --token or set token under [serve]",
+            bind
+        ));
+    };
+    let listener =
+        TcpListener::bind(bind).map_err(|e| format!("could not listen on {}: {}", bind, e))?;
+    println!(
+        "Serving tasks on http://{}{} (Ctrl-C to stop)",
+        bind,
+        if read_only { ", read-only" } else { "" }
+    );
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+        let (label, response, allowed) = match read_request(&stream) {
+            Err(response) => ("-".to_string(), response, None),
+            Ok(request) => {
+                let label = format!("{} /{}", request.method, request.path.join("/"));
+                let allowed = request
+                    .origin
+                    .clone()
+                    .filter(|o| origins.iter().any(|allowed| allowed == o));
+                // A preflight carries no token; it gets no tasks either
+                let response = if request.method == "OPTIONS" {
+                    Response::json(204, String::new())
+                } else if !request
+                    .token
+                    .as_deref()
+                    .is_some_and(|given| same_token(given, token))
+                {
+                    Response::error(401, "missing or wrong token")
+                } else if read_only && request.method != "GET" {
+                    Response::error(403, "the server is read-only")
+                } else {
+                    handle(&request)
+                };
+                (label, response, allowed)
+            }
+        };
+        println!(
+            "{} {} {}",
+            Local::now().format("%H:%M:%S"),
+            label,
+            response.status
+        );
+        write_response(&stream, &response, allowed.as_deref());
+    }
+    Ok(())
+}
+
+/// Tasks as a JSON array, each with the `num` that `done` and `cancel`
+/// take (`null` for tasks that aren't pending). Only pending tasks unless
+/// `all`.
+pub fn list(lines: &[String], pending: &[usize], all: bool) -> Response {
+    let mut items = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(task) = Task::parse(line) else {
+            continue;
+        };
+        let num = pending.iter().position(|&p| p == i).map(|n| n + 1);
+        if num.is_none() && !all {
+            continue;
+        }
+        let object = sandbox::task_json(&task);
+        let num = num.map_or("null".to_string(), |n| n.to_string());
+        items.push(format!("{{\"num\":{},{}", num, &object[1..]));
+    }
+    Response::json(200, format!("[{}]", items.join(",")))
+}
+
+/// `task add` arguments for a body like `{"text": "Buy milk", "due":
+/// "tomorrow", "priority": "high"}`. A `null` due date adds it without one.
+pub fn add_args(body: &str) -> Result<Vec<String>, String> {
+    let task = json::parse(body)?;
+    let text = task
+        .str("text")
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or("missing \"text\"")?;
+    let mut args = vec!["add".to_string()];
+    for key in [
+        "priority",
+        "start",
+        "scheduled",
+        "recur",
+        "estimate",
+        "source",
+    ] {
+        if let Some(value) = task.str(key) {
+            args.push(format!("--{}", key));
+            args.push(value.to_string());
+        }
+    }
+    if task.get("due") == Some(&Json::Null) {
+        args.push("--no-due".to_string());
+    }
+    args.push("--".to_string());
+    if let Some(due) = task.str("due") {
+        args.push(due.to_string());
+    }
+    args.push(text.to_string());
+    Ok(args)
+}
+
+/// `task cancel` arguments for task `num`, with an optional `{"reason": …}`
+/// body.
+pub fn cancel_args(num: &str, body: &str) -> Result<Vec<String>, String> {
+    let mut args = vec!["cancel".to_string(), num.to_string()];
+    if !body.trim().is_empty()
+        && let Some(reason) = json::parse(body)?.str("reason")
+    {
+        args.push("--reason".to_string());
+        args.push(reason.to_string());
+    }
+    Ok(args)
+}
+
+/// Run `task` with `args` on `task_file`, so a change goes through the same
+/// checks and locking as on the command line. Its output is returned as
+/// `message` with `status`; if it fails, its first line on stderr becomes a
+/// 400.
+pub fn replay(task_file: &Path, args: &[String], status: u16) -> Response {
+    let output = std::env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .arg("--file")
+            .arg(task_file)
+            .arg("--quiet")
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+    });
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => return Response::error(500, &format!("could not run task: {}", e)),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let message = stderr
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("command failed");
+        let message = message
+            .trim_start_matches("Error: ")
+            .trim_start_matches("error: ");
+        return Response::error(400, message);
+    }
+    Response::json(
+        status,
+        format!("{{\"message\":{}}}", json::string(stdout.trim())),
+    )
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::json;
+use crate::storage;
+use crate::task::{Status, Task};
+
+/// The counts `task status` reports.
+pub struct Summary {
+    pub overdue: usize,
+    pub due_today: usize,
+    pub pending: usize,
+}
+
+pub fn summarize(lines: &[String], today: NaiveDate) -> Summary {
+    let pending = lines
+        .iter()
+        .filter_map(|l| Task::parse(l))
+        .filter(|t| t.status == Status::Pending)
+        .collect::<Vec<_>>();
+    Summary {
+        overdue: pending
+            .iter()
+            .filter(|t| t.due.is_some_and(|d| d < today))
+            .count(),
+        due_today: pending.iter().filter(|t| t.due == Some(today)).count(),
+        pending: pending.len(),
+    }
+}
+
+/// One line for a prompt or status bar, such as `3 due today, 1 overdue`.
+fn short(summary: &Summary) -> String {
+    let mut parts = Vec::new();
+    if summary.due_today > 0 {
+        parts.push(format!("{} due today", summary.due_today));
+    }
+    if summary.overdue > 0 {
+        parts.push(format!("{} overdue", summary.overdue));
+    }
+    if parts.is_empty() {
+        return "nothing due".to_string();
+    }
+    parts.join(", ")
+}
+
+/// A waybar custom module's JSON: the short line, the full counts as a
+/// tooltip and a class to style it by, `overdue`, `today` or `clear`.
+fn waybar(summary: &Summary) -> String {
+    let class = if summary.overdue > 0 {
+        "overdue"
+    } else if summary.due_today > 0 {
+        "today"
+    } else {
+        "clear"
+    };
+    let tooltip = format!(
+        "{} overdue\n{} due today\n{} pending",
+        summary.overdue, summary.due_today, summary.pending
+    );
+    format!(
+        "{{\"text\":{},\"tooltip\":{},\"class\":\"{}\",\"alt\":\"{}\"}}",
+        json::string(&short(summary)),
+        json::string(&tooltip),
+        class,
+        class
+    )
+}
+
+pub fn print(summary: &Summary, one_line: bool, as_json: bool) {
+    if as_json {
+        println!("{}", waybar(summary));
+    } else if one_line {
+        println!("{}", short(summary));
+    } else {
+        println!("Overdue:   {}", summary.overdue);
+        println!("Due today: {}", summary.due_today);
+        println!("Pending:   {}", summary.pending);
+    }
+}
+
+/// What the cached counts are only good for: this version of the task file,
+/// on this day.
+fn cache_key(task_file: &Path, today: NaiveDate) -> Option<String> {
+    Some(format!("{} {}", storage::fingerprint(task_file)?, today))
+}
+
+/// The counts from the last run, if the task file hasn't changed since
+/// and it's still the same day, so no need to read it.
+pub fn cached(task_file: &Path, today: NaiveDate) -> Option<Summary> {
+    let key = cache_key(task_file, today)?;
+    let contents = fs::read_to_string(storage::cache_file(task_file, "status")).ok()?;
+    let (stored, counts) = contents.split_once('\n')?;
+    if stored != key {
+        return None;
+    }
+    let counts = counts
+        .split_whitespace()
+        .map(|n| n.parse().ok())
+        .collect::<Option<Vec<usize>>>()?;
+    let [overdue, due_today, pending] = counts[..] else {
+        return None;
+    };
+    Some(Summary {
+        overdue,
+        due_today,
+        pending,
+    })
+}
+
+/// Keep `summary` for the next run. The cache only saves time, so failing
+/// to write it is ignored.
+pub fn store(task_file: &Path, today: NaiveDate, summary: &Summary) {
+    let Some(key) = cache_key(task_file, today) else {
+        return;
+    };
+    let path = storage::cache_file(task_file, "status");
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(
+        &path,
+        format!(
+            "{}\n{} {} {}\n",
+            key, summary.overdue, summary.due_today, summary.pending
+        ),
+    );
+}
@@ -0,0 +1,1147 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read as _, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::backup;
+use crate::context;
+use crate::crypt;
+use crate::index;
+use crate::recurrence::stable_hash;
+use crate::sections;
+use crate::task::Task;
+
+/// Exit status when the task file can't be read, locked or written.
+pub const EXIT_STORAGE: i32 = 3;
+
+/// Directory `task init` creates for a project-local task list.
+pub const PROJECT_DIR: &str = ".task";
+
+/// The nearest project task directory in the current directory or one of its
+/// parents, recognised by the task file inside it.
+pub fn find_project_dir() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_DIR))
+        .find(|dir| dir.join("work_log.md").is_file())
+}
+
+/// The home directory: `USERPROFILE` (or `HOMEDRIVE` and `HOMEPATH`) on
+/// Windows, `HOME` elsewhere.
+fn home_dir() -> PathBuf {
+    let home = if cfg!(windows) {
+        // On Windows, try USERPROFILE first, then HOMEDRIVE+HOMEPATH
+        env::var("USERPROFILE")
+            .or_else(|_: env::VarError| -> Result<String, env::VarError> {
+                let drive = env::var("HOMEDRIVE").unwrap_or_else(|_| String::from("C:"));
+                let path =
+                    env::var("HOMEPATH").unwrap_or_else(|_| String::from("\\Users\\Default"));
+                Ok(format!("{}{}", drive, path))
+            })
+            .unwrap_or_else(|_| String::from("."))
+    } else {
+        // On Unix systems (Linux, macOS), use HOME
+        env::var("HOME").unwrap_or_else(|_| String::from("."))
+    };
+    PathBuf::from(home)
+}
+
+/// `$<var>/task`, or `<fallback>/task` under the home directory when the
+/// variable is unset or not an absolute path, as the XDG spec says.
+fn xdg_dir(var: &str, home: &Path, fallback: &str) -> PathBuf {
+    env::var(var)
+        .map(PathBuf::from)
+        .ok()
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home.join(fallback))
+        .join("task")
+}
+
+/// Where the task file and everything kept with it live by default.
+fn data_dir(home: &Path) -> PathBuf {
+    if cfg!(windows) {
+        home.join("AppData").join("Local").join("Task")
+    } else if cfg!(target_os = "macos") {
+        home.join("Library")
+            .join("Application Support")
+            .join("task")
+    } else {
+        xdg_dir("XDG_DATA_HOME", home, ".local/share")
+    }
+}
+
+/// Where `config.toml` lives by default; the data directory except on
+/// Linux and other XDG systems.
+fn config_dir(home: &Path) -> PathBuf {
+    if cfg!(windows) || cfg!(target_os = "macos") {
+        data_dir(home)
+    } else {
+        xdg_dir("XDG_CONFIG_HOME", home, ".config")
+    }
+}
+
+/// Where data that can be thrown away, such as the `task status` cache, is
+/// kept.
+pub fn cache_dir() -> PathBuf {
+    let home = home_dir();
+    if cfg!(windows) {
+        data_dir(&home).join("cache")
+    } else if cfg!(target_os = "macos") {
+        home.join("Library").join("Caches").join("task")
+    } else {
+        xdg_dir("XDG_CACHE_HOME", &home, ".cache")
+    }
+}
+
+/// The `kind` cache file for `task_file`, one per task file so switching
+/// lists doesn't throw the others' away.
+pub fn cache_file(task_file: &Path, kind: &str) -> PathBuf {
+    let hash = stable_hash(&task_file.display().to_string()) as u32;
+    cache_dir().join(format!("{}-{:08x}", kind, hash))
+}
+
+/// The size and modification time of `path`, which a cache made from it is
+/// only good for.
+pub fn fingerprint(path: &Path) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("{} {}", metadata.len(), modified.as_nanos()))
+}
+
+// Taskwarrior keeps its data in ~/.task too; a directory holding it is
+// never moved
+const TASKWARRIOR_FILES: &[&str] = &["pending.data", "completed.data", "taskchampion.sqlite3"];
+
+/// The task directory to use, after moving `legacy` (`~/.task`, where
+/// earlier versions kept everything) to `dir` the first time, with its
+/// config file going to `config_dir`. A `legacy` shared with Taskwarrior
+/// is left, and used, where it is.
+fn migrate(legacy: &Path, dir: &Path, config_dir: &Path) -> PathBuf {
+    if dir.exists() || !legacy.is_dir() {
+        return dir.to_path_buf();
+    }
+    if TASKWARRIOR_FILES.iter().any(|f| legacy.join(f).exists()) {
+        return if legacy.join("work_log.md").exists() {
+            legacy.to_path_buf()
+        } else {
+            dir.to_path_buf()
+        };
+    }
+    let moved = dir
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::rename(legacy, dir));
+    if let Err(e) = moved {
+        eprintln!(
+            "Warning: could not move {} to {}: {}; still using it.",
+            legacy.display(),
+            dir.display(),
+            e
+        );
+        return legacy.to_path_buf();
+    }
+    eprintln!("Moved {} to {}.", legacy.display(), dir.display());
+
+    let config = dir.join("config.toml");
+    let new_config = config_dir.join("config.toml");
+    if config.exists() && !new_config.exists() {
+        match fs::create_dir_all(config_dir).and_then(|_| fs::rename(&config, &new_config)) {
+            Ok(()) => eprintln!("Moved config.toml to {}.", new_config.display()),
+            Err(e) => eprintln!(
+                "Warning: could not move config.toml to {}: {}",
+                config_dir.display(),
+                e
+            ),
+        }
+    }
+    dir.to_path_buf()
+}
+
+pub fn get_task_dir() -> PathBuf {
+    // A project set up with `task init` takes precedence
+    if let Some(dir) = find_project_dir() {
+        return dir;
+    }
+    let home = home_dir();
+    if cfg!(windows) {
+        return data_dir(&home);
+    }
+    migrate(&home.join(".task"), &data_dir(&home), &config_dir(&home))
+}
+
+/// The config file: `TASK_CONFIG`, else `config.toml` in the task directory
+/// if there is one (a project's, or one never moved out), else in the
+/// platform's config directory.
+pub fn config_file(task_dir: &Path) -> PathBuf {
+    if let Ok(path) = env::var("TASK_CONFIG") {
+        return PathBuf::from(path);
+    }
+    let local = task_dir.join("config.toml");
+    if local.exists() {
+        return local;
+    }
+    config_dir(&home_dir()).join("config.toml")
+}
+
+pub fn get_task_file(task_dir: &Path) -> PathBuf {
+    // First try to use TASK_FILE environment variable if set
+    if let Ok(path) = env::var("TASK_FILE") {
+        return PathBuf::from(path);
+    }
+
+    // Create directory if it doesn't exist
+    if !task_dir.exists() {
+        std::fs::create_dir_all(task_dir).unwrap_or_else(|e| {
+            eprintln!("Error creating task directory: {}", e);
+            eprintln!("Please set TASK_FILE environment variable to a writable location.");
+            std::process::exit(EXIT_STORAGE);
+        });
+    }
+
+    task_dir.join("work_log.md")
+}
+
+/// Environment variable naming the task list to use, like `--list`.
+pub const LIST_VAR: &str = "TASK_LIST";
+
+/// Set for the commands `task batch` runs on its copy of the task file,
+/// which take no backups and run no hooks: the batch does that once.
+pub const BATCH_VAR: &str = "TASK_BATCH";
+
+/// Name of the main task file among the task lists.
+pub const DEFAULT_LIST: &str = "default";
+
+/// Directory under the task directory holding the named task lists.
+const LISTS_DIR: &str = "lists";
+
+/// The file of task list `name`: `lists/<name>.md` in the task directory,
+/// or the main task file for `default`. A list that doesn't exist yet is
+/// created when `create` is set, so the first `add` starts it; otherwise
+/// it's an error.
+pub fn list_file(task_dir: &Path, name: &str, create: bool) -> Result<PathBuf, String> {
+    if name == DEFAULT_LIST {
+        return Ok(get_task_file(task_dir));
+    }
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "'{}' isn't a valid list name; use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    let path = task_dir.join(LISTS_DIR).join(format!("{}.md", name));
+    if !path.exists() {
+        if !create {
+            return Err(format!(
+                "there is no list '{}'; 'task lists' shows them, and adding a task starts one",
+                name
+            ));
+        }
+        fs::create_dir_all(task_dir.join(LISTS_DIR))
+            .map_err(|e| format!("could not create the list directory: {}", e))?;
+    }
+    Ok(path)
+}
+
+/// Every task list by name, the default one first and the rest sorted.
+pub fn lists(task_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut named = fs::read_dir(task_dir.join(LISTS_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|e| e == "md"))
+                .filter_map(|path| {
+                    let name = path.file_stem()?.to_string_lossy().into_owned();
+                    Some((name, path))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    named.sort();
+    let mut lists = vec![(DEFAULT_LIST.to_string(), get_task_file(task_dir))];
+    lists.extend(named);
+    lists
+}
+
+/// Size in bytes from which task files are updated in place, from
+/// `partial_writes_kb` in the config. Unset or 0 always rewrites the file.
+static PARTIAL_WRITES: OnceLock<u64> = OnceLock::new();
+
+/// In-place updates of a task file between whole rewrites, from
+/// `compact_every` in the config. 0 never rewrites it whole.
+static COMPACT_EVERY: OnceLock<u64> = OnceLock::new();
+
+/// Share of a task file's lines, from `max_removed_percent` in the config,
+/// that a single write may remove. Unset or 0 never refuses.
+static MAX_REMOVED: OnceLock<u64> = OnceLock::new();
+
+/// Set by commands whose job is to take lines out of the task file, which
+/// the [`MAX_REMOVED`] check would otherwise stop.
+static SHRINKING: OnceLock<()> = OnceLock::new();
+
+// Fewer removed lines than this are never refused, so deleting a couple of
+// tasks from a short file goes through
+const MIN_REFUSED: usize = 10;
+
+pub fn init(partial_writes_kb: u64, compact_every: u64, max_removed_percent: u64) {
+    let _ = PARTIAL_WRITES.set(partial_writes_kb * 1024);
+    let _ = COMPACT_EVERY.set(compact_every);
+    let _ = MAX_REMOVED.set(max_removed_percent);
+}
+
+/// Let this process's writes remove any number of lines: for `archive`,
+/// `delete` and the like, which keep a copy of what they take out.
+pub fn allow_shrinking() {
+    let _ = SHRINKING.set(());
+}
+
+/// Check a task file given with `--file` and make it absolute, so commands
+/// that pass it on (`shell`, `as-of`) still find it from another directory.
+pub fn explicit_task_file(path: &Path) -> Result<PathBuf, String> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()
+            .map_err(|e| format!("could not resolve {}: {}", path.display(), e))?
+            .join(path)
+    };
+    if path.is_dir() {
+        return Err(format!(
+            "{} is a directory, not a task file",
+            path.display()
+        ));
+    }
+    match path.parent() {
+        Some(dir) if !dir.is_dir() => Err(format!(
+            "directory {} doesn't exist; create it first or pick another path for --file",
+            dir.display()
+        )),
+        _ => Ok(path),
+    }
+}
+
+/// A task file as last read or written by this process.
+#[derive(Clone, Copy)]
+struct Read {
+    version: u64,
+    lines: usize,
+    /// Lines end in `\r\n` rather than `\n`
+    crlf: bool,
+    /// The last line ends in a line break too
+    final_newline: bool,
+}
+
+/// How each task file was when last read or written. Writes check the
+/// version so an edit made in the meantime by something that doesn't take
+/// the lock (an editor, Obsidian, a sync client) is never silently lost,
+/// and keep the line endings the file had.
+static READS: Mutex<Option<HashMap<PathBuf, Read>>> = Mutex::new(None);
+
+fn version(lines: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    lines.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn record(path: &Path, lines: &[String], crlf: bool, final_newline: bool) {
+    let mut reads = READS.lock().unwrap_or_else(|e| e.into_inner());
+    reads.get_or_insert_with(HashMap::new).insert(
+        path.to_path_buf(),
+        Read {
+            version: version(lines),
+            lines: lines.len(),
+            crlf,
+            final_newline,
+        },
+    );
+}
+
+fn last_read(path: &Path) -> Option<Read> {
+    let reads = READS.lock().unwrap_or_else(|e| e.into_inner());
+    reads.as_ref().and_then(|r| r.get(path)).copied()
+}
+
+/// Refuse to write if the file no longer matches the version that was read.
+fn check_version(path: &Path) {
+    let Some(expected) = last_read(path).map(|r| r.version) else {
+        return;
+    };
+
+    let current = read_text(path)
+        .map(|s| s.lines().map(String::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if version(&current) != expected {
+        eprintln!(
+            "Error: {} was changed by another program while this command ran.",
+            path.display()
+        );
+        eprintln!("Nothing was written. Run the command again to apply it to the latest version.");
+        std::process::exit(EXIT_STORAGE);
+    }
+}
+
+pub fn read_lines(path: &Path) -> Vec<String> {
+    recover(path);
+    if !path.exists() {
+        File::create(path).unwrap_or_else(|e| {
+            eprintln!("Error creating task file at {}: {}", path.display(), e);
+            eprintln!("Please set TASK_FILE environment variable to a writable location.");
+            std::process::exit(EXIT_STORAGE);
+        });
+    }
+    let bytes = fs::read(path).unwrap_or_else(|e| {
+        eprintln!("Error opening task file at {}: {}", path.display(), e);
+        eprintln!("Please check file permissions or set TASK_FILE environment variable.");
+        std::process::exit(EXIT_STORAGE);
+    });
+    let text = crypt::open(bytes).unwrap_or_else(|e| {
+        eprintln!("Error reading task file: {}", e);
+        std::process::exit(EXIT_STORAGE);
+    });
+    let lines = text.lines().map(String::from).collect::<Vec<_>>();
+    let (crlf, final_newline) = line_endings(&text);
+    record(path, &lines, crlf, final_newline);
+    viewed(path, lines)
+}
+
+/// `lines` of `path` as the active context and `--section` show them, if
+/// it's the file being read through them.
+fn viewed(path: &Path, lines: Vec<String>) -> Vec<String> {
+    match VIEWED.get() {
+        Some(viewed) if viewed == path => sections::view(context::view(lines)),
+        _ => lines,
+    }
+}
+
+/// The lines of a task file one at a time, so the whole file never has to
+/// be in memory at once. An encrypted file is decrypted whole first; a
+/// missing one has no lines.
+pub fn stream(path: &Path) -> Box<dyn Iterator<Item = String>> {
+    let Ok(file) = File::open(path) else {
+        return Box::new(std::iter::empty());
+    };
+    let mut reader = BufReader::new(file);
+    let encrypted = reader
+        .fill_buf()
+        .is_ok_and(|start| crypt::tool_of(start) != crypt::Tool::None);
+    if encrypted {
+        let text = read_text(path).unwrap_or_else(|e| {
+            eprintln!("Error reading task file: {}", e);
+            std::process::exit(EXIT_STORAGE);
+        });
+        return Box::new(
+            text.lines()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        );
+    }
+    let path = path.to_path_buf();
+    Box::new(reader.lines().map(move |line| {
+        line.unwrap_or_else(|e| {
+            eprintln!("Error reading task file at {}: {}", path.display(), e);
+            std::process::exit(EXIT_STORAGE);
+        })
+    }))
+}
+
+/// Whether `line` could be an open task: pending (`[ ]`), waiting (`[>]`)
+/// or proposed (`[?]`), or is a heading, which tells the section of the
+/// tasks below it. Cheaper than parsing it.
+pub fn open_or_heading(line: &str) -> bool {
+    line.starts_with('#') || ["[ ]", "[>]", "[?]"].iter().any(|c| line.contains(c))
+}
+
+/// The task file for listings of open tasks: read a line at a time, with
+/// every line that can't be an open task (finished tasks, notes) left
+/// empty, so line numbers stay as they are in the file while the history
+/// isn't kept or parsed. With an up-to-date index only the open tasks and
+/// headings are read at all.
+pub fn read_open(path: &Path) -> Vec<String> {
+    recover(path);
+    let lines = index::open_lines(path)
+        .or_else(|| index::scan(path))
+        .unwrap_or_else(|| {
+            stream(path)
+                .map(|line| {
+                    if open_or_heading(&line) {
+                        line
+                    } else {
+                        String::new()
+                    }
+                })
+                .collect()
+        });
+    viewed(path, lines)
+}
+
+/// The text of a task file, a backup of one or any file [`write_text`]
+/// wrote, decrypted if it's encrypted.
+pub fn read_text(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    crypt::open(bytes)
+}
+
+/// Write `text` to `path`, encrypted if the task file is written encrypted:
+/// for the other files kept in the task directory that hold tasks, such as
+/// the archive, the time log and sync state. [`read_text`] reads them back.
+pub fn write_text(path: &Path, text: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let contents = crypt::seal(text)?;
+    fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+/// Task file whose reads hide the tasks the active context or `--section`
+/// filters out.
+static VIEWED: OnceLock<PathBuf> = OnceLock::new();
+
+/// Read `path` through the active context and `--section` from now on.
+/// Only for commands that never write it back.
+pub fn read_through_context(path: &Path) {
+    let _ = VIEWED.set(path.to_path_buf());
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    stable_hash(&String::from_utf8_lossy(bytes))
+}
+
+/// Write `contents` to `path` and flush it to disk.
+fn write_synced(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Put right an in-place update of `path` that never finished, from the
+/// journal [`write_in_place`] keeps while it writes.
+fn recover(path: &Path) {
+    undo(path, &cache_file(path, "journal"));
+}
+
+/// Undo an in-place update of `path` cut short, as recorded in `journal`:
+/// a file that has all the new contents keeps them, anything else gets its
+/// old bytes back. A journal that can't be read is ignored. Either way it's
+/// removed.
+fn undo(path: &Path, journal: &Path) {
+    let Ok(record) = fs::read(journal) else {
+        return;
+    };
+    // As journal_entry writes it
+    let header = record.iter().position(|&b| b == b'\n').and_then(|end| {
+        let fields = std::str::from_utf8(&record[..end])
+            .ok()?
+            .split(' ')
+            .map(|f| f.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let [start, len, written] = fields[..] else {
+            return None;
+        };
+        Some((end, start, len, written))
+    });
+    if let Some((end, start, len, written)) = header
+        && !fs::read(path).is_ok_and(|current| checksum(&current) == written)
+    {
+        let undone = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|mut file| {
+                file.seek(SeekFrom::Start(start))?;
+                file.write_all(&record[end + 1..])?;
+                file.set_len(len)?;
+                file.sync_all()
+            });
+        if let Err(e) = undone {
+            eprintln!(
+                "Error: could not undo an interrupted update of {}: {}",
+                path.display(),
+                e
+            );
+            eprintln!("'task restore' can put back the version from before it.");
+            std::process::exit(EXIT_STORAGE);
+        }
+        eprintln!(
+            "Warning: An update of {} was interrupted and has been undone.",
+            path.display()
+        );
+    }
+    let _ = fs::remove_file(journal);
+}
+
+/// Whether the next update of `path` should rewrite it whole, every
+/// `compact_every` in-place updates, counting this one towards it.
+fn compact_due(path: &Path) -> bool {
+    let Some(&every) = COMPACT_EVERY.get().filter(|every| **every > 0) else {
+        return false;
+    };
+    let counter = cache_file(path, "updates");
+    let updates = fs::read_to_string(&counter)
+        .ok()
+        .and_then(|n| n.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    let due = updates >= every;
+    // Only a count: losing it just moves the next whole rewrite
+    let _ = fs::create_dir_all(cache_dir());
+    let _ = fs::write(counter, if due { 0 } else { updates + 1 }.to_string());
+    due
+}
+
+/// Overwrite only the bytes of a large task file that changed, starting at
+/// the first changed line, so sync clients see a small change rather than a
+/// new file. Returns `Ok(false)` when the file should be rewritten whole
+/// instead, because it's below the size threshold or due a compacting
+/// rewrite.
+fn write_in_place(path: &Path, old: &[u8], new: &[u8]) -> std::io::Result<bool> {
+    let Some(&min) = PARTIAL_WRITES.get().filter(|min| **min > 0) else {
+        return Ok(false);
+    };
+    if (old.len() as u64) < min || compact_due(path) {
+        return Ok(false);
+    }
+    patch(path, &cache_file(path, "journal"), old, new)?;
+    Ok(true)
+}
+
+/// The journal entry for overwriting `old` with `new` from byte `start`:
+/// where the old bytes went, the file's old length and the new contents'
+/// checksum, then the old bytes.
+fn journal_entry(old: &[u8], new: &[u8], start: usize) -> Vec<u8> {
+    let mut entry = format!("{} {} {}\n", start, old.len(), checksum(new)).into_bytes();
+    entry.extend_from_slice(&old[start..]);
+    entry
+}
+
+/// Change `path` from `old` to `new` in place. The bytes about to be
+/// overwritten are saved to `journal`, and flushed to disk, first, so an
+/// update cut short is undone by [`undo`] and the file is never left
+/// half-written.
+fn patch(path: &Path, journal: &Path, old: &[u8], new: &[u8]) -> std::io::Result<()> {
+    let same = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let start = old[..same]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    // An edit that keeps the length, like ticking a checkbox, can leave the
+    // rest of the file untouched too
+    let end = if old.len() == new.len() {
+        let suffix = old[start..]
+            .iter()
+            .rev()
+            .zip(new[start..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        new.len() - suffix
+    } else {
+        new.len()
+    };
+
+    write_synced(journal, &journal_entry(old, new, start))?;
+
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(start as u64))?;
+    file.write_all(&new[start..end])?;
+    file.set_len(new.len() as u64)?;
+    file.sync_all()?;
+    let _ = fs::remove_file(journal);
+    Ok(())
+}
+
+/// Read the file back and check each line parses to the same task as the
+/// line that was meant to be written, catching encoding and filesystem
+/// problems that would otherwise corrupt tasks silently.
+fn verify(path: &Path, lines: &[String]) -> Result<(), String> {
+    let text = read_text(path).map_err(|e| format!("it could not be read back: {}", e))?;
+    let written = text.lines().collect::<Vec<_>>();
+    if written.len() != lines.len() {
+        return Err(format!(
+            "{} lines were written but {} read back",
+            lines.len(),
+            written.len()
+        ));
+    }
+    for (i, (meant, got)) in lines.iter().zip(&written).enumerate() {
+        if Task::parse(meant) != Task::parse(got) {
+            return Err(format!("line {} read back as \"{}\"", i + 1, got));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse a write that would remove more than `max_removed_percent` of the
+/// lines the file had when read, which is far more likely a bug or a bad
+/// merge than what was meant.
+fn check_shrinking(path: &Path, before: usize, after: usize) {
+    let max = MAX_REMOVED.get().copied().unwrap_or(0);
+    if !too_many_removed(before, after, max, SHRINKING.get().is_some()) {
+        return;
+    }
+    let removed = before - after;
+    eprintln!(
+        "Error: This change would remove {} of the {} lines in {}, more than max_removed_percent ({}%).",
+        removed,
+        before,
+        path.display(),
+        max
+    );
+    eprintln!("Nothing was written. If that's intended, raise max_removed_percent in config.toml.");
+    std::process::exit(EXIT_STORAGE);
+}
+
+/// Whether going from `before` lines to `after` removes more than `max`
+/// percent of them, `max` being 0 for no limit. At least [`MIN_REFUSED`]
+/// lines must go, and none are refused once `shrinking` is allowed.
+fn too_many_removed(before: usize, after: usize, max: u64, shrinking: bool) -> bool {
+    let removed = before.saturating_sub(after);
+    max > 0 && !shrinking && removed >= MIN_REFUSED && removed as u64 * 100 > max * before as u64
+}
+
+/// Whether text's lines end in `\r\n`, and whether its last line ends in a
+/// line break too, so writes keep both as they were.
+fn line_endings(text: &str) -> (bool, bool) {
+    let crlf = text.find('\n').is_some_and(|i| text[..i].ends_with('\r'));
+    (crlf, text.is_empty() || text.ends_with('\n'))
+}
+
+/// `lines` as the file's text, with the endings [`line_endings`] found.
+fn join_lines(lines: &[String], crlf: bool, final_newline: bool) -> String {
+    let ending = if crlf { "\r\n" } else { "\n" };
+    let mut contents = lines.join(ending);
+    if final_newline && !lines.is_empty() {
+        contents.push_str(ending);
+    }
+    contents
+}
+
+pub fn write_lines(path: &Path, lines: &[String]) {
+    check_version(path);
+    let read = last_read(path);
+    if let Some(read) = read {
+        check_shrinking(path, read.lines, lines.len());
+    }
+    // Line endings stay as they were, including a missing one at the end
+    let (crlf, final_newline) = read.map_or((false, true), |r| (r.crlf, r.final_newline));
+    let contents = join_lines(lines, crlf, final_newline);
+    let contents = crypt::seal(&contents).unwrap_or_else(|e| {
+        eprintln!("Error: could not encrypt the task file: {}", e);
+        eprintln!("Nothing was written.");
+        std::process::exit(EXIT_STORAGE);
+    });
+    let previous = fs::read(path).ok();
+    if let Some(old) = &previous {
+        backup::take(path, old);
+    }
+    // A failed in-place write falls back to rewriting the whole file. An
+    // encrypted file changes throughout, so it's always rewritten.
+    let in_place = !crypt::enabled()
+        && previous
+            .as_deref()
+            .is_some_and(|old| write_in_place(path, old, &contents).unwrap_or(false));
+    if !in_place {
+        replace(path, &contents);
+    }
+
+    if let Err(e) = verify(path, lines) {
+        eprintln!("Error: The task file didn't read back as written: {}.", e);
+        match previous {
+            Some(old) => {
+                replace(path, &old);
+                eprintln!("The previous version has been restored; nothing was changed.");
+            }
+            None => eprintln!("Check {} by hand.", path.display()),
+        }
+        std::process::exit(EXIT_STORAGE);
+    }
+    record(path, lines, crlf, final_newline);
+    if !crypt::enabled() {
+        index::update(path, lines, crlf);
+    }
+}
+
+/// Add `new` to the end of a plain-text task file without rewriting it, so
+/// the tasks already in it are never at risk and no backup is needed.
+/// Returns false, having written nothing, for an encrypted file, which can
+/// only be rewritten whole.
+pub fn append_lines(path: &Path, new: &[String]) -> bool {
+    if crypt::enabled() {
+        return false;
+    }
+    recover(path);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .unwrap_or_else(|e| {
+            eprintln!("Error opening task file at {}: {}", path.display(), e);
+            eprintln!("Please check file permissions or set TASK_FILE environment variable.");
+            std::process::exit(EXIT_STORAGE);
+        });
+    let len = file.metadata().map_or(0, |m| m.len());
+    let mut first = Vec::new();
+    let _ = BufReader::new(&file).read_until(b'\n', &mut first);
+    if crypt::tool_of(&first) != crypt::Tool::None {
+        return false;
+    }
+    let mut last = [0];
+    let final_newline = len == 0
+        || (file.seek(SeekFrom::End(-1)).is_ok()
+            && file.read_exact(&mut last).is_ok()
+            && last[0] == b'\n');
+    let previous = fingerprint(path);
+
+    // Line endings stay as they were, including a missing one at the end
+    let ending = if first.ends_with(b"\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut contents = new.join(ending);
+    let start = if final_newline {
+        contents.push_str(ending);
+        len
+    } else {
+        contents.insert_str(0, ending);
+        len + ending.len() as u64
+    };
+    if let Err((e, removed)) = append_checked(&mut file, len, start, contents.as_bytes(), new) {
+        eprintln!("Error: The task file didn't read back as written: {}.", e);
+        if removed {
+            eprintln!("The added lines have been removed; nothing was changed.");
+        } else {
+            eprintln!("Check the end of {} by hand.", path.display());
+        }
+        std::process::exit(EXIT_STORAGE);
+    }
+    index::append(path, previous.as_deref(), start, new, ending == "\r\n");
+    true
+}
+
+/// Append `contents` to `file`, `len` bytes long, and check the lines read
+/// back from `start` parse as `new` does. If they don't, the file is cut
+/// back to `len`; the error says why, and whether that worked.
+fn append_checked(
+    file: &mut File,
+    len: u64,
+    start: u64,
+    contents: &[u8],
+    new: &[String],
+) -> Result<(), (String, bool)> {
+    let written = file.write_all(contents).and_then(|_| file.sync_data());
+
+    let mut appended = String::new();
+    let read_back = file
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_to_string(&mut appended));
+    let check = match (written, read_back) {
+        (Err(e), _) | (_, Err(e)) => Err(e.to_string()),
+        (Ok(_), Ok(_)) => {
+            let got = appended.lines().collect::<Vec<_>>();
+            match new
+                .iter()
+                .zip(&got)
+                .position(|(n, g)| Task::parse(n) != Task::parse(g))
+            {
+                Some(i) => Err(format!("line {} read back as \"{}\"", i + 1, got[i])),
+                None if got.len() != new.len() => Err(format!(
+                    "{} lines were added but {} read back",
+                    new.len(),
+                    got.len()
+                )),
+                None => Ok(()),
+            }
+        }
+    };
+    check.map_err(|e| (e, file.set_len(len).is_ok()))
+}
+
+/// Write `contents` to a temporary file and rename it over `path`.
+fn replace(path: &Path, contents: &[u8]) {
+    let temp_path = path.with_extension("tmp");
+    let mut file = File::create(&temp_path).unwrap_or_else(|e| {
+        eprintln!("Error creating temporary file: {}", e);
+        eprintln!("Please check directory permissions or set TASK_FILE environment variable.");
+        std::process::exit(EXIT_STORAGE);
+    });
+
+    file.write_all(contents).unwrap_or_else(|e| {
+        eprintln!("Error writing to file: {}", e);
+        std::process::exit(EXIT_STORAGE);
+    });
+
+    file.sync_all().unwrap_or_else(|e| {
+        eprintln!("Error syncing file: {}", e);
+        std::process::exit(EXIT_STORAGE);
+    });
+
+    fs::rename(temp_path, path).unwrap_or_else(|e| {
+        eprintln!("Error renaming temporary file: {}", e);
+        std::process::exit(EXIT_STORAGE);
+    });
+}
+
+/// Exclusive advisory lock on the task file, held for the duration of a
+/// read-modify-write. Released when dropped.
+pub struct FileLock {
+    _file: File,
+}
+
+/// The file `lock` locks for `task_file`: one in the cache directory per
+/// task file, however the path to it was given, so none is left next to
+/// the tasks. A separate file is used because most writes replace the task
+/// file itself via rename, and it's never removed because a process may be
+/// waiting on it.
+pub fn lock_file(task_file: &Path) -> PathBuf {
+    let resolved = fs::canonicalize(task_file).unwrap_or_else(|_| task_file.to_path_buf());
+    cache_file(&resolved, "lock")
+}
+
+/// Lock the task file, waiting up to `timeout` for other `task` processes
+/// to finish.
+pub fn lock(path: &Path, timeout: Duration) -> FileLock {
+    let lock_path = lock_file(path);
+    if let Some(dir) = lock_path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .unwrap_or_else(|e| {
+            eprintln!("Error creating lock file at {}: {}", lock_path.display(), e);
+            eprintln!("Please check directory permissions or set TASK_FILE environment variable.");
+            std::process::exit(EXIT_STORAGE);
+        });
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return FileLock { _file: file },
+            Err(fs::TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                eprintln!(
+                    "Error: Task file is locked by another task process (waited {}s).",
+                    timeout.as_secs()
+                );
+                eprintln!(
+                    "Try again once the other command has finished, or raise lock_timeout in config.toml."
+                );
+                std::process::exit(EXIT_STORAGE);
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                eprintln!("Error locking task file: {}", e);
+                std::process::exit(EXIT_STORAGE);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const OLD: &str = "- [ ] First\n- [ ] Second\n- [ ] Third\n";
+    const NEW: &str = "- [ ] First\n- [x] Second ✅ 2025-09-15\n- [ ] Third\n";
+
+    /// A task file holding `contents` in a directory of its own, and where
+    /// its journal goes.
+    fn task_file(contents: &str) -> (TempDir, PathBuf, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("work_log.md");
+        fs::write(&path, contents).unwrap();
+        let journal = dir.path().join("journal");
+        (dir, path, journal)
+    }
+
+    /// The journal an update from `OLD` to `NEW` leaves if it's cut short.
+    fn interrupted(journal: &Path) {
+        let start = OLD.find("- [ ] Second").unwrap();
+        fs::write(
+            journal,
+            journal_entry(OLD.as_bytes(), NEW.as_bytes(), start),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn shrinking_is_refused_past_the_limit() {
+        // 50% of 100 lines may go, not 51
+        assert!(!too_many_removed(100, 50, 50, false));
+        assert!(too_many_removed(100, 49, 50, false));
+        // Growing or keeping the file is always fine
+        assert!(!too_many_removed(100, 120, 50, false));
+        // No limit set
+        assert!(!too_many_removed(100, 0, 0, false));
+    }
+
+    #[test]
+    fn small_removals_are_never_refused() {
+        // All of a short file
+        assert!(!too_many_removed(MIN_REFUSED - 1, 0, 10, false));
+        assert!(too_many_removed(MIN_REFUSED, 0, 10, false));
+    }
+
+    #[test]
+    fn allowed_shrinking_is_never_refused() {
+        assert!(!too_many_removed(100, 0, 10, true));
+        allow_shrinking();
+        assert!(SHRINKING.get().is_some());
+    }
+
+    #[test]
+    fn line_endings_survive_a_round_trip() {
+        for text in [
+            "- [ ] A\n- [ ] B\n",
+            "- [ ] A\n- [ ] B",
+            "- [ ] A\r\n- [ ] B\r\n",
+            "- [ ] A\r\n- [ ] B",
+            "- [ ] A\n",
+            "",
+        ] {
+            let lines = text.lines().map(String::from).collect::<Vec<_>>();
+            let (crlf, final_newline) = line_endings(text);
+            assert_eq!(join_lines(&lines, crlf, final_newline), text, "{:?}", text);
+        }
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn appending_keeps_what_is_there() {
+        let (_dir, path, _) = task_file("- [ ] A\n");
+        assert!(append_lines(&path, &lines(&["- [ ] B", "- [ ] C"])));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "- [ ] A\n- [ ] B\n- [ ] C\n"
+        );
+    }
+
+    #[test]
+    fn appending_keeps_the_line_endings_and_a_missing_final_newline() {
+        let (_dir, path, _) = task_file("- [ ] A\r\n- [ ] B");
+        assert!(append_lines(&path, &lines(&["- [ ] C"])));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "- [ ] A\r\n- [ ] B\r\n- [ ] C"
+        );
+    }
+
+    #[test]
+    fn appending_to_a_new_file_creates_it() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("work_log.md");
+        assert!(append_lines(&path, &lines(&["- [ ] A"])));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] A\n");
+    }
+
+    #[test]
+    fn lines_that_read_back_wrong_are_removed_again() {
+        let (_dir, path, _) = task_file("- [ ] A\n");
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .unwrap();
+        // What reaches the file isn't the task that was meant
+        let (e, removed) =
+            append_checked(&mut file, 8, 8, b"- [ ] Garbled\n", &lines(&["- [ ] B"])).unwrap_err();
+        assert_eq!(e, "line 1 read back as \"- [ ] Garbled\"");
+        assert!(removed);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] A\n");
+
+        let (e, _) = append_checked(
+            &mut file,
+            8,
+            8,
+            b"- [ ] B\n",
+            &lines(&["- [ ] B", "- [ ] C"]),
+        )
+        .unwrap_err();
+        assert_eq!(e, "2 lines were added but 1 read back");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] A\n");
+    }
+
+    #[test]
+    fn appending_ignores_changes_made_since_the_file_was_read() {
+        // Unlike write_lines, which would refuse: nothing already in the
+        // file is rewritten, so an edit made meanwhile can't be lost
+        let (_dir, path, _) = task_file("- [ ] A\n");
+        read_lines(&path);
+        fs::write(&path, "- [ ] A edited elsewhere\n").unwrap();
+        assert!(append_lines(&path, &lines(&["- [ ] B"])));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "- [ ] A edited elsewhere\n- [ ] B\n"
+        );
+    }
+
+    #[test]
+    fn patching_writes_the_new_contents_and_leaves_no_journal() {
+        let (_dir, path, journal) = task_file(OLD);
+        patch(&path, &journal, OLD.as_bytes(), NEW.as_bytes()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), NEW);
+        assert!(!journal.exists());
+
+        // Shorter again, so the end is cut off
+        patch(&path, &journal, NEW.as_bytes(), OLD.as_bytes()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), OLD);
+    }
+
+    #[test]
+    fn a_half_written_update_is_undone() {
+        let half = &NEW[..NEW.find("✅").unwrap()];
+        let (_dir, path, journal) = task_file(half);
+        interrupted(&journal);
+        undo(&path, &journal);
+        assert_eq!(fs::read_to_string(&path).unwrap(), OLD);
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn a_truncated_file_gets_its_old_bytes_back() {
+        let (_dir, path, journal) = task_file("- [ ] First\n");
+        interrupted(&journal);
+        undo(&path, &journal);
+        assert_eq!(fs::read_to_string(&path).unwrap(), OLD);
+    }
+
+    #[test]
+    fn a_finished_update_is_kept() {
+        let (_dir, path, journal) = task_file(NEW);
+        interrupted(&journal);
+        undo(&path, &journal);
+        assert_eq!(fs::read_to_string(&path).unwrap(), NEW);
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn a_corrupt_journal_is_ignored() {
+        for header in ["garbage\n", "12 34\n", "1 2 three\n", "no newline at all"] {
+            let (_dir, path, journal) = task_file("- [ ] Fir");
+            fs::write(&journal, format!("{}- [ ] Second\n", header)).unwrap();
+            undo(&path, &journal);
+            assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] Fir");
+            assert!(!journal.exists());
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::config::TaskFormat;
+use crate::diff;
+use crate::hooks;
+use crate::sections;
+use crate::storage;
+use crate::waiting;
+
+/// Where commands read the task file's lines from and write them back to:
+/// the task file itself, or in tests a list in memory.
+pub trait TaskStore {
+    /// Every line of the task file.
+    fn read(&mut self) -> Vec<String>;
+
+    /// The lines listings of open tasks need: the rest may be left empty,
+    /// keeping line numbers as they are in the file.
+    fn read_open(&mut self) -> Vec<String> {
+        self.read()
+    }
+
+    /// Replace `before`, the lines as read, with `lines`, first printing a
+    /// diff of the rewritten lines when `show_diff` is set.
+    fn write(&mut self, before: &[String], lines: &[String], show_diff: bool);
+
+    /// Add `new` to the end of the task file, or of the `--section` or
+    /// journal day.
+    fn append(&mut self, new: &[String], show_diff: bool) {
+        let before = self.read();
+        let mut lines = before.clone();
+        sections::place(&mut lines, new.to_vec());
+        self.write(&before, &lines, show_diff);
+    }
+}
+
+/// The task file on disk, written through the hooks, with each write
+/// checked and backed up by [`storage::write_lines`].
+///
+/// Waiting tasks whose wake-up date has come are read as pending, so every
+/// listing shows them and numbers them alike; the file only changes when a
+/// command writes it anyway.
+pub struct FileStore {
+    path: PathBuf,
+    today: NaiveDate,
+    format: TaskFormat,
+    quiet: bool,
+    /// Tasks the last read woke up, announced once they're written
+    woken: Vec<String>,
+}
+
+impl FileStore {
+    pub fn new(path: &Path, today: NaiveDate, format: TaskFormat, quiet: bool) -> Self {
+        FileStore {
+            path: path.to_path_buf(),
+            today,
+            format,
+            quiet,
+            woken: Vec::new(),
+        }
+    }
+
+    fn woken(&mut self, mut lines: Vec<String>) -> Vec<String> {
+        self.woken = waiting::wake(&mut lines, self.today, self.format);
+        lines
+    }
+}
+
+impl TaskStore for FileStore {
+    fn read(&mut self) -> Vec<String> {
+        let lines = storage::read_lines(&self.path);
+        self.woken(lines)
+    }
+
+    fn read_open(&mut self) -> Vec<String> {
+        let lines = storage::read_open(&self.path);
+        self.woken(lines)
+    }
+
+    fn write(&mut self, before: &[String], lines: &[String], show_diff: bool) {
+        if let Err(e) = hooks::pre(&self.path, before, lines) {
+            eprintln!("Error: {}; nothing was changed.", e);
+            std::process::exit(1);
+        }
+        if show_diff {
+            diff::print(before, lines);
+        }
+        storage::write_lines(&self.path, lines);
+        hooks::post(&self.path, before, lines);
+        // On stderr, so it never ends up in JSON, an export or a pipe
+        for text in self.woken.drain(..) {
+            if !self.quiet {
+                eprintln!("⏰ Back from waiting: {}", text);
+            }
+        }
+    }
+
+    /// Only appends to the file, leaving the rest untouched, unless the
+    /// tasks go in a section, a diff is to be shown or the file is
+    /// encrypted, which need it read and written whole.
+    fn append(&mut self, new: &[String], show_diff: bool) {
+        if !show_diff && sections::target().is_none() {
+            // The tasks a hook is given are the added ones either way
+            if let Err(e) = hooks::pre(&self.path, &[], new) {
+                eprintln!("Error: {}; nothing was changed.", e);
+                std::process::exit(1);
+            }
+            if storage::append_lines(&self.path, new) {
+                hooks::post(&self.path, &[], new);
+                return;
+            }
+        }
+        let before = self.read();
+        let mut lines = before.clone();
+        sections::place(&mut lines, new.to_vec());
+        self.write(&before, &lines, show_diff);
+    }
+}
+
+/// A task file held in memory, for driving commands in tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    pub lines: Vec<String>,
+    /// How many times the lines were written
+    pub writes: usize,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    pub fn new(lines: &[&str]) -> Self {
+        MemoryStore {
+            lines: lines.iter().map(|l| l.to_string()).collect(),
+            writes: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl TaskStore for MemoryStore {
+    fn read(&mut self) -> Vec<String> {
+        self.lines.clone()
+    }
+
+    fn write(&mut self, before: &[String], lines: &[String], _show_diff: bool) {
+        assert_eq!(before, self.lines, "written over lines that weren't read");
+        self.lines = lines.to_vec();
+        self.writes += 1;
+    }
+}
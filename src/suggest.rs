@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::task::{Status, Task};
+
+/// Words that say something about a task: lowercase, tags stripped of `#`,
+/// short filler words dropped.
+fn keywords(text: &str) -> HashSet<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.trim_start_matches('#')
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|w| w.chars().count() > 2)
+        .collect()
+}
+
+fn similar(a: &Task, words: &HashSet<String>, tags: &[&str]) -> bool {
+    if a.tags().iter().any(|t| tags.contains(t)) {
+        return true;
+    }
+    let other = keywords(&a.text);
+    let shared = words.intersection(&other).count();
+    let union = words.union(&other).count();
+    union > 0 && shared * 2 >= union
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suggestion {
+    pub days: i64,
+    pub due: NaiveDate,
+    pub matches: usize,
+}
+
+/// Suggest a due date for `text` from how long similar completed tasks took
+/// between creation and completion (the median, so outliers don't dominate).
+pub fn due_date(tasks: &[Task], text: &str, today: NaiveDate) -> Option<Suggestion> {
+    let probe = Task::new(text.to_string(), None, today);
+    let tags = probe.tags();
+    let words = keywords(text);
+
+    let mut latencies = tasks
+        .iter()
+        .filter(|t| t.status == Status::Done && similar(t, &words, &tags))
+        .filter_map(|t| Some((t.done? - t.created?).num_days()))
+        .filter(|d| *d >= 0)
+        .collect::<Vec<_>>();
+    if latencies.is_empty() {
+        return None;
+    }
+
+    latencies.sort_unstable();
+    let days = latencies[latencies.len() / 2];
+    if days == 0 {
+        return None;
+    }
+    Some(Suggestion {
+        days,
+        due: today + Duration::days(days),
+        matches: latencies.len(),
+    })
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::crypt;
+use crate::storage;
+use crate::task::{Status, Task};
+
+/// `[sync]` config for `task sync git` and `task sync caldav`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Remote repository URL, used when `task sync git` isn't given one
+    pub git_remote: Option<String>,
+    pub git_branch: String,
+    /// CalDAV task list URL, used when `task sync caldav` isn't given one
+    pub caldav_url: Option<String>,
+    pub caldav_user: Option<String>,
+    pub caldav_password: Option<String>,
+    /// Command printing the CalDAV password, e.g. from a keyring
+    pub caldav_password_command: Option<String>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        SyncConfig {
+            git_remote: None,
+            git_branch: "main".to_string(),
+            caldav_url: None,
+            caldav_user: None,
+            caldav_password: None,
+            caldav_password_command: None,
+        }
+    }
+}
+
+/// Identity of a task across copies of the file: its text and creation
+/// date, plus an occurrence counter so duplicates stay distinct.
+type Key = (String, Option<NaiveDate>, usize);
+
+fn keyed(lines: &[String]) -> Vec<(Option<Key>, &String)> {
+    let mut seen: HashMap<(String, Option<NaiveDate>), usize> = HashMap::new();
+    lines
+        .iter()
+        .map(|line| {
+            let key = Task::parse(line).map(|t| {
+                let n = seen.entry((t.text.clone(), t.created)).or_default();
+                *n += 1;
+                (t.text, t.created, *n)
+            });
+            (key, line)
+        })
+        .collect()
+}
+
+/// Pick between two conflicting edits of the same task: the one with the
+/// most recent activity wins, and a resolved task beats a pending one.
+fn resolve<'a>(local: &'a String, remote: &'a String) -> &'a String {
+    let rank = |line: &str| {
+        Task::parse(line).map(|t| {
+            let latest = [t.created, t.done, t.cancelled].into_iter().flatten().max();
+            (latest, matches!(t.status, Status::Done | Status::Cancelled))
+        })
+    };
+    if rank(remote) > rank(local) {
+        remote
+    } else {
+        local
+    }
+}
+
+/// Collapse copies of the same recurring occurrence (generated separately on
+/// two machines, possibly on different days) into the one with the most
+/// recent activity. Returns the lines and the number of copies dropped.
+fn dedupe_occurrences(lines: Vec<String>) -> (Vec<String>, usize) {
+    let mut kept: Vec<String> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut dropped = 0;
+
+    for line in lines {
+        let id = Task::parse(&line).and_then(|t| t.field("occurrence").map(String::from));
+        if let Some(id) = id {
+            if let Some(&i) = seen.get(&id) {
+                kept[i] = resolve(&kept[i], &line).clone();
+                dropped += 1;
+                continue;
+            }
+            seen.insert(id, kept.len());
+        }
+        kept.push(line);
+    }
+    (kept, dropped)
+}
+
+/// Three-way merge of the task file against the state of the last sync.
+/// Returns the merged lines and the number of conflicting tasks, counting
+/// duplicated recurring occurrences as conflicts.
+pub fn merge(base: &[String], local: &[String], remote: &[String]) -> (Vec<String>, usize) {
+    let base = keyed(base)
+        .into_iter()
+        .filter_map(|(k, l)| k.map(|k| (k, l)))
+        .collect::<HashMap<_, _>>();
+    let remote_keyed = keyed(remote);
+    let remote_map = remote_keyed
+        .iter()
+        .filter_map(|(k, l)| k.clone().map(|k| (k, *l)))
+        .collect::<HashMap<_, _>>();
+
+    let mut merged = Vec::new();
+    let mut conflicts = 0;
+    let mut local_keys = Vec::new();
+
+    for (key, line) in keyed(local) {
+        let Some(key) = key else {
+            // Notes and headings follow the local layout
+            merged.push(line.clone());
+            continue;
+        };
+        let chosen = match (base.get(&key), remote_map.get(&key)) {
+            // Removed remotely and untouched here
+            (Some(b), None) if *b == line => None,
+            (_, None) => Some(line),
+            (_, Some(r)) if *r == line => Some(line),
+            // Changed on one side only
+            (Some(b), Some(r)) if *b == line => Some(*r),
+            (Some(b), Some(r)) if *b == *r => Some(line),
+            (_, Some(r)) => {
+                conflicts += 1;
+                Some(resolve(line, r))
+            }
+        };
+        if let Some(chosen) = chosen {
+            merged.push(chosen.clone());
+        }
+        local_keys.push(key);
+    }
+
+    // Tasks added on the remote side (or edited there after a local delete)
+    for (key, line) in remote_keyed {
+        let Some(key) = key else { continue };
+        if local_keys.contains(&key) {
+            continue;
+        }
+        if base.get(&key).is_some_and(|b| *b == line) {
+            continue;
+        }
+        merged.push(line.clone());
+    }
+
+    let (merged, duplicates) = dedupe_occurrences(merged);
+    (merged, conflicts + duplicates)
+}
+
+/// Split `user@host:path` into the ssh destination and the remote task file.
+/// A directory path (the default layout) gets `work_log.md` appended.
+fn parse_remote(remote: &str) -> Option<(&str, String)> {
+    let (host, path) = remote.split_once(':')?;
+    if host.is_empty() {
+        return None;
+    }
+    let path = if path.is_empty() {
+        "~/.local/share/task"
+    } else {
+        path
+    };
+    let file = if path.ends_with(".md") {
+        path.to_string()
+    } else {
+        format!("{}/work_log.md", path.trim_end_matches('/'))
+    };
+    Some((host, file))
+}
+
+fn base_path(task_dir: &Path, remote: &str) -> PathBuf {
+    let name = remote
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    task_dir.join("sync").join(format!("{}.md", name))
+}
+
+fn lines_of(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+}
+
+fn fetch(host: &str, file: &str) -> Result<Vec<String>, String> {
+    let output = Command::new("ssh")
+        .arg(host)
+        .arg(format!("if [ -f {0} ]; then cat {0}; fi", file))
+        .output()
+        .map_err(|e| format!("could not run ssh: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ssh {} failed: {}",
+            host,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // The copy there is encrypted as the task file is
+    let text = crypt::open(output.stdout).map_err(|e| format!("error reading {}: {}", file, e))?;
+    Ok(lines_of(&text))
+}
+
+fn push(host: &str, file: &str, lines: &[String]) -> Result<(), String> {
+    let script = format!(
+        "mkdir -p $(dirname {0}) && cat > {0}.tmp && mv {0}.tmp {0}",
+        file
+    );
+    let mut child = Command::new("ssh")
+        .arg(host)
+        .arg(script)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run ssh: {}", e))?;
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    let contents = crypt::seal(&text)?;
+    let mut stdin = child.stdin.take().ok_or("could not open ssh stdin")?;
+    stdin
+        .write_all(&contents)
+        .map_err(|e| format!("error sending task file: {}", e))?;
+    drop(stdin);
+
+    let status = child.wait().map_err(|e| format!("ssh failed: {}", e))?;
+    if !status.success() {
+        return Err(format!("ssh {} exited with {}", host, status));
+    }
+    Ok(())
+}
+
+/// Two-way sync of the task file with a copy on another machine over ssh.
+/// Returns the merged lines for the caller to write locally.
+pub fn ssh(task_dir: &Path, remote: &str, local: &[String]) -> Result<Vec<String>, String> {
+    let (host, file) = parse_remote(remote)
+        .ok_or_else(|| format!("invalid remote '{}' (expected user@host:path)", remote))?;
+
+    let base_file = base_path(task_dir, remote);
+    let base = storage::read_text(&base_file)
+        .map(|s| lines_of(&s))
+        .unwrap_or_default();
+
+    println!("Fetching {}:{}...", host, file);
+    let remote_lines = fetch(host, &file)?;
+    let (merged, conflicts) = merge(&base, local, &remote_lines);
+
+    if merged != remote_lines {
+        println!("Pushing merged task file to {}...", host);
+        push(host, &file, &merged)?;
+    }
+
+    let mut contents = merged.join("\n");
+    contents.push('\n');
+    storage::write_text(&base_file, &contents)
+        .map_err(|e| format!("error saving sync state: {}", e))?;
+
+    if conflicts > 0 {
+        println!(
+            "Resolved {} conflicting task(s) in favour of the most recent change.",
+            conflicts
+        );
+    }
+    Ok(merged)
+}
+
+/// Run git in the task directory, returning its output.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Commit the task file to a git repository in the task directory, then pull
+/// from and push to `remote`. Diverged histories are merged task by task
+/// with the same rules as `sync ssh` rather than git's line merge, and the
+/// result is handed to `write` before the merge is committed. Returns the
+/// number of conflicting tasks resolved.
+pub fn git_sync(
+    task_dir: &Path,
+    task_file: &Path,
+    remote: Option<&str>,
+    branch: &str,
+    local: &[String],
+    write: &mut dyn FnMut(&[String]),
+) -> Result<usize, String> {
+    let file = task_file
+        .strip_prefix(task_dir)
+        .ok()
+        .and_then(Path::to_str)
+        .ok_or_else(|| {
+            format!(
+                "{} must be inside {} to sync it with git",
+                task_file.display(),
+                task_dir.display()
+            )
+        })?;
+
+    if !task_dir.join(".git").exists() {
+        println!("Creating a git repository in {}...", task_dir.display());
+        git(task_dir, &["init", "--quiet", "--initial-branch", branch])?;
+    }
+    match (git(task_dir, &["remote", "get-url", "origin"]), remote) {
+        (Ok(url), Some(remote)) if url.trim() != remote => {
+            git(task_dir, &["remote", "set-url", "origin", remote])?;
+        }
+        (Ok(_), _) => {}
+        (Err(_), Some(remote)) => {
+            git(task_dir, &["remote", "add", "origin", remote])?;
+        }
+        (Err(_), None) => {
+            return Err(
+                "no git remote configured; pass one or set git_remote under [sync]".to_string(),
+            );
+        }
+    }
+
+    git(task_dir, &["add", "--", file])?;
+    if git(task_dir, &["diff", "--cached", "--quiet"]).is_err() {
+        git(task_dir, &["commit", "--quiet", "-m", "Update tasks"])?;
+    }
+
+    let mut conflicts = 0;
+    let remote_head = format!("refs/remotes/origin/{}", branch);
+    let has_remote = !git(task_dir, &["ls-remote", "--heads", "origin", branch])?
+        .trim()
+        .is_empty();
+    if has_remote {
+        println!("Pulling from origin/{}...", branch);
+        git(task_dir, &["fetch", "--quiet", "origin", branch])?;
+        let has_local = git(task_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok();
+        let ancestor =
+            |a: &str, b: &str| git(task_dir, &["merge-base", "--is-ancestor", a, b]).is_ok();
+
+        if !has_local || ancestor("HEAD", &remote_head) {
+            git(task_dir, &["merge", "--quiet", "--ff-only", &remote_head])?;
+        } else if !ancestor(&remote_head, "HEAD") {
+            let show = |rev: &str| {
+                git(task_dir, &["show", &format!("{}:{}", rev, file)])
+                    .and_then(|s| crypt::open(s.into_bytes()))
+                    .map(|s| lines_of(&s))
+                    .unwrap_or_default()
+            };
+            // Histories started separately on each machine share no base
+            let base = git(task_dir, &["merge-base", "HEAD", &remote_head])
+                .map(|rev| show(rev.trim()))
+                .unwrap_or_default();
+            let (merged, count) = merge(&base, local, &show(&remote_head));
+            conflicts = count;
+            // Record the merge without git touching the file, then commit ours
+            git(
+                task_dir,
+                &[
+                    "merge",
+                    "--quiet",
+                    "--no-commit",
+                    "--allow-unrelated-histories",
+                    "-s",
+                    "ours",
+                    &remote_head,
+                ],
+            )?;
+            write(&merged);
+            git(task_dir, &["add", "--", file])?;
+            git(task_dir, &["commit", "--quiet", "--no-edit"])?;
+        }
+    }
+
+    println!("Pushing to origin/{}...", branch);
+    git(
+        task_dir,
+        &["push", "--quiet", "origin", &format!("HEAD:{}", branch)],
+    )?;
+    Ok(conflicts)
+}
@@ -0,0 +1,824 @@
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::config::TaskFormat;
+use crate::dates;
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Obsidian Tasks emoji markers
+const DUE: &str = "📅";
+const SCHEDULED: &str = "⏳";
+const START: &str = "🛫";
+const CREATED: &str = "➕";
+const LEGACY_CREATED: &str = "📋";
+const DONE: &str = "✅";
+const CANCELLED: &str = "❌";
+const RECURRENCE: &str = "🔁";
+const ID: &str = "🆔";
+const DEPENDS_ON: &str = "⛔";
+
+const DATE_MARKERS: [&str; 7] = [
+    DUE,
+    SCHEDULED,
+    START,
+    CREATED,
+    LEGACY_CREATED,
+    DONE,
+    CANCELLED,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pending,
+    Done,
+    Cancelled,
+    /// Delegated, with a `[wake:: date]` when it goes back to pending
+    Waiting,
+    /// Added by a bot or integration and awaiting `task approve`
+    Proposed,
+}
+
+impl Status {
+    fn checkbox(self) -> &'static str {
+        match self {
+            Status::Pending => "[ ]",
+            Status::Done => "[x]",
+            Status::Cancelled => "[-]",
+            Status::Waiting => "[>]",
+            Status::Proposed => "[?]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    Lowest,
+    Low,
+    #[default]
+    Normal,
+    Medium,
+    High,
+    Highest,
+}
+
+impl Priority {
+    pub fn emoji(self) -> Option<&'static str> {
+        match self {
+            Priority::Highest => Some("🔺"),
+            Priority::High => Some("⏫"),
+            Priority::Medium => Some("🔼"),
+            Priority::Normal => None,
+            Priority::Low => Some("🔽"),
+            Priority::Lowest => Some("⏬"),
+        }
+    }
+
+    fn from_emoji(s: &str) -> Option<Self> {
+        match s {
+            "🔺" => Some(Priority::Highest),
+            "⏫" => Some(Priority::High),
+            "🔼" => Some(Priority::Medium),
+            "🔽" => Some(Priority::Low),
+            "⏬" => Some(Priority::Lowest),
+            _ => None,
+        }
+    }
+
+    /// One level more urgent, saturating at `Highest`.
+    pub fn up(self) -> Self {
+        match self {
+            Priority::Lowest => Priority::Low,
+            Priority::Low => Priority::Normal,
+            Priority::Normal => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High | Priority::Highest => Priority::Highest,
+        }
+    }
+
+    /// One level less urgent, saturating at `Lowest`.
+    pub fn down(self) -> Self {
+        match self {
+            Priority::Highest => Priority::High,
+            Priority::High => Priority::Medium,
+            Priority::Medium => Priority::Normal,
+            Priority::Normal => Priority::Low,
+            Priority::Low | Priority::Lowest => Priority::Lowest,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Highest => "highest",
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+            Priority::Lowest => "lowest",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "highest" => Some(Priority::Highest),
+            "high" | "h" => Some(Priority::High),
+            "medium" | "m" => Some(Priority::Medium),
+            "normal" | "none" | "n" => Some(Priority::Normal),
+            "low" | "l" => Some(Priority::Low),
+            "lowest" => Some(Priority::Lowest),
+            _ => None,
+        }
+    }
+}
+
+/// A single task line parsed from the task file.
+///
+/// Both the Obsidian Tasks layout (description first, metadata after) and the
+/// legacy layout (`📅 due 📋 created` before the description) are understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub status: Status,
+    pub text: String,
+    pub priority: Priority,
+    pub recurrence: Option<String>,
+    pub created: Option<NaiveDate>,
+    pub start: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    pub due: Option<NaiveDate>,
+    pub done: Option<NaiveDate>,
+    pub cancelled: Option<NaiveDate>,
+    /// `🆔` identifier other tasks can depend on
+    pub id: Option<String>,
+    /// `⛔` ids of tasks that must be completed first
+    pub depends_on: Vec<String>,
+    /// Dataview-style `[key:: value]` annotations, in file order
+    pub fields: Vec<(String, String)>,
+}
+
+impl Task {
+    pub fn new(text: String, due: Option<NaiveDate>, created: NaiveDate) -> Self {
+        Task {
+            text,
+            due,
+            created: Some(created),
+            ..Task::blank(Status::Pending)
+        }
+    }
+
+    fn blank(status: Status) -> Self {
+        Task {
+            status,
+            text: String::new(),
+            priority: Priority::Normal,
+            recurrence: None,
+            created: None,
+            start: None,
+            scheduled: None,
+            due: None,
+            done: None,
+            cancelled: None,
+            id: None,
+            depends_on: Vec::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Parse a markdown checkbox line, returning `None` for anything that
+    /// isn't a task (headings, notes, blank lines).
+    pub fn parse(line: &str) -> Option<Self> {
+        // Obsidian also reads `*` and `+` bullets as tasks
+        let line = line.trim_start();
+        let rest = ["- [", "* [", "+ ["]
+            .iter()
+            .find_map(|bullet| line.strip_prefix(bullet))?;
+        let mut chars = rest.chars();
+        let status = match chars.next()? {
+            ' ' => Status::Pending,
+            'x' | 'X' => Status::Done,
+            '-' => Status::Cancelled,
+            '>' => Status::Waiting,
+            '?' => Status::Proposed,
+            _ => return None,
+        };
+        let rest = chars.as_str().strip_prefix(']')?;
+
+        let mut task = Task::blank(status);
+
+        let (rest, fields) = extract_fields(rest);
+        for (key, value) in fields {
+            if !task.set_dataview(&key, &value) {
+                task.fields.push((key, value));
+            }
+        }
+
+        let tokens = rest
+            .split_whitespace()
+            .map(strip_variation)
+            .collect::<Vec<_>>();
+        let mut words = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+
+            if let Some(priority) = Priority::from_emoji(token) {
+                task.priority = priority;
+                i += 1;
+                continue;
+            }
+
+            if token == RECURRENCE {
+                let end = tokens[i + 1..]
+                    .iter()
+                    .position(|t| is_marker(t))
+                    .map_or(tokens.len(), |p| i + 1 + p);
+                task.recurrence = Some(tokens[i + 1..end].join(" "));
+                i = end;
+                continue;
+            }
+
+            if let Some((marker, value, consumed)) = id_field(&tokens[i..]) {
+                if marker == ID {
+                    task.id = Some(value.to_string());
+                } else {
+                    task.depends_on.extend(
+                        value
+                            .split(',')
+                            .filter(|id| !id.is_empty())
+                            .map(String::from),
+                    );
+                }
+                i += consumed;
+                continue;
+            }
+
+            if let Some((marker, date, consumed)) = date_field(&tokens[i..]) {
+                let slot = match marker {
+                    DUE => &mut task.due,
+                    SCHEDULED => &mut task.scheduled,
+                    START => &mut task.start,
+                    CREATED | LEGACY_CREATED => &mut task.created,
+                    DONE => &mut task.done,
+                    _ => &mut task.cancelled,
+                };
+                *slot = Some(date);
+                i += consumed;
+                continue;
+            }
+
+            words.push(token);
+            i += 1;
+        }
+
+        let text = words.join(" ");
+        task.text = match text.strip_prefix("~~").and_then(|t| t.strip_suffix("~~")) {
+            Some(inner) if task.status == Status::Cancelled => inner.to_string(),
+            _ => text,
+        };
+        Some(task)
+    }
+
+    /// `#tags` in the description, without the leading `#`. Purely numeric
+    /// words like `#12` are references, not tags.
+    pub fn tags(&self) -> Vec<&str> {
+        self.text
+            .split_whitespace()
+            .filter_map(|w| w.strip_prefix('#'))
+            .map(|t| t.trim_end_matches(|c: char| c.is_ascii_punctuation() && c != '-' && c != '_'))
+            .filter(|t| !t.is_empty() && !t.chars().all(|c| c.is_ascii_digit()))
+            .collect()
+    }
+
+    /// Whether the task has a 🛫 start date still in the future, so it isn't
+    /// actionable yet.
+    pub fn is_deferred(&self, today: NaiveDate) -> bool {
+        self.start.is_some_and(|start| start > today)
+    }
+
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Estimated minutes from an `[estimate:: 10m]` annotation.
+    pub fn estimate(&self) -> Option<i64> {
+        self.field("estimate")
+            .and_then(|e| dates::parse_estimate(e).ok())
+    }
+
+    /// When a waiting task goes back to pending, from `[wake:: YYYY-MM-DD]`.
+    pub fn wakes(&self) -> Option<NaiveDate> {
+        self.field("wake")
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    /// Time of day the task is due, from `[time:: 14:00]`.
+    pub fn due_time(&self) -> Option<NaiveTime> {
+        self.field("time").and_then(dates::parse_time)
+    }
+
+    /// Times the task was put off with `task snooze`, from `[snoozed:: n]`.
+    pub fn snoozes(&self) -> u32 {
+        self.field("snoozed")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Pomodoros completed on this task, from `[pomodoros:: n]`.
+    pub fn pomodoros(&self) -> u32 {
+        self.field("pomodoros")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Minutes of focused work logged by `task pomo`, from `[focus:: 1h15m]`.
+    pub fn focus(&self) -> i64 {
+        self.field("focus")
+            .and_then(|f| dates::parse_estimate(f).ok())
+            .unwrap_or(0)
+    }
+
+    /// Set an annotation, replacing any existing value for `key`.
+    pub fn set_field(&mut self, key: &str, value: &str) {
+        match self.fields.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.fields.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Take an Obsidian Tasks Dataview field (`[due:: 2025-10-01]`) as the
+    /// metadata its emoji would give. Returns false for any other field, or
+    /// a value that doesn't parse, which is kept as a plain annotation.
+    fn set_dataview(&mut self, key: &str, value: &str) -> bool {
+        let slot = match key {
+            "created" => &mut self.created,
+            "start" => &mut self.start,
+            "scheduled" => &mut self.scheduled,
+            "due" => &mut self.due,
+            "completion" => &mut self.done,
+            "cancelled" => &mut self.cancelled,
+            "priority" => {
+                let Some(priority) = Priority::from_name(value) else {
+                    return false;
+                };
+                self.priority = priority;
+                return true;
+            }
+            "repeat" => {
+                self.recurrence = Some(value.to_string());
+                return true;
+            }
+            "id" => {
+                self.id = Some(value.to_string());
+                return true;
+            }
+            "dependsOn" => {
+                let ids = value.split(',').map(str::trim).filter(|id| !id.is_empty());
+                self.depends_on.extend(ids.map(String::from));
+                return true;
+            }
+            _ => return false,
+        };
+        match parse_date(value) {
+            Some(date) => {
+                *slot = Some(date);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_field(&mut self, key: &str) {
+        self.fields.retain(|(k, _)| k != key);
+    }
+
+    /// Render the task back into a markdown line in the configured layout.
+    pub fn to_line(&self, format: TaskFormat) -> String {
+        let text = if self.status == Status::Cancelled {
+            format!("~~{}~~", self.text)
+        } else {
+            self.text.clone()
+        };
+
+        let mut parts = vec![format!("- {}", self.status.checkbox())];
+        match format {
+            TaskFormat::Legacy => {
+                push_date(&mut parts, DONE, self.done);
+                push_date(&mut parts, CANCELLED, self.cancelled);
+                push_date(&mut parts, DUE, self.due);
+                push_date(&mut parts, LEGACY_CREATED, self.created);
+                parts.push(text);
+                parts.extend(self.emoji_extras());
+                push_date(&mut parts, START, self.start);
+                push_date(&mut parts, SCHEDULED, self.scheduled);
+            }
+            TaskFormat::Obsidian => {
+                parts.push(text);
+                parts.extend(self.emoji_extras());
+                push_date(&mut parts, CREATED, self.created);
+                push_date(&mut parts, START, self.start);
+                push_date(&mut parts, SCHEDULED, self.scheduled);
+                push_date(&mut parts, DUE, self.due);
+                push_date(&mut parts, CANCELLED, self.cancelled);
+                push_date(&mut parts, DONE, self.done);
+            }
+            TaskFormat::Dataview => {
+                parts.push(text);
+                parts.extend(self.dataview_fields());
+            }
+        }
+        parts.retain(|p| !p.is_empty());
+        parts.join(" ")
+    }
+
+    /// The fields written after the text in the emoji layouts, dates aside.
+    fn emoji_extras(&self) -> Vec<String> {
+        let mut extras = self
+            .fields
+            .iter()
+            .map(|(key, value)| format!("[{}:: {}]", key, value))
+            .collect::<Vec<_>>();
+        if let Some(emoji) = self.priority.emoji() {
+            extras.push(emoji.to_string());
+        }
+        if let Some(rule) = &self.recurrence {
+            extras.push(format!("{} {}", RECURRENCE, rule));
+        }
+        if !self.depends_on.is_empty() {
+            extras.push(format!("{} {}", DEPENDS_ON, self.depends_on.join(",")));
+        }
+        if let Some(id) = &self.id {
+            extras.push(format!("{} {}", ID, id));
+        }
+        extras
+    }
+
+    /// Every field as a `[key:: value]` annotation, dates included, for the
+    /// Dataview layout.
+    fn dataview_fields(&self) -> Vec<String> {
+        let dates = [
+            ("created", self.created),
+            ("start", self.start),
+            ("scheduled", self.scheduled),
+            ("due", self.due),
+            ("cancelled", self.cancelled),
+            ("completion", self.done),
+        ];
+        let fields = [
+            (self.priority != Priority::Normal)
+                .then(|| ("priority", self.priority.name().to_string())),
+            self.recurrence.clone().map(|r| ("repeat", r)),
+            (!self.depends_on.is_empty()).then(|| ("dependsOn", self.depends_on.join(","))),
+            self.id.clone().map(|id| ("id", id)),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            dates
+                .into_iter()
+                .filter_map(|(key, date)| date.map(|d| (key, d.format(DATE_FORMAT).to_string()))),
+        );
+        self.fields
+            .iter()
+            .map(|(key, value)| format!("[{}:: {}]", key, value))
+            .chain(fields.map(|(key, value)| format!("[{}:: {}]", key, value)))
+            .collect()
+    }
+}
+
+/// Indentation used for note lines written under a task.
+const NOTE_INDENT: &str = "    ";
+
+/// Notes attached to the task on `lines[idx]`: the indented continuation
+/// lines directly below it, up to the next task or unindented line.
+pub fn notes(lines: &[String], idx: usize) -> Vec<&str> {
+    lines[idx + 1..]
+        .iter()
+        .take_while(|l| is_note(l))
+        .map(|l| l.trim())
+        .collect()
+}
+
+/// Render note text as continuation lines, one per line of `text`.
+pub fn note_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| format!("{}{}", NOTE_INDENT, l))
+        .collect()
+}
+
+fn is_note(line: &str) -> bool {
+    line.starts_with([' ', '\t']) && !line.trim().is_empty() && Task::parse(line).is_none()
+}
+
+/// What a line of the task file holds, as `task lint` sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineKind {
+    Task,
+    Heading,
+    /// Indented text under a task
+    Note,
+    Blank,
+    /// Looks like a task but isn't read as one: why, and the line repaired
+    /// when there is an obvious repair
+    Malformed {
+        why: String,
+        fixed: Option<String>,
+    },
+    Other,
+}
+
+/// Classify one line of the task file.
+pub fn classify(line: &str) -> LineKind {
+    let trimmed = line.trim();
+    if Task::parse(line).is_some() {
+        LineKind::Task
+    } else if trimmed.is_empty() {
+        LineKind::Blank
+    } else if trimmed.trim_start_matches('#').starts_with(' ') {
+        LineKind::Heading
+    } else if let Some((why, fixed)) = near_miss(line) {
+        LineKind::Malformed { why, fixed }
+    } else if is_note(line) {
+        LineKind::Note
+    } else {
+        LineKind::Other
+    }
+}
+
+/// Why a line that looks like a checkbox item isn't read as a task, with
+/// the line as a task when the intent is clear.
+fn near_miss(line: &str) -> Option<(String, Option<String>)> {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    let repaired = |fixed: String| Some(fixed).filter(|f| Task::parse(f).is_some());
+
+    if let Some(rest) = trimmed.strip_prefix("-[") {
+        let fixed = format!("{}- [{}", indent, rest);
+        return Some((
+            "no space between '-' and the checkbox".to_string(),
+            repaired(fixed),
+        ));
+    }
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let (bullet, rest) = if digits > 0 {
+        let rest = &trimmed[digits..];
+        ("", rest.strip_prefix(". ").or(rest.strip_prefix(") "))?)
+    } else if ["- ", "* ", "+ "].iter().any(|b| trimmed.starts_with(b)) {
+        (&trimmed[..2], &trimmed[2..])
+    } else {
+        return None;
+    };
+    let rest = rest.trim_start();
+    // `[[wiki links]]` and `[links](…)` are ordinary list items
+    if !rest.starts_with('[') || rest.starts_with("[[") {
+        return None;
+    }
+    if digits > 0 {
+        let fixed = format!("{}- {}", indent, rest);
+        return Some((
+            "numbered list item; tasks need a '- ' bullet".to_string(),
+            repaired(fixed),
+        ));
+    }
+    if let Some(after) = rest.strip_prefix("[]") {
+        let fixed = format!("{}{}[ ]{}", indent, bullet, after);
+        return Some((
+            "empty checkbox '[]', expected '[ ]'".to_string(),
+            repaired(fixed),
+        ));
+    }
+    let mut chars = rest[1..].chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(status), Some(']'), None | Some(' ')) => {
+            Some((format!("unknown status '[{}]'", status), None))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a zero-padded ISO `YYYY-MM-DD` date. chrono alone would also accept
+/// `2025-9-1`, which Obsidian does not.
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+    if s.len() != 10 {
+        return None;
+    }
+    NaiveDate::parse_from_str(s, DATE_FORMAT).ok()
+}
+
+fn push_date(parts: &mut Vec<String>, marker: &str, date: Option<NaiveDate>) {
+    if let Some(date) = date {
+        parts.push(format!("{} {}", marker, date.format(DATE_FORMAT)));
+    }
+}
+
+/// Pull `[key:: value]` inline fields out of a line, returning what's left.
+fn extract_fields(s: &str) -> (String, Vec<(String, String)>) {
+    let mut rest = String::new();
+    let mut fields = Vec::new();
+    let mut remaining = s;
+
+    while let Some(open) = remaining.find('[') {
+        let candidate = &remaining[open + 1..];
+        let parsed = candidate.find(']').and_then(|close| {
+            let (key, value) = candidate[..close].split_once("::")?;
+            let valid_key = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+            valid_key.then(|| (key.to_string(), value.trim().to_string(), close))
+        });
+        match parsed {
+            Some((key, value, close)) => {
+                rest.push_str(&remaining[..open]);
+                fields.push((key, value));
+                remaining = &candidate[close + 1..];
+            }
+            None => {
+                rest.push_str(&remaining[..=open]);
+                remaining = candidate;
+            }
+        }
+    }
+    rest.push_str(remaining);
+    (rest, fields)
+}
+
+// Some editors emit emoji with a trailing variation selector (U+FE0F)
+fn strip_variation(token: &str) -> &str {
+    token.strip_suffix('\u{FE0F}').unwrap_or(token)
+}
+
+fn is_marker(token: &str) -> bool {
+    token == RECURRENCE
+        || token.starts_with(ID)
+        || token.starts_with(DEPENDS_ON)
+        || Priority::from_emoji(token).is_some()
+        || DATE_MARKERS.iter().any(|m| token.starts_with(m))
+}
+
+/// Match `🆔 id` or `⛔ id1,id2` at the start of `tokens`, attached or
+/// space-separated like dates. Returns the marker, the value and the tokens
+/// consumed.
+fn id_field<'a>(tokens: &[&'a str]) -> Option<(&'static str, &'a str, usize)> {
+    let token = tokens[0];
+    let marker = [ID, DEPENDS_ON]
+        .into_iter()
+        .find(|m| token.starts_with(m))?;
+    let attached = &token[marker.len()..];
+    if !attached.is_empty() {
+        return Some((marker, attached, 1));
+    }
+    tokens
+        .get(1)
+        .filter(|t| !is_marker(t))
+        .map(|t| (marker, *t, 2))
+}
+
+/// Match a date marker at the start of `tokens`, accepting both `📅 2025-01-01`
+/// and `📅2025-01-01`. Returns the marker, the date and the tokens consumed.
+fn date_field(tokens: &[&str]) -> Option<(&'static str, NaiveDate, usize)> {
+    let token = tokens[0];
+    let marker = DATE_MARKERS.iter().find(|m| token.starts_with(**m))?;
+    let attached = &token[marker.len()..];
+    if !attached.is_empty() {
+        return parse_date(attached).map(|d| (*marker, d, 1));
+    }
+    tokens
+        .get(1)
+        .and_then(|t| parse_date(t))
+        .map(|d| (*marker, d, 2))
+}
+
+/// Validate a line for `--strict` mode: every emoji in a task line must be a
+/// known marker and every date marker must carry an ISO date.
+pub fn strict_check(line: &str) -> Result<(), String> {
+    if Task::parse(line).is_none() {
+        return Ok(());
+    }
+
+    let tokens = line
+        .split_whitespace()
+        .map(strip_variation)
+        .collect::<Vec<_>>();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if token == RECURRENCE {
+            // Recurrence rules are free text up to the next marker
+            i += 1;
+            while i < tokens.len() && !is_marker(tokens[i]) {
+                i += 1;
+            }
+            continue;
+        }
+        if token.starts_with(ID) || token.starts_with(DEPENDS_ON) {
+            let (_, _, consumed) = id_field(&tokens[i..])
+                .ok_or_else(|| format!("'{}' is not followed by a task id", token))?;
+            i += consumed;
+            continue;
+        }
+        if DATE_MARKERS.iter().any(|m| token.starts_with(m)) {
+            let (_, _, consumed) = date_field(&tokens[i..])
+                .ok_or_else(|| format!("'{}' is not followed by a YYYY-MM-DD date", token))?;
+            i += consumed;
+            continue;
+        }
+        if Priority::from_emoji(token).is_none() && token.chars().any(is_emoji) {
+            return Err(format!("unknown metadata '{}'", token));
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    #[test]
+    fn parses_every_emoji_field() {
+        let task = Task::parse(
+            "- [ ] Ship release #work ⏫ 🔁 every week 🆔 abc123 ⛔ x1,y2 ➕ 2025-09-01 🛫 2025-09-02 ⏳ 2025-09-03 📅 2025-09-05",
+        )
+        .unwrap();
+        assert_eq!(task.status, Status::Pending);
+        assert_eq!(task.text, "Ship release #work");
+        assert_eq!(task.priority, Priority::High);
+        assert_eq!(task.recurrence.as_deref(), Some("every week"));
+        assert_eq!(task.id.as_deref(), Some("abc123"));
+        assert_eq!(task.depends_on, ["x1", "y2"]);
+        assert_eq!(task.created, Some(date("2025-09-01")));
+        assert_eq!(task.start, Some(date("2025-09-02")));
+        assert_eq!(task.scheduled, Some(date("2025-09-03")));
+        assert_eq!(task.due, Some(date("2025-09-05")));
+        assert_eq!(task.tags(), ["work"]);
+    }
+
+    #[test]
+    fn emoji_variation_selectors_are_ignored() {
+        let plain = Task::parse("- [ ] Call back ⏳ 2025-09-03").unwrap();
+        let with_selector = Task::parse("- [ ] Call back ⏳\u{fe0f} 2025-09-03").unwrap();
+        assert_eq!(plain, with_selector);
+    }
+
+    #[test]
+    fn round_trips_through_each_format() {
+        let line = "- [x] Write report [note:: sent] ⏫ ➕ 2025-09-01 📅 2025-09-05 ✅ 2025-09-04";
+        let task = Task::parse(line).unwrap();
+        assert_eq!(task.to_line(TaskFormat::Obsidian), line);
+        for format in [TaskFormat::Legacy, TaskFormat::Dataview] {
+            assert_eq!(Task::parse(&task.to_line(format)), Some(task.clone()));
+        }
+    }
+
+    #[test]
+    fn cancelled_text_loses_its_strikethrough() {
+        let task = Task::parse("- [-] ~~Old idea~~ ❌ 2025-09-04").unwrap();
+        assert_eq!(task.status, Status::Cancelled);
+        assert_eq!(task.text, "Old idea");
+        assert_eq!(task.cancelled, Some(date("2025-09-04")));
+    }
+
+    #[test]
+    fn malformed_lines_are_not_tasks() {
+        for line in [
+            "-[ ] No space",
+            "- [ ]",
+            "- [y] Odd box",
+            "1. [ ] Numbered",
+            "Just a note",
+        ] {
+            let task = Task::parse(line);
+            assert!(task.is_none_or(|t| t.text.is_empty()), "{}", line);
+        }
+        assert!(matches!(
+            classify("-[ ] No space"),
+            LineKind::Malformed { fixed: Some(_), .. }
+        ));
+        assert_eq!(classify("## Q4"), LineKind::Heading);
+        assert_eq!(classify("    a note"), LineKind::Note);
+    }
+
+    #[test]
+    fn invalid_dates_stay_in_the_text() {
+        let task = Task::parse("- [ ] Pay rent 📅 2025-02-30").unwrap();
+        assert_eq!(task.due, None);
+        assert!(task.text.contains("2025-02-30"));
+        assert_eq!(parse_date("2024-02-29"), Some(date("2024-02-29")));
+        assert_eq!(parse_date("2025-02-29"), None);
+        assert_eq!(parse_date("2025-9-5"), None);
+    }
+}
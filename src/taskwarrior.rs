@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::config::TaskFormat;
+use crate::json::{self, Json};
+use crate::recurrence;
+use crate::task::{self, Priority, Status, Task};
+
+/// Parse `task export` output: a JSON array, or one object per line as
+/// Taskwarrior 2.4 and older write it.
+fn parse(text: &str) -> Result<Vec<Json>, String> {
+    let mut tasks = Vec::new();
+    for value in json::values(text)? {
+        match value {
+            Json::Array(items) => tasks.extend(items),
+            object @ Json::Object(_) => tasks.push(object),
+            _ => return Err("invalid JSON: expected tasks".to_string()),
+        }
+    }
+    Ok(tasks)
+}
+
+/// Taskwarrior's UTC timestamps (`20251001T120000Z`) as local dates.
+fn date(task: &Json, key: &str) -> Option<NaiveDate> {
+    let stamp = NaiveDateTime::parse_from_str(task.str(key)?, "%Y%m%dT%H%M%SZ").ok()?;
+    Some(
+        Utc.from_utc_datetime(&stamp)
+            .with_timezone(&Local)
+            .date_naive(),
+    )
+}
+
+/// Taskwarrior recurrence periods as `task` rules, where there's one.
+fn rule(period: &str, today: NaiveDate) -> Option<String> {
+    let rule = match period {
+        "daily" | "day" => "every day".to_string(),
+        "weekdays" => "every weekday".to_string(),
+        "weekly" | "week" => "every week".to_string(),
+        "biweekly" | "fortnight" => "every 2 weeks".to_string(),
+        "monthly" | "month" => "every month".to_string(),
+        "quarterly" => "every 3 months".to_string(),
+        "yearly" | "annual" | "year" => "every year".to_string(),
+        _ => {
+            let split = period.find(|c: char| !c.is_ascii_digit())?;
+            let (n, unit) = period.split_at(split);
+            let unit = match unit {
+                "d" | "day" | "days" => "days",
+                "w" | "wk" | "wks" | "week" | "weeks" => "weeks",
+                "mo" | "mos" | "month" | "months" => "months",
+                "y" | "yr" | "yrs" | "year" | "years" => "years",
+                _ => return None,
+            };
+            format!("every {} {}", n, unit)
+        }
+    };
+    recurrence::next_occurrence(&rule, today).map(|_| rule)
+}
+
+/// Short uuid, as Taskwarrior shows them, for `🆔` and `⛔`.
+fn short(uuid: &str) -> String {
+    uuid.chars().take(8).collect()
+}
+
+/// The result of reading a Taskwarrior export.
+pub struct Imported {
+    /// Task lines, each followed by its annotations as notes
+    pub lines: Vec<String>,
+    pub tasks: usize,
+    /// Tasks skipped because their uuid is already in the task file
+    pub already: usize,
+}
+
+/// Convert `task export` JSON into task lines, oldest first. The uuid is
+/// kept in a `[uuid:: …]` field so a second import skips what's already
+/// there. Projects become nested tags (`Home.Garden` → `#home/garden`),
+/// `wait` becomes the start date, completed and deleted tasks keep their
+/// end date as done or cancelled, and annotations become notes. Recurring
+/// tasks are imported as their instances, with the recurrence on the
+/// latest pending one so completing it carries on the series.
+pub fn import(
+    text: &str,
+    existing: &[String],
+    today: NaiveDate,
+    format: TaskFormat,
+) -> Result<Imported, String> {
+    let known = existing
+        .iter()
+        .filter_map(|l| Task::parse(l)?.field("uuid").map(String::from))
+        .collect::<HashSet<_>>();
+    let mut exported = parse(text)?
+        .into_iter()
+        .filter(|t| t.str("status") != Some("recurring"))
+        .filter(|t| t.str("uuid").is_some() && t.str("description").is_some())
+        .collect::<Vec<_>>();
+    exported.sort_by_key(|t| t.str("entry").unwrap_or_default().to_string());
+
+    let depended_on = exported
+        .iter()
+        .flat_map(|t| t.strings("depends"))
+        .map(|u| short(&u))
+        .collect::<HashSet<_>>();
+    // The pending instance of each recurring series that carries it on
+    let mut series: HashMap<&str, (usize, Option<NaiveDate>)> = HashMap::new();
+    for (i, t) in exported.iter().enumerate() {
+        if let (Some(parent), Some("pending" | "waiting")) = (t.str("parent"), t.str("status")) {
+            let due = date(t, "due");
+            let latest = series.entry(parent).or_insert((i, due));
+            if due >= latest.1 {
+                *latest = (i, due);
+            }
+        }
+    }
+    let carries_on = series.values().map(|(i, _)| *i).collect::<HashSet<_>>();
+
+    let mut imported = Imported {
+        lines: Vec::new(),
+        tasks: 0,
+        already: 0,
+    };
+    for (i, t) in exported.iter().enumerate() {
+        let uuid = t.str("uuid").unwrap_or_default();
+        if known.contains(uuid) {
+            imported.already += 1;
+            continue;
+        }
+
+        let mut text = t.str("description").unwrap_or_default().replace('\n', " ");
+        if let Some(project) = t.str("project") {
+            let tag = project.to_lowercase().replace(' ', "-").replace('.', "/");
+            text = format!("{} #{}", text, tag);
+        }
+        for tag in t.strings("tags") {
+            text = format!("{} #{}", text, tag);
+        }
+
+        let mut task = Task::new(text, date(t, "due"), NaiveDate::MIN);
+        task.created = date(t, "entry");
+        task.scheduled = date(t, "scheduled");
+        task.start = date(t, "wait");
+        task.priority = match t.str("priority") {
+            Some("H") => Priority::High,
+            Some("M") => Priority::Medium,
+            Some("L") => Priority::Low,
+            _ => Priority::Normal,
+        };
+        match t.str("status") {
+            Some("completed") => {
+                task.status = Status::Done;
+                task.done = date(t, "end").or(date(t, "modified"));
+            }
+            Some("deleted") => {
+                task.status = Status::Cancelled;
+                task.cancelled = date(t, "end").or(date(t, "modified"));
+            }
+            _ => {}
+        }
+        if carries_on.contains(&i) {
+            task.recurrence = t.str("recur").and_then(|p| rule(p, today));
+        }
+        if depended_on.contains(&short(uuid)) {
+            task.id = Some(short(uuid));
+        }
+        task.depends_on = t.strings("depends").iter().map(|u| short(u)).collect();
+        task.set_field("uuid", uuid);
+
+        imported.lines.push(task.to_line(format));
+        if let Some(Json::Array(annotations)) = t.get("annotations") {
+            for annotation in annotations {
+                if let Some(note) = annotation.str("description") {
+                    imported.lines.extend(task::note_lines(note));
+                }
+            }
+        }
+        imported.tasks += 1;
+    }
+    Ok(imported)
+}
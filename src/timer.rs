@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::storage;
+use crate::task::Task;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// One work interval from `time_log`. The task is identified by its
+/// creation date and text, which also carries its tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub start: NaiveDateTime,
+    /// `None` while the timer is running
+    pub end: Option<NaiveDateTime>,
+    pub created: Option<NaiveDate>,
+    pub text: String,
+}
+
+impl Entry {
+    fn is_for(&self, task: &Task) -> bool {
+        self.created == task.created && self.text == task.text
+    }
+
+    pub fn duration(&self, now: NaiveDateTime) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+
+    fn tags(&self) -> Vec<String> {
+        let task = Task::new(self.text.clone(), None, NaiveDate::MIN);
+        task.tags().into_iter().map(String::from).collect()
+    }
+}
+
+/// `1h 30m`, or just minutes under an hour.
+pub fn format_duration(d: Duration) -> String {
+    let minutes = d.num_minutes().max(0);
+    match minutes / 60 {
+        0 => format!("{}m", minutes),
+        hours => format!("{}h {:02}m", hours, minutes % 60),
+    }
+}
+
+pub fn load(task_dir: &Path) -> Vec<Entry> {
+    storage::read_text(&task_dir.join("time_log"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\t');
+            let start = NaiveDateTime::parse_from_str(parts.next()?, TIME_FORMAT).ok()?;
+            let end = match parts.next()? {
+                "-" => None,
+                end => Some(NaiveDateTime::parse_from_str(end, TIME_FORMAT).ok()?),
+            };
+            let created = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok();
+            let text = parts.next()?.to_string();
+            Some(Entry {
+                start,
+                end,
+                created,
+                text,
+            })
+        })
+        .collect()
+}
+
+fn save(task_dir: &Path, entries: &[Entry]) -> Result<(), String> {
+    let contents = entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{}\t{}\t{}\t{}\n",
+                e.start.format(TIME_FORMAT),
+                e.end
+                    .map_or("-".to_string(), |t| t.format(TIME_FORMAT).to_string()),
+                e.created.map_or("-".to_string(), |d| d.to_string()),
+                e.text
+            )
+        })
+        .collect::<String>();
+    storage::write_text(&task_dir.join("time_log"), &contents)
+        .map_err(|e| format!("error saving time log: {}", e))
+}
+
+/// The running timer, if any.
+pub fn active(task_dir: &Path) -> Option<Entry> {
+    load(task_dir).into_iter().find(|e| e.end.is_none())
+}
+
+/// Stop the running timer, returning the finished interval.
+pub fn stop(task_dir: &Path, now: NaiveDateTime) -> Result<Option<Entry>, String> {
+    let mut entries = load(task_dir);
+    let Some(running) = entries.iter_mut().find(|e| e.end.is_none()) else {
+        return Ok(None);
+    };
+    running.end = Some(now);
+    let stopped = running.clone();
+    save(task_dir, &entries)?;
+    Ok(Some(stopped))
+}
+
+/// Start timing `task`. Only one timer runs at a time, so any other running
+/// timer is stopped first and returned.
+pub fn start(task_dir: &Path, task: &Task, now: NaiveDateTime) -> Result<Option<Entry>, String> {
+    if active(task_dir).is_some_and(|e| e.is_for(task)) {
+        return Err(format!("already timing '{}'", task.text));
+    }
+    let stopped = stop(task_dir, now)?;
+    let mut entries = load(task_dir);
+    entries.push(Entry {
+        start: now,
+        end: None,
+        created: task.created,
+        text: task.text.clone(),
+    });
+    save(task_dir, &entries)?;
+    Ok(stopped)
+}
+
+/// Stop the running timer if it belongs to `task`, e.g. when the task is
+/// completed.
+pub fn stop_for(task_dir: &Path, task: &Task, now: NaiveDateTime) -> Result<Option<Entry>, String> {
+    if !active(task_dir).is_some_and(|e| e.is_for(task)) {
+        return Ok(None);
+    }
+    stop(task_dir, now)
+}
+
+/// Time logged against `task` in `entries`.
+pub fn tracked(entries: &[Entry], task: &Task, now: NaiveDateTime) -> Duration {
+    entries
+        .iter()
+        .filter(|e| e.is_for(task))
+        .fold(Duration::zero(), |sum, e| sum + e.duration(now))
+}
+
+/// Print time spent per task and per tag for intervals started on or after
+/// `since`, longest first.
+pub fn summary(entries: &[Entry], since: Option<NaiveDate>, now: NaiveDateTime) {
+    let entries = entries
+        .iter()
+        .filter(|e| since.is_none_or(|d| e.start.date() >= d))
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        println!("No time tracked in this period.");
+        return;
+    }
+
+    let mut by_task: BTreeMap<&str, Duration> = BTreeMap::new();
+    let mut by_tag: BTreeMap<String, Duration> = BTreeMap::new();
+    for entry in &entries {
+        let spent = entry.duration(now);
+        *by_task.entry(&entry.text).or_insert(Duration::zero()) += spent;
+        for tag in entry.tags() {
+            *by_tag.entry(tag).or_insert(Duration::zero()) += spent;
+        }
+    }
+
+    let mut by_task = by_task.into_iter().collect::<Vec<_>>();
+    by_task.sort_by_key(|(_, d)| std::cmp::Reverse(*d));
+    println!("By task:");
+    for (text, spent) in &by_task {
+        println!("  {:>8}  {}", format_duration(*spent), text);
+    }
+
+    if !by_tag.is_empty() {
+        let mut by_tag = by_tag.into_iter().collect::<Vec<_>>();
+        by_tag.sort_by_key(|(_, d)| std::cmp::Reverse(*d));
+        println!("By tag:");
+        for (tag, spent) in &by_tag {
+            println!("  {:>8}  #{}", format_duration(*spent), tag);
+        }
+    }
+
+    let total = by_task
+        .iter()
+        .fold(Duration::zero(), |sum, (_, spent)| sum + *spent);
+    println!("Total: {}", format_duration(total));
+}
@@ -0,0 +1,260 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::config::TaskFormat;
+use crate::recurrence;
+use crate::task::{self, Priority, Status, Task};
+
+/// Columns of Todoist's CSV template format, as written by its project
+/// export and read by its project import.
+const HEADER: &str =
+    "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE";
+
+/// Split CSV text into records, honouring quoted fields with embedded
+/// commas, quotes and newlines.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Rewrite word-initial marks from one tag style to the other: Todoist's
+/// `@label` and the task file's `#tag`.
+fn retag(text: &str, from: char, to: char) -> String {
+    text.split(' ')
+        .map(|w| match w.strip_prefix(from) {
+            Some(label) if label.starts_with(|c: char| c.is_alphanumeric()) => {
+                format!("{}{}", to, label)
+            }
+            _ => w.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Todoist's p1 (1) is the most urgent and p4 (4) means no priority.
+fn priority(value: &str) -> Priority {
+    match value.trim() {
+        "1" => Priority::Highest,
+        "2" => Priority::High,
+        "3" => Priority::Medium,
+        _ => Priority::Normal,
+    }
+}
+
+fn todoist_priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::Highest => 1,
+        Priority::High => 2,
+        Priority::Medium => 3,
+        _ => 4,
+    }
+}
+
+/// Read a Todoist date string: ISO dates (with or without a time),
+/// `today`/`tomorrow`, and English month-day forms such as `Oct 20 2025` or
+/// `20 Oct`, which fall in the coming year when the year is left out.
+fn parse_date(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let value = value.trim();
+    match value.to_lowercase().as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+    if let Some(date) = value.get(..10).and_then(task::parse_date) {
+        return Some(date);
+    }
+    let value = value.replace(',', "");
+    for format in ["%b %d %Y", "%B %d %Y", "%d %b %Y", "%d %B %Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(&value, format) {
+            return Some(date);
+        }
+    }
+    let this_year = format!("{} {}", value, today.year());
+    ["%b %d %Y", "%B %d %Y", "%d %b %Y", "%d %B %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(&this_year, format).ok())
+        .map(|date| {
+            if date < today {
+                date.with_year(today.year() + 1).unwrap_or(date)
+            } else {
+                date
+            }
+        })
+}
+
+/// The result of reading a Todoist export.
+pub struct Imported {
+    /// Task lines, each followed by its notes
+    pub lines: Vec<String>,
+    pub tasks: usize,
+    /// Dates that couldn't be read, kept as a `[todoist_date:: …]` field
+    pub unparsed_dates: usize,
+}
+
+/// Convert a Todoist CSV export into task lines. Labels become tags,
+/// `project` (if any) is added as a tag, sections become a `[section:: …]`
+/// field, and descriptions and comments become notes. Recurring dates that
+/// `task` understands (`every week`) are kept as recurrences.
+pub fn import(
+    text: &str,
+    project: Option<&str>,
+    today: NaiveDate,
+    format: TaskFormat,
+) -> Result<Imported, String> {
+    let mut records = parse_csv(text).into_iter();
+    let header = records
+        .next()
+        .ok_or("the file is empty")?
+        .iter()
+        .map(|h| h.trim().to_uppercase())
+        .collect::<Vec<_>>();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(kind), Some(content)) = (column("TYPE"), column("CONTENT")) else {
+        return Err("not a Todoist CSV export (expected TYPE and CONTENT columns)".to_string());
+    };
+    let description = column("DESCRIPTION");
+    let priority_col = column("PRIORITY");
+    let date_col = column("DATE");
+    let deadline_col = column("DEADLINE");
+
+    let project_tag = project
+        .map(|p| {
+            p.trim()
+                .to_lowercase()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .filter(|p| !p.is_empty());
+
+    let mut imported = Imported {
+        lines: Vec::new(),
+        tasks: 0,
+        unparsed_dates: 0,
+    };
+    let mut section = None;
+    for record in records {
+        let get = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .map(|v| v.trim())
+                .unwrap_or_default()
+        };
+        let text = get(Some(content));
+        match get(Some(kind)).to_lowercase().as_str() {
+            "section" => section = Some(text.to_string()).filter(|s| !s.is_empty()),
+            "note" if !imported.lines.is_empty() => {
+                imported.lines.extend(task::note_lines(text));
+            }
+            "task" if !text.is_empty() => {
+                let mut text = retag(&text.replace('\n', " "), '@', '#');
+                if let Some(tag) = &project_tag {
+                    text = format!("{} #{}", text, tag);
+                }
+                let mut task = Task::new(text, None, today);
+                task.priority = priority(get(priority_col));
+
+                let date = get(date_col);
+                let deadline = get(deadline_col);
+                if date.to_lowercase().starts_with("every")
+                    && recurrence::next_occurrence(date, today).is_some()
+                {
+                    task.recurrence = Some(date.to_lowercase());
+                    task.due = Some(today);
+                } else if !date.is_empty() {
+                    match parse_date(date, today) {
+                        Some(d) => task.due = Some(d),
+                        None => {
+                            task.set_field("todoist_date", date);
+                            imported.unparsed_dates += 1;
+                        }
+                    }
+                }
+                if let Some(deadline) = parse_date(deadline, today) {
+                    task.scheduled = task.due.filter(|_| task.recurrence.is_none());
+                    task.due = Some(deadline);
+                }
+                if let Some(section) = &section {
+                    task.set_field("section", section);
+                }
+
+                imported.lines.push(task.to_line(format));
+                imported.lines.extend(task::note_lines(get(description)));
+                imported.tasks += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(imported)
+}
+
+/// Write pending tasks as a Todoist CSV template, with notes as the
+/// description, for Todoist's "Import from template". Todoist doesn't import
+/// completed tasks, so those are left out. Returns the CSV and task count.
+pub fn export(lines: &[String]) -> (String, usize) {
+    let mut csv = format!("{}\n", HEADER);
+    let mut count = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let Some(task) = Task::parse(line).filter(|t| t.status == Status::Pending) else {
+            continue;
+        };
+        let date = match (&task.recurrence, task.due) {
+            (Some(rule), Some(due)) => format!("{} starting {}", rule, due),
+            (Some(rule), None) => rule.clone(),
+            (None, Some(due)) => due.to_string(),
+            (None, None) => String::new(),
+        };
+        let row = [
+            "task".to_string(),
+            retag(&task.text, '#', '@'),
+            task::notes(lines, i).join("\n"),
+            todoist_priority(task.priority).to_string(),
+            "1".to_string(),
+            String::new(),
+            String::new(),
+            date,
+            "en".to_string(),
+            String::new(),
+        ];
+        csv.push_str(
+            &row.iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+        count += 1;
+    }
+    (csv, count)
+}
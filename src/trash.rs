@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::config::TaskFormat;
+use crate::storage;
+use crate::task::{self, Status, Task};
+
+/// File in the task directory that deleted tasks are moved to.
+const FILE: &str = "trash.md";
+
+pub fn path(task_dir: &Path) -> PathBuf {
+    task_dir.join(FILE)
+}
+
+/// Lines of the trash file; empty when nothing has been deleted yet.
+pub fn load(task_dir: &Path) -> Vec<String> {
+    let path = path(task_dir);
+    if path.exists() {
+        storage::read_lines(&path)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Line indices of the tasks in the trash, most recently deleted first.
+pub fn entries(trash: &[String]) -> Vec<usize> {
+    let mut entries = (0..trash.len())
+        .filter(|&i| Task::parse(&trash[i]).is_some())
+        .collect::<Vec<_>>();
+    entries.reverse();
+    entries
+}
+
+/// Move the tasks on `line_indices` of `lines`, with their notes, to the
+/// end of `trash`, stamped with the day they were deleted.
+pub fn put(
+    lines: &mut Vec<String>,
+    trash: &mut Vec<String>,
+    line_indices: &[usize],
+    today: NaiveDate,
+    format: TaskFormat,
+) {
+    let mut line_indices = line_indices.to_vec();
+    line_indices.sort_unstable();
+    let mut removed = Vec::new();
+    for &idx in line_indices.iter().rev() {
+        let Some(mut task) = Task::parse(&lines[idx]) else {
+            continue;
+        };
+        let block = 1 + task::notes(lines, idx).len();
+        let mut taken = lines.drain(idx..idx + block).collect::<Vec<_>>();
+        task.set_field("deleted", &today.to_string());
+        taken[0] = task.to_line(format);
+        removed.push(taken);
+    }
+    // Keep the file order of the deleted tasks
+    trash.extend(removed.into_iter().rev().flatten());
+}
+
+/// Take the task on `idx` of `trash`, with its notes, out of the trash.
+pub fn take(trash: &mut Vec<String>, idx: usize) -> Vec<String> {
+    let block = 1 + task::notes(trash, idx).len();
+    trash.drain(idx..idx + block).collect()
+}
+
+/// Make a deleted, cancelled or completed task pending again, dropping what
+/// marked it as removed or finished.
+pub fn reopen(task: &mut Task) {
+    task.status = Status::Pending;
+    task.done = None;
+    task.cancelled = None;
+    task.remove_field("deleted");
+    task.remove_field("reason");
+    task.remove_field("note");
+}
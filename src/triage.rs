@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+/// What to do with the ticked tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Done,
+    Cancel,
+    Postpone,
+}
+
+// Rows of the list shown at once; longer lists scroll with the cursor
+const WINDOW: usize = 15;
+
+/// The terminal in raw mode, so single key presses can be read. The
+/// previous settings are put back when dropped.
+pub struct RawMode {
+    saved: String,
+}
+
+pub fn stty(args: &[&str]) -> Result<String, String> {
+    let tty = File::open("/dev/tty").map_err(|e| format!("could not open the terminal: {}", e))?;
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::from(tty))
+        .output()
+        .map_err(|e| format!("could not run stty: {}", e))?;
+    if !output.status.success() {
+        return Err("stty could not change the terminal settings".to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl RawMode {
+    pub fn enter() -> Result<RawMode, String> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+enum Key {
+    Up,
+    Down,
+    Toggle,
+    All,
+    Char(char),
+    Quit,
+}
+
+fn read_key(input: &mut impl Read) -> Key {
+    let mut byte = [0u8; 1];
+    if input.read(&mut byte).unwrap_or(0) == 0 {
+        return Key::Quit;
+    }
+    match byte[0] {
+        // Arrow keys arrive as ESC [ A/B; ESC followed by anything else quits
+        0x1b => {
+            let mut next = || {
+                let mut b = [0u8; 1];
+                input.read(&mut b).ok().filter(|n| *n == 1).map(|_| b[0])
+            };
+            match (next(), next()) {
+                (Some(b'['), Some(b'A')) => Key::Up,
+                (Some(b'['), Some(b'B')) => Key::Down,
+                _ => Key::Quit,
+            }
+        }
+        b' ' => Key::Toggle,
+        b'k' => Key::Up,
+        b'j' => Key::Down,
+        b'a' => Key::All,
+        // Ctrl-C and Ctrl-D
+        0x03 | 0x04 | b'q' => Key::Quit,
+        b => Key::Char(b as char),
+    }
+}
+
+fn draw(out: &mut impl Write, items: &[String], ticked: &[bool], cursor: usize, first: usize) {
+    let _ = write!(out, "\x1b[J");
+    for (i, item) in items.iter().enumerate().skip(first).take(WINDOW) {
+        let pointer = if i == cursor { ">" } else { " " };
+        let check = if ticked[i] { "[x]" } else { "[ ]" };
+        let _ = write!(out, "{} {} {}\r\n", pointer, check, item);
+    }
+    let count = ticked.iter().filter(|t| **t).count();
+    let _ = write!(
+        out,
+        "{} selected  ↑/↓ move  space tick  a all  d done  c cancel  p postpone  q quit",
+        count
+    );
+    let rows = items.len().saturating_sub(first).min(WINDOW);
+    // Back to the top of the list for the next redraw
+    let _ = write!(out, "\r\x1b[{}A", rows);
+    let _ = out.flush();
+}
+
+/// Show `items` as a checklist on the terminal and let the user tick several
+/// and pick an action for them. Returns the action with the indices of the
+/// ticked items, or `None` if they quit or ticked nothing.
+pub fn pick(items: &[String]) -> Result<Option<(Action, Vec<usize>)>, String> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err("triage needs a terminal".to_string());
+    }
+    if items.is_empty() {
+        return Ok(None);
+    }
+
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    let mut ticked = vec![false; items.len()];
+    let (mut cursor, mut first) = (0, 0);
+    let action = {
+        let _raw = RawMode::enter()?;
+        loop {
+            draw(&mut stdout, items, &ticked, cursor, first);
+            match read_key(&mut stdin) {
+                Key::Up => cursor = cursor.saturating_sub(1),
+                Key::Down => cursor = (cursor + 1).min(items.len() - 1),
+                Key::Toggle => ticked[cursor] = !ticked[cursor],
+                Key::All => {
+                    let all = ticked.iter().all(|t| *t);
+                    ticked.iter_mut().for_each(|t| *t = !all);
+                }
+                Key::Char('d') => break Some(Action::Done),
+                Key::Char('c') => break Some(Action::Cancel),
+                Key::Char('p') => break Some(Action::Postpone),
+                Key::Char(_) => {}
+                Key::Quit => break None,
+            }
+            if cursor < first {
+                first = cursor;
+            } else if cursor >= first + WINDOW {
+                first = cursor + 1 - WINDOW;
+            }
+        }
+    };
+    // Leave the final state of the list on screen
+    let _ = write!(stdout, "\x1b[{}B\r\n", items.len().min(WINDOW));
+    let _ = stdout.flush();
+
+    let selected = (0..items.len()).filter(|&i| ticked[i]).collect::<Vec<_>>();
+    Ok(action
+        .filter(|_| !selected.is_empty())
+        .map(|a| (a, selected)))
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::task::{Priority, Task};
+
+/// `[urgency]` config: how much each factor adds to a pending task's
+/// urgency, as in Taskwarrior, with extra weight for particular tags such
+/// as `[urgency.tags] next = 15.0`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrgencyConfig {
+    /// For a task overdue by a week or more; less the further off it is due
+    pub due: f64,
+    /// For highest priority; high and medium get less, low and lowest
+    /// take it away
+    pub priority: f64,
+    /// For a task `age_days` old or older
+    pub age: f64,
+    pub age_days: i64,
+    /// For a task with three or more tags; fewer tags get a little less
+    pub tagged: f64,
+    pub tags: HashMap<String, f64>,
+}
+
+impl Default for UrgencyConfig {
+    fn default() -> Self {
+        UrgencyConfig {
+            due: 12.0,
+            priority: 6.0,
+            age: 2.0,
+            age_days: 365,
+            tagged: 1.0,
+            tags: HashMap::new(),
+        }
+    }
+}
+
+struct Urgency {
+    config: UrgencyConfig,
+    today: NaiveDate,
+}
+
+static URGENCY: OnceLock<Urgency> = OnceLock::new();
+
+pub fn init(config: &UrgencyConfig, today: NaiveDate) -> Result<(), String> {
+    if config.age_days <= 0 {
+        return Err("age_days in [urgency] must be at least 1".to_string());
+    }
+    let mut config = config.clone();
+    config.tags = config
+        .tags
+        .into_iter()
+        .map(|(tag, weight)| (tag.trim_start_matches('#').to_lowercase(), weight))
+        .collect();
+    let _ = URGENCY.set(Urgency { config, today });
+    Ok(())
+}
+
+/// How close `due` is, from 0.2 two weeks or more ahead to 1.0 a week or
+/// more overdue, rising evenly in between.
+fn due_factor(due: NaiveDate, today: NaiveDate) -> f64 {
+    match (today - due).num_days() {
+        d if d >= 7 => 1.0,
+        d if d >= -14 => (d + 14) as f64 * 0.8 / 21.0 + 0.2,
+        _ => 0.2,
+    }
+}
+
+fn priority_factor(priority: Priority) -> f64 {
+    match priority {
+        Priority::Highest => 1.0,
+        Priority::High => 0.65,
+        Priority::Medium => 0.3,
+        Priority::Normal => 0.0,
+        Priority::Low => -0.3,
+        Priority::Lowest => -0.65,
+    }
+}
+
+/// How urgent `task` is: the sum of its weighted due date, priority, age
+/// and tags. Higher is more urgent; 0 when there's nothing to go on.
+pub fn score(task: &Task) -> f64 {
+    let Some(urgency) = URGENCY.get() else {
+        return 0.0;
+    };
+    let (config, today) = (&urgency.config, urgency.today);
+    let mut score = config.priority * priority_factor(task.priority);
+    if let Some(due) = task.due {
+        score += config.due * due_factor(due, today);
+    }
+    if let Some(created) = task.created {
+        let age = (today - created).num_days().clamp(0, config.age_days);
+        score += config.age * age as f64 / config.age_days as f64;
+    }
+    let tags = task.tags();
+    score += config.tagged
+        * match tags.len() {
+            0 => 0.0,
+            1 => 0.8,
+            2 => 0.9,
+            _ => 1.0,
+        };
+    for tag in tags {
+        score += config.tags.get(&tag.to_lowercase()).copied().unwrap_or(0.0);
+    }
+    score
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDate;
+
+use crate::config::TaskFormat;
+use crate::task::{Status, Task};
+
+/// Park `task` as delegated until `until`, when it goes back to pending.
+pub fn wait(task: &mut Task, until: NaiveDate) {
+    task.status = Status::Waiting;
+    task.set_field("wake", &until.to_string());
+}
+
+/// Whether a waiting task's wake-up date has arrived. One without a date
+/// stays waiting until it's edited by hand.
+pub fn is_due(task: &Task, today: NaiveDate) -> bool {
+    task.status == Status::Waiting && task.wakes().is_some_and(|d| d <= today)
+}
+
+/// Return waiting tasks whose wake-up date has arrived to pending, giving
+/// the text of each.
+pub fn wake(lines: &mut [String], today: NaiveDate, format: TaskFormat) -> Vec<String> {
+    let mut woken = Vec::new();
+    for line in lines.iter_mut() {
+        // Most lines aren't waiting tasks; skip parsing them
+        if !line.contains("[>]") {
+            continue;
+        }
+        let Some(mut task) = Task::parse(line).filter(|t| is_due(t, today)) else {
+            continue;
+        };
+        task.status = Status::Pending;
+        task.remove_field("wake");
+        *line = task.to_line(format);
+        woken.push(task.text);
+    }
+    woken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::parse_date;
+
+    #[test]
+    fn wakes_only_tasks_whose_date_has_come() {
+        let mut lines = vec![
+            "- [>] Quote [wake:: 2025-09-15]".to_string(),
+            "- [>] Later [wake:: 2025-09-16]".to_string(),
+            "- [>] Undated".to_string(),
+        ];
+        let today = parse_date("2025-09-15").unwrap();
+        let woken = wake(&mut lines, today, TaskFormat::Obsidian);
+        assert_eq!(woken, ["Quote"]);
+        assert_eq!(lines[0], "- [ ] Quote");
+        assert_eq!(lines[1], "- [>] Later [wake:: 2025-09-16]");
+        assert_eq!(lines[2], "- [>] Undated");
+    }
+}
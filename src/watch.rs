@@ -0,0 +1,42 @@
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chrono::NaiveDate;
+
+use crate::dates;
+
+/// What a change to the task file looks like from outside: its modification
+/// time and size. Editors that save by renaming a new file over the old one
+/// change both.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Call `render` with the current day now, whenever `path` changes and when
+/// the day rolls over, checking every `interval`. On a terminal the screen is
+/// cleared first so the output stays in place. Runs until interrupted.
+///
+/// This polls rather than subscribing to file system events: the day has to
+/// be checked on a timer anyway, a `stat` every interval costs nothing, and
+/// polling also sees changes that events miss on network shares and in
+/// folders a sync client replaces wholesale.
+pub fn run(path: &Path, interval: Duration, mut render: impl FnMut(NaiveDate)) {
+    let interactive = std::io::stdout().is_terminal();
+    let mut seen = None;
+    loop {
+        let now = (stamp(path), dates::today());
+        if seen.as_ref() != Some(&now) {
+            if interactive {
+                print!("\x1b[2J\x1b[H");
+            }
+            render(now.1);
+            let _ = std::io::stdout().flush();
+            seen = Some(now);
+        }
+        thread::sleep(interval);
+    }
+}
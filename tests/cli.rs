@@ -0,0 +1,372 @@
+//! End-to-end tests: the `task` binary run against a task file in a
+//! temporary directory, with today pinned to 2025-09-15.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use assert_cmd::Command;
+use tempfile::TempDir;
+
+/// A home directory of its own, so no test reads the user's config, cache
+/// or task file, and a task file in it.
+struct Sandbox {
+    dir: TempDir,
+    /// Extra environment for every run
+    env: Vec<(&'static str, PathBuf)>,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        Sandbox {
+            dir: TempDir::new().unwrap(),
+            env: Vec::new(),
+        }
+    }
+
+    /// A sandbox whose task file starts out as `lines`.
+    fn with(lines: &[&str]) -> Self {
+        let sandbox = Sandbox::new();
+        let mut text = lines.join("\n");
+        text.push('\n');
+        fs::write(sandbox.file(), text).unwrap();
+        sandbox
+    }
+
+    fn file(&self) -> PathBuf {
+        self.dir.path().join("work_log.md")
+    }
+
+    fn lines(&self) -> Vec<String> {
+        fs::read_to_string(self.file())
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn task(&self, args: &[&str]) -> Command {
+        let home = self.dir.path().join("home");
+        let mut cmd = Command::cargo_bin("task").unwrap();
+        cmd.current_dir(self.dir.path())
+            .env_clear()
+            .env("PATH", std::env::var("PATH").unwrap_or_default())
+            .env("HOME", &home)
+            .env("XDG_DATA_HOME", home.join("data"))
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_CACHE_HOME", home.join("cache"))
+            .env("TASK_AS_OF", "2025-09-15")
+            .env("NO_COLOR", "1")
+            .envs(self.env.iter().cloned())
+            .arg("--file")
+            .arg(self.file())
+            .arg("--no-pager")
+            .args(args)
+            .write_stdin("");
+        cmd
+    }
+
+    /// Run `task` with `args`, which must succeed, returning its stdout.
+    fn run(&self, args: &[&str]) -> String {
+        let output = self.task(args).assert().success().get_output().clone();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Run `task` with `args`, which must fail with `code`, returning its
+    /// stderr.
+    fn fail(&self, args: &[&str], code: i32) -> String {
+        let output = self.task(args).assert().code(code).get_output().clone();
+        String::from_utf8(output.stderr).unwrap()
+    }
+}
+
+#[test]
+fn add_then_done_round_trip() {
+    let sandbox = Sandbox::new();
+    sandbox.run(&["add", "Buy milk"]);
+    assert_eq!(
+        sandbox.lines(),
+        ["- [ ] Buy milk ➕ 2025-09-15 📅 2025-09-15"]
+    );
+    assert!(sandbox.run(&["pending"]).contains("1 - [ ] Buy milk"));
+
+    sandbox.run(&["done", "1"]);
+    assert_eq!(
+        sandbox.lines(),
+        ["- [x] Buy milk ➕ 2025-09-15 📅 2025-09-15 ✅ 2025-09-15"]
+    );
+    assert!(!sandbox.run(&["pending"]).contains("Buy milk"));
+}
+
+#[test]
+fn adding_takes_no_backup_but_changing_a_task_does() {
+    let sandbox = Sandbox::with(&["- [ ] Existing"]);
+    // Adding only appends, so there is nothing to back up
+    sandbox.run(&["add", "Buy milk"]);
+    assert!(sandbox.run(&["backup", "list"]).contains("No backups"));
+
+    sandbox.run(&["done", "1"]);
+    let list = sandbox.run(&["backup", "list"]);
+    assert!(list.contains("2 task(s)"), "{}", list);
+}
+
+#[test]
+fn hooks_run_for_commands_that_lock_only_to_write() {
+    let sandbox = Sandbox::with(&["- [ ] Write report"]);
+    let hooks = sandbox.dir.path().join("home/data/task/hooks");
+    fs::create_dir_all(&hooks).unwrap();
+    for name in ["pre-capture", "pre-pomo"] {
+        let hook = hooks.join(name);
+        fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let refused = sandbox.fail(&["capture", "call the dentist tomorrow"], 1);
+    assert!(refused.contains("pre-capture hook refused"), "{}", refused);
+    let refused = sandbox.fail(&["pomo", "1", "--work", "0", "--break", "0"], 1);
+    assert!(refused.contains("pre-pomo hook refused"), "{}", refused);
+    assert_eq!(sandbox.lines(), ["- [ ] Write report"]);
+}
+
+#[test]
+fn cancel_keeps_the_reason_and_strikes_the_text() {
+    let sandbox = Sandbox::with(&["- [ ] Old plan 📅 2025-09-20"]);
+    sandbox.run(&["cancel", "1", "--reason", "Not needed"]);
+    assert_eq!(
+        sandbox.lines(),
+        ["- [-] ~~Old plan~~ [reason:: Not needed] 📅 2025-09-20 ❌ 2025-09-15"]
+    );
+
+    // Reopening undoes all of it
+    sandbox.run(&["reopen", "L1"]);
+    assert_eq!(sandbox.lines(), ["- [ ] Old plan 📅 2025-09-20"]);
+}
+
+#[test]
+fn done_on_a_recurring_task_adds_the_next_one() {
+    let sandbox = Sandbox::new();
+    sandbox.run(&["add", "Water plants", "--recur", "every week"]);
+    let out = sandbox.run(&["done", "1"]);
+    assert!(out.contains("Next occurrence due 📅 2025-09-22"), "{}", out);
+    let lines = sandbox.lines();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().any(|l| l.starts_with("- [x] Water plants")));
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.starts_with("- [ ] Water plants") && l.ends_with("📅 2025-09-22"))
+    );
+}
+
+#[test]
+fn a_missing_task_number_changes_nothing() {
+    let sandbox = Sandbox::with(&["- [ ] Only task 📅 2025-09-20"]);
+    let err = sandbox.fail(&["done", "2"], 2);
+    assert!(err.contains("Task number 2 out of range"), "{}", err);
+    assert_eq!(sandbox.lines(), ["- [ ] Only task 📅 2025-09-20"]);
+}
+
+#[test]
+fn leap_days_are_accepted_only_in_leap_years() {
+    let sandbox = Sandbox::new();
+    sandbox.run(&["add", "2028-02-29", "Leap"]);
+    let err = sandbox.fail(&["add", "2027-02-29", "Not leap"], 1);
+    assert!(err.contains("Invalid date '2027-02-29'"), "{}", err);
+    assert_eq!(sandbox.lines(), ["- [ ] Leap ➕ 2025-09-15 📅 2028-02-29"]);
+}
+
+#[test]
+fn postponing_crosses_month_and_year_ends() {
+    let sandbox = Sandbox::with(&[
+        "- [ ] Pay rent 📅 2025-09-30",
+        "- [ ] New year 📅 2025-12-31",
+    ]);
+    sandbox.run(&["postpone", "1", "+1d"]);
+    sandbox.run(&["postpone", "2", "+1d"]);
+    assert_eq!(
+        sandbox.lines(),
+        [
+            "- [ ] Pay rent 📅 2025-10-01",
+            "- [ ] New year 📅 2026-01-01"
+        ]
+    );
+}
+
+#[test]
+fn overflowing_offsets_are_refused() {
+    let sandbox = Sandbox::with(&["- [ ] Someday 📅 2025-09-20"]);
+    sandbox.fail(&["postpone", "1", "+99999999999w"], 1);
+    assert_eq!(sandbox.lines(), ["- [ ] Someday 📅 2025-09-20"]);
+}
+
+#[test]
+fn overdue_tasks_number_first_and_listings_agree_with_commands() {
+    let sandbox = Sandbox::with(&["- [ ] Later 📅 2025-10-01", "- [ ] Overdue 📅 2025-09-01"]);
+    assert!(sandbox.run(&["pending"]).contains("1 - [ ] Overdue"));
+    sandbox.run(&["done", "1"]);
+    assert!(sandbox.lines()[1].starts_with("- [x] Overdue"));
+}
+
+#[test]
+fn malformed_lines_are_reported_and_left_alone() {
+    let file = [
+        "# Notes",
+        "-[ ] Missing space",
+        "- [ ] Impossible 📅 2025-13-01",
+        "plain text",
+        "- [ ] Real task 📅 2025-09-20",
+    ];
+    let sandbox = Sandbox::with(&file);
+    let lint = sandbox.run(&["lint"]);
+    assert!(lint.contains("Line 2: not read as a task"), "{}", lint);
+    assert!(lint.contains("Line 3: '📅' is not followed by a YYYY-MM-DD date"));
+
+    // Only well-formed tasks are numbered, and completing one rewrites
+    // nothing else
+    let pending = sandbox.run(&["pending"]);
+    assert!(!pending.contains("Missing space"));
+    sandbox.run(&["done", "Real task"]);
+    let lines = sandbox.lines();
+    assert_eq!(lines[..4], file[..4]);
+    assert_eq!(lines[4], "- [x] Real task 📅 2025-09-20 ✅ 2025-09-15");
+}
+
+#[test]
+fn emoji_fields_are_read_with_or_without_variation_selectors() {
+    let sandbox = Sandbox::with(&[
+        "- [ ] Plain ⏫ 🔁 every week 🛫 2025-09-01 📅 2025-09-16",
+        "- [ ] Selector 📅\u{fe0f} 2025-09-17",
+    ]);
+    let show = sandbox.run(&["show", "1"]);
+    assert!(show.contains("Priority:   high"), "{}", show);
+    assert!(show.contains("Recurs:     every week"));
+    assert!(show.contains("Start:      Mon 2025-09-01"));
+    assert!(show.contains("Due:        Tue 2025-09-16"));
+    let show = sandbox.run(&["show", "2"]);
+    assert!(show.contains("Due:        Wed 2025-09-17"), "{}", show);
+
+    // A rewrite drops the selector
+    sandbox.run(&["reschedule", "2", "2025-09-18"]);
+    assert_eq!(sandbox.lines()[1], "- [ ] Selector 📅 2025-09-18");
+}
+
+#[test]
+fn notes_move_with_their_task() {
+    let sandbox = Sandbox::with(&["- [ ] Ship it 📅 2025-09-20", "    see the PR"]);
+    sandbox.run(&["note", "1", "tagged v2"]);
+    sandbox.run(&["done", "1"]);
+    assert_eq!(
+        sandbox.lines(),
+        [
+            "- [x] Ship it 📅 2025-09-20 ✅ 2025-09-15",
+            "    see the PR",
+            "    tagged v2",
+        ]
+    );
+}
+
+#[test]
+fn a_file_without_a_final_newline_or_with_crlf_keeps_its_style() {
+    let sandbox = Sandbox::new();
+    fs::write(
+        sandbox.file(),
+        "- [ ] One 📅 2025-09-20\r\n- [ ] Two 📅 2025-09-21",
+    )
+    .unwrap();
+    sandbox.run(&["done", "2"]);
+    assert_eq!(
+        fs::read_to_string(sandbox.file()).unwrap(),
+        "- [ ] One 📅 2025-09-20\r\n- [x] Two 📅 2025-09-21 ✅ 2025-09-15"
+    );
+}
+
+#[test]
+fn waiting_tasks_wake_in_listings_and_are_written_by_the_next_change() {
+    let sandbox = Sandbox::with(&[
+        "- [>] Quote from Sam [wake:: 2025-09-15]",
+        "- [ ] Other 📅 2025-09-20",
+    ]);
+    assert!(sandbox.run(&["pending"]).contains("Quote from Sam"));
+    // Listing didn't touch the file
+    assert_eq!(
+        sandbox.lines()[0],
+        "- [>] Quote from Sam [wake:: 2025-09-15]"
+    );
+
+    let output = sandbox.task(&["done", "Other"]).assert().success();
+    let stderr = String::from_utf8(output.get_output().stderr.clone()).unwrap();
+    assert!(
+        stderr.contains("⏰ Back from waiting: Quote from Sam"),
+        "{}",
+        stderr
+    );
+    assert_eq!(sandbox.lines()[0], "- [ ] Quote from Sam");
+}
+
+/// Every file under `dir` whose contents include `text`.
+fn files_containing(dir: &Path, text: &str, found: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files_containing(&path, text, found);
+        } else if String::from_utf8_lossy(&fs::read(&path).unwrap()).contains(text) {
+            found.push(path);
+        }
+    }
+}
+
+#[test]
+fn nothing_is_kept_in_plain_text_when_encryption_is_on() {
+    if process::Command::new("gpg")
+        .arg("--version")
+        .output()
+        .is_err()
+    {
+        eprintln!("gpg isn't installed; skipping");
+        return;
+    }
+    let mut sandbox = Sandbox::new();
+    // A throwaway keyring outside the directory searched below
+    let keys = TempDir::new().unwrap();
+    let generated = process::Command::new("gpg")
+        .env("GNUPGHOME", keys.path())
+        .args(["--batch", "--passphrase", "", "--quick-gen-key"])
+        .args([
+            "Task Test <test@example.invalid>",
+            "default",
+            "default",
+            "never",
+        ])
+        .output()
+        .unwrap();
+    assert!(generated.status.success(), "{:?}", generated);
+    sandbox.env.push(("GNUPGHOME", keys.path().to_path_buf()));
+    let config = sandbox.dir.path().join("home/config/task/config.toml");
+    fs::create_dir_all(config.parent().unwrap()).unwrap();
+    fs::write(
+        &config,
+        "[encryption]\ntool = \"gpg\"\nrecipients = [\"test@example.invalid\"]\n",
+    )
+    .unwrap();
+
+    sandbox.run(&["add", "Secret alpha"]);
+    sandbox.run(&["add", "Secret beta"]);
+    sandbox.run(&["done", "Secret alpha"]);
+    sandbox.run(&["archive"]);
+    sandbox.run(&["start", "1"]);
+    sandbox.run(&["stop"]);
+    sandbox.run(&["report", "weekly"]);
+    sandbox.run(&["cancel", "1"]);
+
+    let mut found = Vec::new();
+    files_containing(sandbox.dir.path(), "Secret", &mut found);
+    assert!(found.is_empty(), "plain text in {:?}", found);
+
+    // And it all reads back
+    assert!(sandbox.run(&["cancel"]).contains("Secret beta"));
+    let _ = process::Command::new("gpgconf")
+        .env("GNUPGHOME", keys.path())
+        .args(["--kill", "gpg-agent"])
+        .status();
+}